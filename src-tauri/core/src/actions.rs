@@ -0,0 +1,207 @@
+// Custom file actions backed by external programs declared in settings,
+// e.g. "Convert HEIC to JPEG" running heif-convert. Each run gets a fresh
+// scratch directory as its working directory and a minimal environment.
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::paths::{relative_key, resolve_within};
+use crate::settings;
+use crate::store::Store;
+use crate::workspace;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct FileAction {
+    pub id: String,
+    pub label: String,
+    pub program: String,
+    // {path}, {name}, {stem} and {out} are substituted; {out} is the scratch dir
+    pub args: Vec<String>,
+    // lowercase extensions without the dot; empty matches every file
+    pub extensions: Vec<String>,
+    // move files the program wrote into {out} next to the source file
+    pub keep_outputs: bool,
+    pub timeout_secs: Option<u64>,
+}
+
+impl Default for FileAction {
+    fn default() -> Self {
+        FileAction {
+            id: String::new(),
+            label: String::new(),
+            program: String::new(),
+            args: vec!["{path}".into()],
+            extensions: Vec::new(),
+            keep_outputs: false,
+            timeout_secs: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ActionSummary {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(Serialize)]
+pub struct ActionOutput {
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    // relative paths of files moved next to the source
+    pub outputs: Vec<String>,
+}
+
+pub fn validate(action: &FileAction) -> Result<(), String> {
+    if action.id.trim().is_empty() || action.label.trim().is_empty() {
+        return Err("File actions need an id and a label".into());
+    }
+    if action.program.trim().is_empty() {
+        return Err(format!("File action {} has no program", action.id));
+    }
+    Ok(())
+}
+
+fn applies_to(action: &FileAction, path: &Path) -> bool {
+    if action.extensions.is_empty() {
+        return true;
+    }
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    action.extensions.iter().any(|e| e.to_lowercase() == ext)
+}
+
+/// Actions from settings that apply to the given file.
+pub fn list_file_actions(store: &Store, root: &str, relative_path: &str) -> Result<Vec<ActionSummary>, String> {
+    let path = resolve_within(Path::new(root), relative_path)?;
+    Ok(settings::load(store)?
+        .actions
+        .into_iter()
+        .filter(|a| path.is_file() && applies_to(a, &path))
+        .map(|a| ActionSummary {
+            id: a.id,
+            label: a.label,
+        })
+        .collect())
+}
+
+fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut p) = pipe {
+            let _ = p.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
+/// Run a configured action on one file and return what it printed.
+pub fn run_file_action(store: &Store, root: &str, relative_path: &str, action_id: &str) -> Result<ActionOutput, String> {
+    let source = resolve_within(Path::new(root), relative_path)?;
+    if !source.is_file() {
+        return Err("Target is not a file".into());
+    }
+    let action = settings::load(store)?
+        .actions
+        .into_iter()
+        .find(|a| a.id == action_id)
+        .ok_or_else(|| format!("Unknown file action: {action_id}"))?;
+    if !applies_to(&action, &source) {
+        return Err(format!("{} does not apply to this file", action.label));
+    }
+
+    let out_dir = workspace::scratch("action")?;
+    let name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let stem = source.file_stem().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let args: Vec<String> = action
+        .args
+        .iter()
+        .map(|a| {
+            a.replace("{path}", &source.to_string_lossy())
+                .replace("{name}", &name)
+                .replace("{stem}", &stem)
+                .replace("{out}", &out_dir.to_string_lossy())
+        })
+        .collect();
+
+    let mut cmd = Command::new(&action.program);
+    cmd.args(&args)
+        .current_dir(&out_dir)
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Keep just enough environment for programs to be found and run
+    for key in ["PATH", "SYSTEMROOT", "TEMP", "TMP", "LANG"] {
+        if let Ok(v) = env::var(key) {
+            cmd.env(key, v);
+        }
+    }
+    cmd.env("HOME", &out_dir);
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to run {}: {e}", action.program))?;
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let deadline = Instant::now() + Duration::from_secs(action.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let mut timed_out = false;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                timed_out = true;
+                break None;
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(format!("Failed to wait for {}: {e}", action.program)),
+        }
+    };
+
+    let mut outputs = Vec::new();
+    let succeeded = status.map(|s| s.success()).unwrap_or(false);
+    if action.keep_outputs && succeeded {
+        let dest_dir = source.parent().ok_or("File has no parent directory")?;
+        let root_canon = Path::new(root)
+            .canonicalize()
+            .map_err(|e| format!("Invalid root: {e}"))?;
+        if let Ok(read_dir) = fs::read_dir(&out_dir) {
+            for e in read_dir.flatten() {
+                let p = e.path();
+                if !p.is_file() {
+                    continue;
+                }
+                let dest = dest_dir.join(e.file_name());
+                if dest.exists() {
+                    continue;
+                }
+                // rename fails across devices, fall back to copy
+                if fs::rename(&p, &dest).is_err() && fs::copy(&p, &dest).is_err() {
+                    continue;
+                }
+                outputs.push(relative_key(&root_canon, &dest));
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&out_dir);
+
+    Ok(ActionOutput {
+        exit_code: status.and_then(|s| s.code()),
+        timed_out,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+        outputs,
+    })
+}
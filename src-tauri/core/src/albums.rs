@@ -0,0 +1,230 @@
+// Suggestions for organizing a flat dump of photos and videos into album
+// folders: files shot close together in time end up in the same album,
+// named after its dates and whatever the file names have in common.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::groups::group_key;
+use crate::index::mtime_ms;
+use crate::paths::{relative_key, resolve_within};
+
+// Name prefixes cameras and phones use, which say nothing about the content
+const CAMERA_PREFIXES: &[&str] = &[
+    "img", "dsc", "dscf", "dscn", "mvi", "vid", "pxl", "gopr", "dji", "p", "_mg", "mov",
+];
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct AlbumOptions {
+    // a pause this long between two shots starts a new album
+    pub gap_minutes: u64,
+    // smaller clusters are left where they are
+    pub min_files: usize,
+}
+
+impl Default for AlbumOptions {
+    fn default() -> Self {
+        AlbumOptions {
+            gap_minutes: 6 * 60,
+            min_files: 5,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AlbumSuggestion {
+    // proposed folder, relative to the root
+    pub folder: String,
+    pub first_ms: i64,
+    pub last_ms: i64,
+    pub files: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ProposedMove {
+    pub from: String,
+    pub to_dir: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct AlbumPlan {
+    pub albums: Vec<AlbumSuggestion>,
+    pub moves: Vec<ProposedMove>,
+    // files that didn't fall into a big enough cluster
+    pub unassigned: Vec<String>,
+}
+
+/// Days since 1970-01-01 of a civil date (proleptic Gregorian).
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// `YYYY-MM-DD` of a day count from `days_from_civil`.
+pub(crate) fn civil_from_days(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// When a photo was taken according to its EXIF data, as camera-local time
+/// in ms. Clustering only looks at differences, so the time zone is moot.
+fn exif_taken_ms(path: &Path) -> Option<i64> {
+    let file = File::open(path).ok()?;
+    let data = exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+    let field = data
+        .get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)
+        .or_else(|| data.get_field(exif::Tag::DateTime, exif::In::PRIMARY))?;
+    let exif::Value::Ascii(ref parts) = field.value else {
+        return None;
+    };
+    let dt = exif::DateTime::from_ascii(parts.first()?).ok()?;
+    let days = days_from_civil(i64::from(dt.year), i64::from(dt.month), i64::from(dt.day));
+    let secs = i64::from(dt.hour) * 3600 + i64::from(dt.minute) * 60 + i64::from(dt.second);
+    Some(days * DAY_MS + secs * 1000)
+}
+
+/// The part of a file name that describes it, e.g. `Wedding` for
+/// `Wedding_0042.jpg`, with camera prefixes and counters dropped.
+fn descriptive_stem(name: &str) -> String {
+    let stem = name.split('.').next().unwrap_or("");
+    let words: Vec<&str> = stem
+        .split(|c: char| c == '_' || c == '-' || c == ' ')
+        .map(|w| w.trim_end_matches(|c: char| c.is_ascii_digit()))
+        .filter(|w| !w.is_empty() && !CAMERA_PREFIXES.contains(&w.to_lowercase().as_str()))
+        .collect();
+    words.join(" ")
+}
+
+/// Longest common descriptive stem of the files, if it says anything.
+fn common_label(names: &[String]) -> Option<String> {
+    let mut stems = names.iter().map(|n| descriptive_stem(n));
+    let first = stems.next()?;
+    let mut common: &str = &first;
+    let rest: Vec<String> = stems.collect();
+    for stem in &rest {
+        let len = common
+            .char_indices()
+            .zip(stem.chars())
+            .take_while(|((_, a), b)| a.eq_ignore_ascii_case(b))
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+        common = &common[..len];
+    }
+    let common = common.trim();
+    (common.chars().count() >= 3).then(|| common.to_string())
+}
+
+fn folder_name(first_ms: i64, last_ms: i64, label: Option<String>) -> String {
+    let (first, last) = (
+        civil_from_days(first_ms.div_euclid(DAY_MS)),
+        civil_from_days(last_ms.div_euclid(DAY_MS)),
+    );
+    let dates = if first == last {
+        first
+    } else {
+        format!("{first} - {last}")
+    };
+    match label {
+        Some(label) => format!("{dates} {label}"),
+        None => dates,
+    }
+}
+
+/// Propose albums for the files directly inside `relative_dir`. Files are
+/// dated by EXIF capture time, falling back to their modification time,
+/// and split wherever the pause between two shots exceeds the gap. Sidecar
+/// groups stay together. Nothing is moved; the plan is for review.
+pub fn suggest_albums(root: &str, relative_dir: &str, options: AlbumOptions) -> Result<AlbumPlan, String> {
+    let root_canon = Path::new(root)
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let dir = resolve_within(&root_canon, relative_dir)?;
+    if !dir.is_dir() {
+        return Err("Target is not a directory".into());
+    }
+    let read_dir = fs::read_dir(&dir).map_err(|e| format!("Failed to read dir {}: {e}", dir.display()))?;
+    // Sidecar groups (or single files) with their earliest time
+    let mut units: BTreeMap<String, (i64, Vec<String>)> = BTreeMap::new();
+    for entry in read_dir.flatten() {
+        let p = entry.path();
+        let Ok(meta) = entry.metadata() else { continue };
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !meta.is_file() || name.starts_with('.') {
+            continue;
+        }
+        let taken = exif_taken_ms(&p).unwrap_or_else(|| mtime_ms(&meta));
+        let key = group_key(&name).unwrap_or_else(|| name.clone());
+        let unit = units.entry(key).or_insert((i64::MAX, Vec::new()));
+        unit.0 = unit.0.min(taken);
+        unit.1.push(name);
+    }
+    let mut units: Vec<(i64, Vec<String>)> = units.into_values().collect();
+    units.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+    let gap_ms = options.gap_minutes as i64 * 60 * 1000;
+    let mut clusters: Vec<Vec<(i64, Vec<String>)>> = Vec::new();
+    for unit in units {
+        match clusters.last_mut() {
+            Some(cluster) if unit.0 - cluster.last().map_or(unit.0, |u| u.0) <= gap_ms => cluster.push(unit),
+            _ => clusters.push(vec![unit]),
+        }
+    }
+
+    let base = relative_key(&root_canon, &dir);
+    let mut plan = AlbumPlan::default();
+    for cluster in clusters {
+        let names: Vec<String> = cluster.iter().flat_map(|(_, names)| names.iter().cloned()).collect();
+        let rel = |name: &String| {
+            if base.is_empty() {
+                name.clone()
+            } else {
+                format!("{base}/{name}")
+            }
+        };
+        if names.len() < options.min_files.max(1) {
+            plan.unassigned.extend(names.iter().map(rel));
+            continue;
+        }
+        let (first_ms, last_ms) = (cluster[0].0, cluster[cluster.len() - 1].0);
+        let mut folder = rel(&folder_name(first_ms, last_ms, common_label(&names)));
+        // Two albums of the same day(s) need distinct folders
+        let taken = plan
+            .albums
+            .iter()
+            .filter(|a| a.folder == folder || a.folder.starts_with(&format!("{folder} (")))
+            .count();
+        if taken > 0 {
+            folder = format!("{folder} ({})", taken + 1);
+        }
+        let files: Vec<String> = names.iter().map(rel).collect();
+        plan.moves.extend(files.iter().map(|from| ProposedMove {
+            from: from.clone(),
+            to_dir: folder.clone(),
+        }));
+        plan.albums.push(AlbumSuggestion {
+            folder,
+            first_ms,
+            last_ms,
+            files,
+        });
+    }
+    Ok(plan)
+}
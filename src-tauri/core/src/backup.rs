@@ -0,0 +1,322 @@
+// Export and import of a profile's whole metadata store as one zip archive,
+// so catalogs, accepted duplicates and settings can move between machines,
+// and two-way merging against such an archive kept on a shared drive.
+// Settings that run programs or guard this machine's drives are never taken
+// from an archive.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+
+use crate::encryption;
+use crate::index::{now_ms, CHECKPOINT_DIR};
+use crate::profiles::{ACTIVE_DOC, PROFILES_DIR};
+use crate::settings::{self, Settings};
+use crate::store::Store;
+
+const MANIFEST: &str = "lfm-export.json";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct ExportManifest {
+    pub version: u32,
+    pub exported_at: i64,
+    // document name -> last modification time in ms
+    pub documents: Vec<(String, i64)>,
+}
+
+#[derive(Serialize)]
+pub struct ExportSummary {
+    pub documents: usize,
+    pub bytes: u64,
+    // archives are never encrypted, even when the profile is
+    pub plaintext: bool,
+}
+
+#[derive(Serialize, Default)]
+pub struct MergeReport {
+    // taken from the archive because it was newer or missing locally
+    pub pulled: Vec<String>,
+    // written to the archive because the local copy was newer or only local
+    pub pushed: Vec<String>,
+    pub unchanged: usize,
+    pub skipped: Vec<String>,
+    // machine-local settings, which differ in the archive but were kept as
+    // they are here, e.g. "settings.hooks"
+    pub kept_local: Vec<String>,
+    // the archive was written, unencrypted, from an encrypted profile
+    pub plaintext: bool,
+}
+
+#[derive(Serialize, Default)]
+pub struct ImportSummary {
+    pub imported: Vec<String>,
+    pub skipped: Vec<String>,
+    // see `MergeReport::kept_local`
+    pub kept_local: Vec<String>,
+}
+
+/// A document read back from an archive, already checked to be valid JSON.
+pub struct ArchivedDocument {
+    pub name: String,
+    pub modified_ms: i64,
+    pub data: Vec<u8>,
+}
+
+// The default profile's directory also holds the other profiles and the
+// active-profile marker, neither of which belongs to its metadata, nor do
+// the checkpoints of scans in progress on this machine.
+fn profile_documents(store: &Store) -> Result<Vec<(String, i64)>, String> {
+    Ok(store
+        .documents(&[PROFILES_DIR, CHECKPOINT_DIR])?
+        .into_iter()
+        .filter(|(name, _)| name != ACTIVE_DOC)
+        .collect())
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != ACTIVE_DOC
+        && name
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != ".." && !part.contains('\\'))
+}
+
+/// Bytes of a document as it goes into an archive: settings without their
+/// machine-local part.
+fn read_shared(store: &Store, name: &str) -> Result<Option<Vec<u8>>, String> {
+    let Some(data) = store.read_raw(name)? else {
+        return Ok(None);
+    };
+    if name != settings::SETTINGS_DOC {
+        return Ok(Some(data));
+    }
+    let mut shared: Settings = serde_json::from_slice(&data).map_err(|e| format!("Invalid settings: {e}"))?;
+    take_local(&mut shared, Settings::default());
+    serde_json::to_vec(&shared).map(Some).map_err(|e| e.to_string())
+}
+
+/// Write every document of `store` into a zip archive at `destination`. The
+/// archive is plain JSON in a zip even when the store is encrypted.
+pub fn export_metadata(store: &Store, destination: &Path) -> Result<ExportSummary, String> {
+    let documents = profile_documents(store)?;
+    let mut docs = Vec::with_capacity(documents.len());
+    for (name, modified_ms) in documents {
+        if let Some(data) = read_shared(store, &name)? {
+            docs.push(ArchivedDocument { name, modified_ms, data });
+        }
+    }
+    let mut summary = write_archive(destination, &docs)?;
+    summary.plaintext = encryption::is_encrypted(store.dir());
+    Ok(summary)
+}
+
+pub fn write_archive(destination: &Path, docs: &[ArchivedDocument]) -> Result<ExportSummary, String> {
+    let manifest = ExportManifest {
+        version: FORMAT_VERSION,
+        exported_at: now_ms(),
+        documents: docs.iter().map(|d| (d.name.clone(), d.modified_ms)).collect(),
+    };
+    let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Failed to serialize manifest: {e}"))?;
+
+    // Build next to the destination and rename, so an existing archive is
+    // only replaced by a complete one
+    let tmp = destination.with_extension("partial");
+    let file = File::create(&tmp).map_err(|e| format!("Failed to create {}: {e}", tmp.display()))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut bytes = 0;
+    let mut add = |name: &str, data: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options)
+            .map_err(|e| format!("Failed to write archive: {e}"))?;
+        zip.write_all(data).map_err(|e| format!("Failed to write archive: {e}"))
+    };
+    add(MANIFEST, &manifest)?;
+    for doc in docs {
+        add(&format!("{}.json", doc.name), &doc.data)?;
+        bytes += doc.data.len() as u64;
+    }
+    zip.finish().map_err(|e| format!("Failed to write archive: {e}"))?;
+    fs::rename(&tmp, destination).map_err(|e| format!("Failed to write archive: {e}"))?;
+    Ok(ExportSummary {
+        documents: docs.len(),
+        bytes,
+        plaintext: false,
+    })
+}
+
+/// Every document in an archive written by `export_metadata`. Entries that
+/// aren't valid JSON or whose names would escape the store are skipped and
+/// named in the second list.
+pub fn read_archive(archive: &Path) -> Result<(Vec<ArchivedDocument>, Vec<String>), String> {
+    let file = File::open(archive).map_err(|e| format!("Failed to open {}: {e}", archive.display()))?;
+    let mut zip = zip::ZipArchive::new(BufReader::new(file)).map_err(|e| format!("Not an export archive: {e}"))?;
+
+    let mut read = |name: &str| -> Result<Vec<u8>, String> {
+        let mut entry = zip.by_name(name).map_err(|e| format!("Failed to read {name}: {e}"))?;
+        let mut data = Vec::new();
+        entry
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read {name}: {e}"))?;
+        Ok(data)
+    };
+    let manifest: ExportManifest =
+        serde_json::from_slice(&read(MANIFEST)?).map_err(|e| format!("Not an export archive: {e}"))?;
+    if manifest.version > FORMAT_VERSION {
+        return Err(format!(
+            "Archive format {} is newer than this version supports",
+            manifest.version
+        ));
+    }
+
+    let mut docs = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, modified_ms) in manifest.documents {
+        if !valid_name(&name) {
+            skipped.push(name);
+            continue;
+        }
+        match read(&format!("{name}.json")) {
+            Ok(data) if serde_json::from_slice::<Value>(&data).is_ok() => {
+                docs.push(ArchivedDocument { name, modified_ms, data })
+            }
+            _ => skipped.push(name),
+        }
+    }
+    Ok((docs, skipped))
+}
+
+/// Copy every document of an exported archive into `store`, replacing
+/// documents of the same name. Documents only present locally are kept.
+pub fn import_metadata(store: &Store, archive: &Path) -> Result<ImportSummary, String> {
+    let (docs, skipped) = read_archive(archive)?;
+    let mut summary = ImportSummary {
+        skipped,
+        ..Default::default()
+    };
+    for doc in docs {
+        summary.kept_local.extend(apply(store, &doc)?);
+        summary.imported.push(doc.name);
+    }
+    Ok(summary)
+}
+
+fn same<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Copy the machine-local settings of `from` into `into`, returning the
+/// names of those that differed. They run programs (hooks, file actions,
+/// rules that open executables) or protect this machine's drives (elevation,
+/// deletion rules, the trash, folder limits), and an archive may sit on a
+/// drive anyone can write to.
+fn take_local(into: &mut Settings, from: Settings) -> Vec<&'static str> {
+    let mut differing = Vec::new();
+    macro_rules! take {
+        ($($field:ident),*) => {$(
+            if !same(&into.$field, &from.$field) {
+                differing.push(concat!("settings.", stringify!($field)));
+            }
+            into.$field = from.$field;
+        )*};
+    }
+    take!(
+        hooks,
+        actions,
+        open_rules,
+        allow_elevation,
+        delete_guards,
+        trash_retention_days,
+        folder_quotas
+    );
+    differing
+}
+
+/// Replace the machine-local settings in `incoming` with the ones of
+/// `store`. Returns those the archive set to something else; archives
+/// written by this version leave them at their defaults.
+fn keep_local(store: &Store, incoming: &mut Settings) -> Result<Vec<String>, String> {
+    let set_in_archive = take_local(&mut incoming.clone(), Settings::default());
+    let differing = take_local(incoming, settings::load(store)?);
+    Ok(differing
+        .into_iter()
+        .filter(|name| set_in_archive.contains(name))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Write an archived document to `store`, returning the settings kept local.
+fn apply(store: &Store, doc: &ArchivedDocument) -> Result<Vec<String>, String> {
+    if doc.name == settings::SETTINGS_DOC {
+        // Goes through the same validation as settings edited in the app
+        let mut parsed: Settings = serde_json::from_slice(&doc.data).map_err(|e| format!("Invalid settings: {e}"))?;
+        let kept = keep_local(store, &mut parsed)?;
+        settings::save(store, &parsed)?;
+        Ok(kept)
+    } else {
+        store.write_raw(&doc.name, &doc.data)?;
+        Ok(Vec::new())
+    }
+}
+
+/// Two-way merge of `store` with the archive at `archive`, which is created
+/// if missing. Each document is one unit: whichever side modified it last
+/// wins, and the result is written to both the store and the archive.
+/// Pulled documents keep their original modification time, so the next
+/// merge on either machine still sees which edit is newer.
+pub fn merge_metadata(store: &Store, archive: &Path) -> Result<MergeReport, String> {
+    let mut report = MergeReport::default();
+    let mut remote: BTreeMap<String, ArchivedDocument> = BTreeMap::new();
+    if archive.exists() {
+        let (docs, skipped) = read_archive(archive)?;
+        report.skipped = skipped;
+        remote.extend(docs.into_iter().map(|d| (d.name.clone(), d)));
+    }
+
+    let mut merged = Vec::new();
+    for (name, modified_ms) in profile_documents(store)? {
+        // Machine-local settings never reach the archive, so they can't make
+        // the settings of two machines look different on every merge
+        let Some(data) = read_shared(store, &name)? else { continue };
+        let local = ArchivedDocument { name, modified_ms, data };
+        match remote.remove(&local.name) {
+            Some(theirs) if theirs.data == local.data => {
+                report.unchanged += 1;
+                merged.push(ArchivedDocument {
+                    modified_ms: theirs.modified_ms.max(local.modified_ms),
+                    ..local
+                });
+            }
+            Some(theirs) if theirs.modified_ms > local.modified_ms => {
+                report.kept_local.extend(pull(store, &theirs)?);
+                report.pulled.push(theirs.name.clone());
+                merged.push(theirs);
+            }
+            _ => {
+                report.pushed.push(local.name.clone());
+                merged.push(local);
+            }
+        }
+    }
+    for theirs in remote.into_values() {
+        report.kept_local.extend(pull(store, &theirs)?);
+        report.pulled.push(theirs.name.clone());
+        merged.push(theirs);
+    }
+
+    if !report.pushed.is_empty() || !archive.exists() {
+        merged.sort_by(|a, b| a.name.cmp(&b.name));
+        write_archive(archive, &merged)?;
+        report.plaintext = encryption::is_encrypted(store.dir());
+    }
+    Ok(report)
+}
+
+fn pull(store: &Store, doc: &ArchivedDocument) -> Result<Vec<String>, String> {
+    let kept = apply(store, doc)?;
+    store.set_modified(&doc.name, doc.modified_ms)?;
+    Ok(kept)
+}
@@ -0,0 +1,161 @@
+// Quick drive benchmark on a temporary file: sequential write and read
+// throughput plus random 4 KiB read and write IOPS.
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::index::now_ms;
+use crate::Progress;
+
+const BLOCK: usize = 1024 * 1024;
+const SMALL_BLOCK: usize = 4096;
+const DEFAULT_SIZE_MB: u64 = 256;
+// Each random phase runs for this long
+const RANDOM_PHASE: Duration = Duration::from_secs(3);
+const PHASES: u64 = 4;
+
+#[derive(Serialize)]
+pub struct BenchmarkReport {
+    pub path: String,
+    pub file_size: u64,
+    pub seq_write_mb_s: f64,
+    pub seq_read_mb_s: f64,
+    pub random_read_iops: f64,
+    pub random_write_iops: f64,
+}
+
+// Removes the test file however the benchmark ends
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// xorshift64*, plenty for picking offsets
+struct Offsets(u64);
+
+impl Offsets {
+    fn next(&mut self, blocks: u64) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) % blocks) * SMALL_BLOCK as u64
+    }
+}
+
+#[cfg(target_os = "linux")]
+extern "C" {
+    fn posix_fadvise(fd: i32, offset: i64, len: i64, advice: i32) -> i32;
+}
+
+#[cfg(target_os = "macos")]
+extern "C" {
+    fn fcntl(fd: i32, cmd: i32, ...) -> i32;
+}
+
+// Keep the reads away from the page cache so they hit the device. On Linux
+// the cached pages of the (synced) file are dropped, so call it again before
+// every read pass; on macOS caching is turned off for the handle. Elsewhere
+// the read figures can be flattered by the cache.
+#[cfg(target_os = "linux")]
+fn bypass_cache(file: &File) {
+    use std::os::fd::AsRawFd;
+    const POSIX_FADV_DONTNEED: i32 = 4;
+    // SAFETY: the descriptor is valid for the lifetime of `file`
+    unsafe { posix_fadvise(file.as_raw_fd(), 0, 0, POSIX_FADV_DONTNEED) };
+}
+
+#[cfg(target_os = "macos")]
+fn bypass_cache(file: &File) {
+    use std::os::fd::AsRawFd;
+    const F_NOCACHE: i32 = 48;
+    // SAFETY: the descriptor is valid for the lifetime of `file`
+    unsafe { fcntl(file.as_raw_fd(), F_NOCACHE, 1) };
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn bypass_cache(_file: &File) {}
+
+fn mb_s(bytes: u64, elapsed: Duration) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64().max(1e-9)
+}
+
+/// Benchmark the drive holding `path` (a folder) with a test file of
+/// `size_mb` MiB, 256 by default. Reads bypass the page cache on Linux and
+/// macOS; elsewhere the read figures can be flattered by it when the test
+/// file is small compared to free memory.
+pub fn benchmark_mount(path: &str, size_mb: Option<u64>, on_progress: Progress) -> Result<BenchmarkReport, String> {
+    let dir = Path::new(path);
+    if !dir.is_dir() {
+        return Err(format!("Not a folder: {path}"));
+    }
+    let size_mb = size_mb.unwrap_or(DEFAULT_SIZE_MB).clamp(16, 16 * 1024);
+    let file_size = size_mb * 1024 * 1024;
+    let temp = TempFile(dir.join(format!(".lfm-bench-{}.tmp", std::process::id())));
+    let io_err = |e: std::io::Error| format!("Benchmark failed: {e}");
+
+    // Sequential write, including the time to get it onto the device
+    let mut block = vec![0u8; BLOCK];
+    let mut offsets = Offsets(now_ms() as u64 | 1);
+    for chunk in block.chunks_mut(8) {
+        chunk.copy_from_slice(&offsets.0.to_le_bytes()[..chunk.len()]);
+        offsets.next(1);
+    }
+    let started = Instant::now();
+    let mut file = File::create(&temp.0).map_err(io_err)?;
+    bypass_cache(&file);
+    for _ in 0..size_mb {
+        file.write_all(&block).map_err(io_err)?;
+    }
+    file.sync_all().map_err(io_err)?;
+    let seq_write_mb_s = mb_s(file_size, started.elapsed());
+    bypass_cache(&file);
+    drop(file);
+    on_progress(1, PHASES);
+
+    let mut file = File::open(&temp.0).map_err(io_err)?;
+    bypass_cache(&file);
+    let started = Instant::now();
+    while file.read(&mut block).map_err(io_err)? > 0 {}
+    let seq_read_mb_s = mb_s(file_size, started.elapsed());
+    on_progress(2, PHASES);
+
+    let blocks = file_size / SMALL_BLOCK as u64;
+    let mut small = vec![0u8; SMALL_BLOCK];
+    bypass_cache(&file);
+    let started = Instant::now();
+    let mut reads = 0u64;
+    while started.elapsed() < RANDOM_PHASE {
+        file.seek(SeekFrom::Start(offsets.next(blocks))).map_err(io_err)?;
+        file.read_exact(&mut small).map_err(io_err)?;
+        reads += 1;
+    }
+    let random_read_iops = reads as f64 / started.elapsed().as_secs_f64();
+    on_progress(3, PHASES);
+
+    let mut file = OpenOptions::new().write(true).open(&temp.0).map_err(io_err)?;
+    bypass_cache(&file);
+    let started = Instant::now();
+    let mut writes = 0u64;
+    while started.elapsed() < RANDOM_PHASE {
+        file.seek(SeekFrom::Start(offsets.next(blocks))).map_err(io_err)?;
+        file.write_all(&small).map_err(io_err)?;
+        writes += 1;
+    }
+    file.sync_all().map_err(io_err)?;
+    let random_write_iops = writes as f64 / started.elapsed().as_secs_f64();
+    on_progress(4, PHASES);
+
+    Ok(BenchmarkReport {
+        path: dir.display().to_string(),
+        file_size,
+        seq_write_mb_s,
+        seq_read_mb_s,
+        random_read_iops,
+        random_write_iops,
+    })
+}
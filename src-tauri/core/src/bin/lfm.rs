@@ -0,0 +1,368 @@
+// Headless front end over lfm-core, for scripting the same operations on
+// machines without the GUI. Shares the app's data directory, so indexes
+// built here show up as catalogs in the app and vice versa.
+use lfm_core::backup;
+use lfm_core::dedup;
+use lfm_core::encryption;
+use lfm_core::hashing::HashCache;
+use lfm_core::hooks;
+use lfm_core::index;
+use lfm_core::power;
+use lfm_core::profiles;
+use lfm_core::rpc::{self, Server};
+use lfm_core::settings;
+use lfm_core::store::{self, Store};
+use lfm_core::sync::{self, SyncOptions};
+use serde::Serialize;
+use serde_json::json;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+const PASSPHRASE_VAR: &str = "LFM_PASSPHRASE";
+// Token `serve --listen` requires; a random one is made when unset
+const TOKEN_VAR: &str = "LFM_RPC_TOKEN";
+// Socket `serve` listens on by default, in the app data directory
+const SOCKET_NAME: &str = "lfm.sock";
+
+const USAGE: &str = "usage: lfm [--json] [--profile NAME] <command> [args]
+
+commands:
+  scan <root>                  index a root, hashing new and changed files
+  verify <root>                re-hash indexed files and report corruption
+  dedup [--min-size BYTES] [--catalog ID]... <root>...
+                               list identical files across roots and catalogs
+  catalogs                     list stored indexes
+  sync [--delete] [--dry-run] <source> <destination>
+                               mirror a folder tree onto another
+  export <file>                write this profile's metadata to an archive
+  import <file>                load metadata from an archive
+  merge <file>                 two-way merge with an archive, newest copy wins
+  serve [--socket PATH | --stdio | --listen ADDR]
+                               run headless, answering JSON-RPC 2.0 requests
+                               (one per line) on a socket only this user can
+                               open, lfm.sock in the app data directory by
+                               default. --listen serves a loopback TCP port;
+                               clients send the session token (from
+                               LFM_RPC_TOKEN, or printed at startup) as the
+                               first line";
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let json = take_flag(&mut args, "--json");
+    let profile = match take_values(&mut args, "--profile") {
+        Ok(mut v) => v.pop(),
+        Err(e) => {
+            eprintln!("lfm: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = run(args, json, profile.as_deref());
+    hooks::wait_for_post_hooks();
+    lfm_core::workspace::remove_session();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("lfm: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|a| a == flag) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+fn take_values(args: &mut Vec<String>, flag: &str) -> Result<Vec<String>, String> {
+    let mut values = Vec::new();
+    while let Some(i) = args.iter().position(|a| a == flag) {
+        if i + 1 >= args.len() {
+            return Err(format!("{flag} needs a value"));
+        }
+        values.push(args.remove(i + 1));
+        args.remove(i);
+    }
+    Ok(values)
+}
+
+fn open_store(profile: Option<&str>) -> Result<Store, String> {
+    let base = store::default_dir().ok_or("Cannot determine the app data directory")?;
+    let mut store = match profile {
+        Some(name) => profiles::profile_store(&base, name)?,
+        None => profiles::active_store(&base)?,
+    };
+    if encryption::is_encrypted(store.dir()) {
+        let passphrase = env::var(PASSPHRASE_VAR)
+            .map_err(|_| format!("The metadata store is encrypted, set {PASSPHRASE_VAR} to unlock it"))?;
+        let key = encryption::unlock(store.dir(), &passphrase)?;
+        store = store.with_key(key);
+    }
+    if let Ok(settings) = settings::load(&store) {
+        settings::apply_io(&settings);
+    }
+    Ok(store)
+}
+
+fn progress(done: u64, total: u64) {
+    if io::stderr().is_terminal() {
+        eprint!("\r{done}/{total}");
+        let _ = io::stderr().flush();
+        if done == total {
+            eprintln!();
+        }
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize: {e}"))?;
+    println!("{text}");
+    Ok(())
+}
+
+fn run(mut args: Vec<String>, json: bool, profile: Option<&str>) -> Result<(), String> {
+    if args.is_empty() || args[0] == "-h" || args[0] == "--help" {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    let command = args.remove(0);
+    match command.as_str() {
+        "scan" => {
+            let [root] = args.as_slice() else {
+                return Err(USAGE.into());
+            };
+            let store = open_store(profile)?;
+            let threads = settings::load(&store)?.hash_threads;
+            let root_canon = index::canonical_root(root)?;
+            let _awake = power::inhibit("lfm scan");
+            let summary = hooks::around(&store, "scan", json!({ "root": root }), || {
+                index::refresh_index(&store, &HashCache::default(), &root_canon, threads, &progress)
+                    .map(|(_, summary)| summary)
+            })?;
+            if json {
+                return print_json(&summary);
+            }
+            println!(
+                "{} files: {} hashed, {} unchanged, {} removed",
+                summary.files, summary.hashed, summary.reused, summary.removed
+            );
+        }
+        "verify" => {
+            let [root] = args.as_slice() else {
+                return Err(USAGE.into());
+            };
+            let store = open_store(profile)?;
+            let threads = settings::load(&store)?.hash_threads;
+            let root_canon = index::canonical_root(root)?;
+            let _awake = power::inhibit("lfm verify");
+            let report = index::verify_index(&store, &HashCache::default(), &root_canon, threads, &progress)?;
+            if json {
+                print_json(&report)?;
+            } else {
+                for c in &report.corrupted {
+                    println!("CORRUPT  {}", c.relative_path);
+                }
+                for m in &report.missing {
+                    println!("MISSING  {m}");
+                }
+                for u in &report.unreadable {
+                    println!("UNREADABLE  {u}");
+                }
+                println!(
+                    "{} checked, {} changed since scan, {} corrupted",
+                    report.checked,
+                    report.changed.len(),
+                    report.corrupted.len()
+                );
+            }
+            if !report.corrupted.is_empty() {
+                return Err(format!("{} corrupted file(s)", report.corrupted.len()));
+            }
+        }
+        "dedup" => {
+            let min_size = match take_values(&mut args, "--min-size")?.pop() {
+                Some(v) => v.parse::<u64>().map_err(|_| format!("Invalid --min-size: {v}"))?,
+                None => 1,
+            };
+            let catalogs = take_values(&mut args, "--catalog")?;
+            if args.is_empty() && catalogs.is_empty() {
+                return Err(USAGE.into());
+            }
+            let store = open_store(profile)?;
+            let threads = settings::load(&store)?.hash_threads;
+            let clusters = dedup::find_duplicates(
+                &store,
+                &HashCache::default(),
+                &args,
+                &catalogs,
+                min_size,
+                threads,
+                &progress,
+            )?;
+            if json {
+                return print_json(&clusters);
+            }
+            for c in &clusters {
+                println!("{} bytes x{}  {}", c.size, c.copies.len(), &c.hash[..16]);
+                for copy in &c.copies {
+                    let state = if copy.online { "" } else { " (offline)" };
+                    println!("  {}/{}{state}", copy.root, copy.relative_path);
+                }
+            }
+        }
+        "catalogs" => {
+            let catalogs = dedup::list_catalogs(&open_store(profile)?)?;
+            if json {
+                return print_json(&catalogs);
+            }
+            for c in &catalogs {
+                let state = if c.online { "online" } else { "offline" };
+                println!("{}  {}  {} files  {state}", c.id, c.root, c.files);
+            }
+        }
+        "sync" => {
+            let options = SyncOptions {
+                delete_extraneous: take_flag(&mut args, "--delete"),
+                dry_run: take_flag(&mut args, "--dry-run"),
+            };
+            let [source, destination] = args.as_slice() else {
+                return Err(USAGE.into());
+            };
+            let details = json!({ "source": source, "destination": destination, "options": options });
+            let _awake = power::inhibit("lfm sync");
+            let store = open_store(profile)?;
+            let current = settings::load(&store)?;
+            let report = hooks::around(&store, "sync", details, || {
+                let cancel = AtomicBool::new(false);
+                sync::sync_trees(
+                    &store,
+                    &current,
+                    Path::new(source),
+                    Path::new(destination),
+                    options,
+                    &cancel,
+                    &progress,
+                )
+            })?;
+            if json {
+                return print_json(&report);
+            }
+            for e in &report.errors {
+                eprintln!("error: {e}");
+            }
+            for rel in &report.protected {
+                eprintln!("kept by a deletion rule: {rel}");
+            }
+            println!(
+                "{} copied, {} updated, {} deleted, {} unchanged, {} bytes{}",
+                report.copied.len(),
+                report.updated.len(),
+                report.deleted.len(),
+                report.unchanged,
+                report.bytes,
+                if options.dry_run { " (dry run)" } else { "" }
+            );
+        }
+        "export" => {
+            let [file] = args.as_slice() else {
+                return Err(USAGE.into());
+            };
+            let summary = backup::export_metadata(&open_store(profile)?, Path::new(file))?;
+            if json {
+                return print_json(&summary);
+            }
+            if summary.plaintext {
+                eprintln!("warning: {file} is not encrypted, unlike the profile it was written from");
+            }
+            println!("{} documents, {} bytes", summary.documents, summary.bytes);
+        }
+        "import" => {
+            let [file] = args.as_slice() else {
+                return Err(USAGE.into());
+            };
+            let summary = backup::import_metadata(&open_store(profile)?, Path::new(file))?;
+            if json {
+                return print_json(&summary);
+            }
+            for s in &summary.skipped {
+                eprintln!("skipped: {s}");
+            }
+            for s in &summary.kept_local {
+                eprintln!("kept local: {s}");
+            }
+            println!("{} documents imported", summary.imported.len());
+        }
+        "merge" => {
+            let [file] = args.as_slice() else {
+                return Err(USAGE.into());
+            };
+            let report = backup::merge_metadata(&open_store(profile)?, Path::new(file))?;
+            if json {
+                return print_json(&report);
+            }
+            for name in &report.pulled {
+                println!("PULLED  {name}");
+            }
+            for name in &report.pushed {
+                println!("PUSHED  {name}");
+            }
+            for name in &report.skipped {
+                eprintln!("skipped: {name}");
+            }
+            for name in &report.kept_local {
+                eprintln!("kept local: {name}");
+            }
+            if report.plaintext {
+                eprintln!("warning: {file} is not encrypted, unlike the profile it was written from");
+            }
+            println!(
+                "{} pulled, {} pushed, {} unchanged",
+                report.pulled.len(),
+                report.pushed.len(),
+                report.unchanged
+            );
+        }
+        "serve" => {
+            let stdio = take_flag(&mut args, "--stdio");
+            let socket = take_values(&mut args, "--socket")?.pop();
+            let listen = take_values(&mut args, "--listen")?.pop();
+            if !args.is_empty() {
+                return Err(USAGE.into());
+            }
+            let store = open_store(profile)?;
+            let default_socket = store.dir().join(SOCKET_NAME);
+            let server = Arc::new(Server::new(store));
+            if let Some(addr) = listen {
+                let token = match env::var(TOKEN_VAR) {
+                    Ok(token) => token,
+                    Err(_) => {
+                        let token = rpc::session_token();
+                        eprintln!("lfm: session token {token}");
+                        token
+                    }
+                };
+                return server.serve_tcp(&addr, &token);
+            }
+            if stdio {
+                return server.serve_stdio();
+            }
+            #[cfg(unix)]
+            return server.serve_unix(socket.as_deref().map(Path::new).unwrap_or(&default_socket));
+            #[cfg(not(unix))]
+            {
+                let _ = (socket, default_socket);
+                return Err("Unix sockets are not supported here, use --stdio, or --listen with a token".into());
+            }
+        }
+        other => return Err(format!("Unknown command: {other}\n{USAGE}")),
+    }
+    Ok(())
+}
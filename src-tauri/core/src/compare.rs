@@ -0,0 +1,199 @@
+// Comparing two files that may live on different drives.
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::hashing::HashCache;
+use crate::paths::resolve_within;
+
+const COMPARE_CHUNK: usize = 1024 * 1024;
+// Text diffs are meant for notes and configs, not logs or dumps
+const MAX_DIFF_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+#[derive(Serialize, Default)]
+pub struct TreeDigest {
+    pub digest: String,
+    pub files: u64,
+    pub dirs: u64,
+    pub bytes: u64,
+    // FIFOs, sockets and device nodes, compared by name only
+    pub special: u64,
+}
+
+/// A file given by its root and a path relative to it, like every other
+/// command takes files; the two files compared may be under different roots.
+#[derive(Deserialize)]
+pub struct FileRef {
+    pub root: String,
+    pub relative_path: String,
+}
+
+#[derive(Serialize)]
+pub struct CompareResult {
+    pub identical: bool,
+    pub size_a: u64,
+    pub size_b: u64,
+    // offset of the first differing byte, None when identical or sizes differ
+    pub first_difference: Option<u64>,
+}
+
+// Like read_exact but tolerates EOF, returning how much was read
+fn read_full(file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn resolve_file(file: &FileRef) -> Result<PathBuf, String> {
+    let p = resolve_within(Path::new(&file.root), &file.relative_path)?;
+    if !p.is_file() {
+        return Err(format!("Not a file: {}", file.relative_path));
+    }
+    Ok(p)
+}
+
+/// Byte-for-byte comparison with early exit: differing sizes answer
+/// immediately, otherwise both files are read in lockstep until the first
+/// mismatching chunk.
+pub fn compare_files(file_a: &FileRef, file_b: &FileRef) -> Result<CompareResult, String> {
+    let (path_a, path_b) = (&file_a.relative_path, &file_b.relative_path);
+    let a = resolve_file(file_a)?;
+    let b = resolve_file(file_b)?;
+    let size_a = fs::metadata(&a).map_err(|e| format!("Failed to stat {path_a}: {e}"))?.len();
+    let size_b = fs::metadata(&b).map_err(|e| format!("Failed to stat {path_b}: {e}"))?.len();
+    let mut result = CompareResult {
+        identical: false,
+        size_a,
+        size_b,
+        first_difference: None,
+    };
+    if size_a != size_b {
+        return Ok(result);
+    }
+
+    let mut fa = File::open(&a).map_err(|e| format!("Failed to open {path_a}: {e}"))?;
+    let mut fb = File::open(&b).map_err(|e| format!("Failed to open {path_b}: {e}"))?;
+    let mut buf_a = vec![0u8; COMPARE_CHUNK];
+    let mut buf_b = vec![0u8; COMPARE_CHUNK];
+    let mut offset: u64 = 0;
+    loop {
+        let na = read_full(&mut fa, &mut buf_a).map_err(|e| format!("Read failed: {e}"))?;
+        let nb = read_full(&mut fb, &mut buf_b).map_err(|e| format!("Read failed: {e}"))?;
+        if buf_a[..na] != buf_b[..nb] {
+            let pos = buf_a[..na]
+                .iter()
+                .zip(&buf_b[..nb])
+                .position(|(x, y)| x != y)
+                .unwrap_or(na.min(nb));
+            result.first_difference = Some(offset + pos as u64);
+            return Ok(result);
+        }
+        if na == 0 {
+            break;
+        }
+        offset += na as u64;
+    }
+    result.identical = true;
+    Ok(result)
+}
+
+fn read_text(file: &FileRef) -> Result<String, String> {
+    let p = resolve_file(file)?;
+    let name = &file.relative_path;
+    let size = fs::metadata(&p).map_err(|e| format!("Failed to stat {name}: {e}"))?.len();
+    if size > MAX_DIFF_FILE_SIZE {
+        return Err(format!("{name} is too large to diff"));
+    }
+    let data = fs::read(&p).map_err(|e| format!("Failed to read {name}: {e}"))?;
+    if data.contains(&0) {
+        return Err(format!("{name} looks like a binary file"));
+    }
+    Ok(String::from_utf8_lossy(&data).to_string())
+}
+
+/// Unified diff of two text files with `context_lines` of context around
+/// each change. Empty when the files are identical.
+pub fn diff_text_files(a: &FileRef, b: &FileRef, context_lines: usize) -> Result<String, String> {
+    let text_a = read_text(a)?;
+    let text_b = read_text(b)?;
+    let diff = similar::TextDiff::from_lines(&text_a, &text_b);
+    Ok(diff
+        .unified_diff()
+        .context_radius(context_lines)
+        .header(&a.relative_path, &b.relative_path)
+        .to_string())
+}
+
+// Merkle node: a file hashes its kind, name and content hash, a directory its
+// kind, name and the sorted digests of its children. Symlinks contribute
+// their target, never what it points to, and special files only their name:
+// opening a FIFO would block forever.
+fn node_digest(
+    cache: &HashCache,
+    path: &Path,
+    name: &[u8],
+    totals: &mut TreeDigest,
+) -> Result<blake3::Hash, String> {
+    let meta = fs::symlink_metadata(path).map_err(|e| format!("Failed to stat {}: {e}", path.display()))?;
+    let mut hasher = blake3::Hasher::new();
+    if meta.is_dir() {
+        totals.dirs += 1;
+        let mut children: Vec<(Vec<u8>, PathBuf)> = fs::read_dir(path)
+            .map_err(|e| format!("Failed to read dir {}: {e}", path.display()))?
+            .flatten()
+            .map(|e| (e.file_name().to_string_lossy().as_bytes().to_vec(), e.path()))
+            .collect();
+        children.sort();
+        hasher.update(b"d");
+        hasher.update(&(name.len() as u64).to_le_bytes());
+        hasher.update(name);
+        for (child_name, child_path) in children {
+            let child = node_digest(cache, &child_path, &child_name, totals)?;
+            hasher.update(child.as_bytes());
+        }
+    } else if meta.file_type().is_symlink() {
+        let target = fs::read_link(path).map_err(|e| format!("Failed to read link {}: {e}", path.display()))?;
+        hasher.update(b"l");
+        hasher.update(&(name.len() as u64).to_le_bytes());
+        hasher.update(name);
+        hasher.update(target.to_string_lossy().as_bytes());
+    } else if !meta.is_file() {
+        totals.special += 1;
+        hasher.update(b"s");
+        hasher.update(&(name.len() as u64).to_le_bytes());
+        hasher.update(name);
+    } else {
+        totals.files += 1;
+        totals.bytes += meta.len();
+        let content = cache
+            .hash(path)
+            .map_err(|e| format!("Failed to hash {}: {e}", path.display()))?;
+        hasher.update(b"f");
+        hasher.update(&(name.len() as u64).to_le_bytes());
+        hasher.update(name);
+        hasher.update(content.as_bytes());
+    }
+    Ok(hasher.finalize())
+}
+
+/// Deterministic digest over the names and contents of everything below a
+/// folder. Two trees are identical exactly when their digests match; the
+/// name of the folder itself is not part of it.
+pub fn tree_digest(cache: &HashCache, root: &str, relative_dir: &str) -> Result<TreeDigest, String> {
+    let dir = resolve_within(Path::new(root), relative_dir)?;
+    if !dir.is_dir() {
+        return Err("Target is not a directory".into());
+    }
+    let mut totals = TreeDigest::default();
+    let digest = node_digest(cache, &dir, b"", &mut totals)?;
+    totals.digest = digest.to_hex().to_string();
+    Ok(totals)
+}
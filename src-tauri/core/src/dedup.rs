@@ -0,0 +1,165 @@
+// Duplicate detection over hash indexes. Mounted roots are refreshed first;
+// stored indexes of drives that aren't plugged in act as offline catalogs.
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::hashing::HashCache;
+use crate::index::{self, RootIndex};
+use crate::store::Store;
+use crate::Progress;
+
+const ACCEPTED_DOC: &str = "dedup/accepted";
+
+#[derive(Serialize)]
+pub struct CatalogInfo {
+    pub id: String,
+    pub root: String,
+    pub indexed_at: i64,
+    pub files: u64,
+    pub bytes: u64,
+    pub online: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DuplicateCopy {
+    pub root: String,
+    pub relative_path: String,
+    pub online: bool,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateCluster {
+    pub hash: String,
+    pub size: u64,
+    pub copies: Vec<DuplicateCopy>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AcceptedDuplicate {
+    pub hash: String,
+    pub size: u64,
+    pub note: String,
+    pub accepted_at: i64,
+}
+
+// Intentional copies the user doesn't want to see in reports again, keyed by hash
+#[derive(Serialize, Deserialize, Default)]
+struct AcceptedList {
+    clusters: BTreeMap<String, AcceptedDuplicate>,
+}
+
+/// All stored indexes, whether or not their drive is currently mounted.
+pub fn list_catalogs(store: &Store) -> Result<Vec<CatalogInfo>, String> {
+    let mut out = Vec::new();
+    for id in store.list("index")? {
+        let idx: RootIndex = store.load(&id)?;
+        out.push(CatalogInfo {
+            online: Path::new(&idx.root).is_dir(),
+            files: idx.entries.len() as u64,
+            bytes: idx.entries.values().map(|e| e.size).sum(),
+            root: idx.root,
+            indexed_at: idx.indexed_at,
+            id,
+        });
+    }
+    Ok(out)
+}
+
+/// Group identical files across every given root and catalog. `roots` are
+/// mounted paths (re-indexed before comparing), `catalog_ids` come from
+/// `list_catalogs` and are used as stored. Files smaller than `min_size`
+/// are ignored.
+pub fn find_duplicates(
+    store: &Store,
+    cache: &HashCache,
+    roots: &[String],
+    catalog_ids: &[String],
+    min_size: u64,
+    threads: Option<usize>,
+    on_progress: Progress,
+) -> Result<Vec<DuplicateCluster>, String> {
+    let mut sources: Vec<(RootIndex, bool)> = Vec::new();
+    for root in roots {
+        let root_canon = index::canonical_root(root)?;
+        let (idx, _) = index::refresh_index(store, cache, &root_canon, threads, on_progress)?;
+        sources.push((idx, true));
+    }
+    // Only ids of stored catalogs, never a path into the rest of the store
+    let known = store.list("index")?;
+    for id in catalog_ids {
+        if !known.contains(id) {
+            return Err(format!("Unknown catalog: {id}"));
+        }
+        let idx: RootIndex = store.load(id)?;
+        // Skip catalogs already covered by a live root
+        if sources.iter().any(|(s, _)| s.root == idx.root) {
+            continue;
+        }
+        if idx.entries.is_empty() {
+            return Err(format!("Unknown catalog: {id}"));
+        }
+        let online = Path::new(&idx.root).is_dir();
+        sources.push((idx, online));
+    }
+
+    let mut by_hash: HashMap<(String, u64), Vec<DuplicateCopy>> = HashMap::new();
+    for (idx, online) in &sources {
+        for (rel, entry) in &idx.entries {
+            if entry.size < min_size {
+                continue;
+            }
+            by_hash
+                .entry((entry.hash.clone(), entry.size))
+                .or_default()
+                .push(DuplicateCopy {
+                    root: idx.root.clone(),
+                    relative_path: rel.clone(),
+                    online: *online,
+                });
+        }
+    }
+
+    let accepted: AcceptedList = store.load(ACCEPTED_DOC)?;
+    let mut clusters: Vec<DuplicateCluster> = by_hash
+        .into_iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .filter(|((hash, _), _)| !accepted.clusters.contains_key(hash))
+        .map(|((hash, size), copies)| DuplicateCluster { hash, size, copies })
+        .collect();
+    // Biggest savings first
+    clusters.sort_by(|a, b| {
+        let wa = a.size * (a.copies.len() as u64 - 1);
+        let wb = b.size * (b.copies.len() as u64 - 1);
+        wb.cmp(&wa).then_with(|| a.hash.cmp(&b.hash))
+    });
+    Ok(clusters)
+}
+
+/// Mark a duplicate cluster as intentional so `find_duplicates` skips it.
+pub fn accept_duplicate(store: &Store, hash: String, size: u64, note: String) -> Result<(), String> {
+    let mut accepted: AcceptedList = store.load(ACCEPTED_DOC)?;
+    accepted.clusters.insert(
+        hash.clone(),
+        AcceptedDuplicate {
+            hash,
+            size,
+            note,
+            accepted_at: index::now_ms(),
+        },
+    );
+    store.save(ACCEPTED_DOC, &accepted)
+}
+
+pub fn unaccept_duplicate(store: &Store, hash: &str) -> Result<(), String> {
+    let mut accepted: AcceptedList = store.load(ACCEPTED_DOC)?;
+    if accepted.clusters.remove(hash).is_none() {
+        return Err("Duplicate cluster is not in the accepted list".into());
+    }
+    store.save(ACCEPTED_DOC, &accepted)
+}
+
+pub fn list_accepted_duplicates(store: &Store) -> Result<Vec<AcceptedDuplicate>, String> {
+    let accepted: AcceptedList = store.load(ACCEPTED_DOC)?;
+    Ok(accepted.clusters.into_values().collect())
+}
@@ -0,0 +1,192 @@
+// Opt-in fallback for file operations the user's account isn't allowed to
+// do, e.g. on drives formatted by another system: the operation is handed
+// to a helper run through the OS elevation prompt (pkexec on Linux, the
+// administrator dialog on macOS, UAC on Windows).
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::paths::{canonical_within, normalize_input_path, resolve_within};
+use crate::store::Store;
+use crate::trash;
+
+/// Operations that can be retried with elevated rights, with the same
+/// parameters as the regular commands.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ElevatedOp {
+    Delete { relative_path: String },
+    Rename { relative_path: String, new_name: String },
+    Move { relative_path: String, to_relative_dir: String },
+    CreateFolder { relative_dir: String },
+}
+
+enum Action {
+    Remove(PathBuf),
+    Rename(PathBuf, PathBuf),
+    MakeDir(PathBuf),
+}
+
+impl Action {
+    fn describe(&self) -> String {
+        match self {
+            Action::Remove(p) => format!("delete {}", p.display()),
+            Action::Rename(from, to) => format!("move {} to {}", from.display(), to.display()),
+            Action::MakeDir(p) => format!("create the folder {}", p.display()),
+        }
+    }
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+// Resolve everything with the same root checks as the regular operations,
+// before anything runs with elevated rights
+fn plan(root: &Path, op: &ElevatedOp) -> Result<Action, String> {
+    match op {
+        ElevatedOp::Delete { relative_path } => {
+            let path = resolve_within(root, relative_path)?;
+            if !path.is_file() {
+                return Err("Only files can be deleted with this action".into());
+            }
+            Ok(Action::Remove(path))
+        }
+        ElevatedOp::Rename { relative_path, new_name } => {
+            if !valid_name(new_name) {
+                return Err("Invalid file name".into());
+            }
+            let from = resolve_within(root, relative_path)?;
+            let to = from.with_file_name(new_name);
+            if to.exists() {
+                return Err(format!("{new_name} already exists"));
+            }
+            Ok(Action::Rename(from, to))
+        }
+        ElevatedOp::Move { relative_path, to_relative_dir } => {
+            let from = resolve_within(root, relative_path)?;
+            let dir = resolve_within(root, to_relative_dir)?;
+            if !dir.is_dir() {
+                return Err("Destination is not a folder".into());
+            }
+            let to = dir.join(from.file_name().ok_or("Invalid source")?);
+            if to.exists() {
+                return Err("Destination already has a file with that name".into());
+            }
+            Ok(Action::Rename(from, to))
+        }
+        ElevatedOp::CreateFolder { relative_dir } => {
+            let target = root.join(normalize_input_path(relative_dir.trim_start_matches('/')));
+            let parent = target.parent().unwrap_or(root);
+            let parent = canonical_within(root, parent)?;
+            let name = target.file_name().ok_or("Invalid folder name")?;
+            Ok(Action::MakeDir(parent.join(name)))
+        }
+    }
+}
+
+/// Run `op` below `root` with elevated rights. The OS shows its own
+/// authentication prompt; dismissing it fails the operation.
+pub fn run_elevated(root: &str, op: &ElevatedOp) -> Result<(), String> {
+    run_action(&plan(Path::new(root), op)?)
+}
+
+/// Move a file of `root` into its trash, the move itself with elevated
+/// rights.
+pub fn trash_elevated(store: &Store, root: &str, relative_path: &str) -> Result<(), String> {
+    trash::trash_file_with(store, root, relative_path, |from, to| {
+        run_action(&Action::Rename(from.to_path_buf(), to.to_path_buf()))
+    })
+}
+
+fn run_action(action: &Action) -> Result<(), String> {
+    let status = elevated_command(action)
+        .status()
+        .map_err(|e| format!("Failed to start the elevation helper: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Could not {} with administrator rights", action.describe()))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn elevated_command(action: &Action) -> Command {
+    // pkexec needs absolute program paths and shows which one is run
+    let mut cmd = Command::new("pkexec");
+    match action {
+        Action::Remove(p) => cmd.args(["/bin/rm", "-f", "--"]).arg(p),
+        Action::Rename(from, to) => cmd.args(["/bin/mv", "-n", "--"]).arg(from).arg(to),
+        Action::MakeDir(p) => cmd.args(["/bin/mkdir", "-p", "--"]).arg(p),
+    };
+    cmd
+}
+
+#[cfg(target_os = "macos")]
+fn elevated_command(action: &Action) -> Command {
+    fn sh_quote(p: &Path) -> String {
+        format!("'{}'", p.display().to_string().replace('\'', "'\\''"))
+    }
+    fn as_string(s: &str) -> String {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+    let script = match action {
+        Action::Remove(p) => format!("/bin/rm -f -- {}", sh_quote(p)),
+        Action::Rename(from, to) => format!("/bin/mv -n -- {} {}", sh_quote(from), sh_quote(to)),
+        Action::MakeDir(p) => format!("/bin/mkdir -p -- {}", sh_quote(p)),
+    };
+    let prompt = format!("sdmanager needs administrator rights to {}.", action.describe());
+    let mut cmd = Command::new("osascript");
+    cmd.arg("-e").arg(format!(
+        "do shell script {} with prompt {} with administrator privileges",
+        as_string(&script),
+        as_string(&prompt)
+    ));
+    cmd
+}
+
+#[cfg(windows)]
+fn elevated_command(action: &Action) -> Command {
+    fn ps_quote(p: &Path) -> String {
+        format!("'{}'", p.display().to_string().replace('\'', "''"))
+    }
+    let inner = match action {
+        Action::Remove(p) => format!("Remove-Item -LiteralPath {} -Force", ps_quote(p)),
+        Action::Rename(from, to) => format!("Move-Item -LiteralPath {} -Destination {}", ps_quote(from), ps_quote(to)),
+        Action::MakeDir(p) => format!("New-Item -ItemType Directory -Force -Path {}", ps_quote(p)),
+    };
+    let inner = format!("$ErrorActionPreference = 'Stop'; {inner}");
+    // Passed encoded so no quoting survives two levels of PowerShell
+    let utf16: Vec<u8> = inner.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let outer = format!(
+        "$p = Start-Process powershell -Verb RunAs -Wait -PassThru -WindowStyle Hidden \
+         -ArgumentList '-NoProfile','-EncodedCommand','{}'; exit $p.ExitCode",
+        base64(&utf16)
+    );
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-Command", &outer]);
+    cmd
+}
+
+#[cfg(windows)]
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | ((*b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn elevated_command(_action: &Action) -> Command {
+    // Fails to start, reported as such
+    Command::new("elevation-not-supported")
+}
@@ -0,0 +1,147 @@
+// Optional encryption of a profile's metadata store with a key derived from
+// a passphrase. Documents are encrypted one by one, so a store being
+// converted can be read at any point; the key file holds the salt and a
+// check value used to reject wrong passphrases.
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::store::Store;
+
+pub type Key = [u8; 32];
+
+/// Lives next to the documents but isn't one, so exports never include it.
+pub const KEY_FILE: &str = "store.key";
+// Prefix marking an encrypted document; plain ones are JSON and never start with it
+const MAGIC: &[u8] = b"LFMENC1\0";
+const CHECK: &[u8] = b"lfm-store-check";
+const NONCE_LEN: usize = 24;
+
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    salt: Vec<u8>,
+    check: Vec<u8>,
+}
+
+#[derive(Serialize)]
+pub struct EncryptionStatus {
+    pub encrypted: bool,
+    pub unlocked: bool,
+}
+
+pub fn is_encrypted(dir: &Path) -> bool {
+    dir.join(KEY_FILE).exists()
+}
+
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn seal(key: &Key, plain: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let sealed = cipher
+        .encrypt(&nonce, plain)
+        .map_err(|_| "Encryption failed".to_string())?;
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + sealed.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+pub fn open_sealed(key: &Key, data: &[u8]) -> Result<Vec<u8>, String> {
+    let body = data
+        .strip_prefix(MAGIC)
+        .filter(|b| b.len() >= NONCE_LEN)
+        .ok_or("Not an encrypted document")?;
+    let (nonce, sealed) = body.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), sealed)
+        .map_err(|_| "Wrong passphrase or damaged document".to_string())
+}
+
+fn derive(passphrase: &str, salt: &[u8]) -> Result<Key, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {e}"))?;
+    Ok(key)
+}
+
+fn read_key_file(dir: &Path) -> Result<KeyFile, String> {
+    let data = fs::read(dir.join(KEY_FILE)).map_err(|e| format!("Failed to read key file: {e}"))?;
+    serde_json::from_slice(&data).map_err(|e| format!("Corrupt key file: {e}"))
+}
+
+pub fn status(store: &Store) -> EncryptionStatus {
+    EncryptionStatus {
+        encrypted: is_encrypted(store.dir()),
+        unlocked: store.key().is_some(),
+    }
+}
+
+/// Derive the key of an encrypted store, failing on a wrong passphrase.
+pub fn unlock(dir: &Path, passphrase: &str) -> Result<Key, String> {
+    let key_file = read_key_file(dir)?;
+    let key = derive(passphrase, &key_file.salt)?;
+    match open_sealed(&key, &key_file.check) {
+        Ok(check) if check == CHECK => Ok(key),
+        _ => Err("Wrong passphrase".into()),
+    }
+}
+
+/// Encrypt every document of a plain store and return the key to keep
+/// using it with.
+pub fn enable(store: &Store, passphrase: &str) -> Result<Key, String> {
+    if is_encrypted(store.dir()) {
+        return Err("The metadata store is already encrypted".into());
+    }
+    if passphrase.chars().count() < 8 {
+        return Err("Use a passphrase of at least 8 characters".into());
+    }
+    let mut salt = vec![0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive(passphrase, &salt)?;
+    let key_file = KeyFile {
+        salt,
+        check: seal(&key, CHECK)?,
+    };
+    // The key file goes first: from then on every document, plain or not, can be read
+    let data = serde_json::to_vec(&key_file).map_err(|e| e.to_string())?;
+    fs::write(store.dir().join(KEY_FILE), data).map_err(|e| format!("Failed to write key file: {e}"))?;
+
+    let keyed = Store::open(store.dir().to_path_buf())?.with_key(key);
+    for (name, modified_ms) in documents(&keyed)? {
+        if let Some(data) = keyed.read_raw(&name)? {
+            keyed.write_raw(&name, &data)?;
+            keyed.set_modified(&name, modified_ms)?;
+        }
+    }
+    Ok(key)
+}
+
+/// Decrypt every document and drop the key file.
+pub fn disable(store: &Store, passphrase: &str) -> Result<(), String> {
+    let key = unlock(store.dir(), passphrase)?;
+    let keyed = Store::open(store.dir().to_path_buf())?.with_key(key);
+    for (name, modified_ms) in documents(&keyed)? {
+        if let Some(data) = keyed.read_raw(&name)? {
+            keyed.write_unsealed(&name, &data)?;
+            keyed.set_modified(&name, modified_ms)?;
+        }
+    }
+    fs::remove_file(store.dir().join(KEY_FILE)).map_err(|e| format!("Failed to remove key file: {e}"))
+}
+
+fn documents(store: &Store) -> Result<Vec<(String, i64)>, String> {
+    // Other profiles nest under the default one and have their own key, and
+    // the active profile is read before any store is unlocked
+    let mut docs = store.documents(&[crate::profiles::PROFILES_DIR])?;
+    docs.retain(|(name, _)| name != crate::profiles::ACTIVE_DOC);
+    Ok(docs)
+}
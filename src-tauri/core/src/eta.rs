@@ -0,0 +1,92 @@
+// Transfer speed and ETA estimation. Speed is an exponentially smoothed
+// average of periodic samples, seeded from the throughput previously seen
+// when writing to the same destination so the first estimate isn't a guess.
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::store::Store;
+
+const HISTORY_DOC: &str = "throughput";
+// Weight of the newest sample in the smoothed speed
+const SMOOTHING: f64 = 0.2;
+const MIN_SAMPLE: Duration = Duration::from_millis(500);
+// Shorter transfers say more about latency than about the drive
+const MIN_RECORDED_BYTES: u64 = 16 * 1024 * 1024;
+const MIN_RECORDED_TIME: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Clone, Copy, Default)]
+pub struct Estimate {
+    pub bytes_per_sec: f64,
+    pub eta_secs: Option<u64>,
+}
+
+pub struct EtaEstimator {
+    last_at: Instant,
+    last_bytes: u64,
+    speed: Option<f64>,
+}
+
+impl EtaEstimator {
+    pub fn new(prior_bytes_per_sec: Option<f64>) -> EtaEstimator {
+        EtaEstimator {
+            last_at: Instant::now(),
+            last_bytes: 0,
+            speed: prior_bytes_per_sec.filter(|s| *s > 0.0),
+        }
+    }
+
+    pub fn update(&mut self, done: u64, total: u64) -> Estimate {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_at);
+        if elapsed >= MIN_SAMPLE && done >= self.last_bytes {
+            let sample = (done - self.last_bytes) as f64 / elapsed.as_secs_f64();
+            self.speed = Some(match self.speed {
+                Some(speed) => speed + SMOOTHING * (sample - speed),
+                None => sample,
+            });
+            self.last_at = now;
+            self.last_bytes = done;
+        }
+        let remaining = total.saturating_sub(done);
+        let speed = self.speed.unwrap_or(0.0);
+        let eta_secs = if remaining == 0 {
+            Some(0)
+        } else if speed > 0.0 {
+            Some((remaining as f64 / speed).ceil() as u64)
+        } else {
+            None
+        };
+        Estimate {
+            bytes_per_sec: speed,
+            eta_secs,
+        }
+    }
+}
+
+fn destination_key(destination: &Path) -> String {
+    destination
+        .canonicalize()
+        .unwrap_or_else(|_| destination.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Average throughput of earlier transfers to `destination`, in bytes/s.
+pub fn prior_throughput(store: &Store, destination: &Path) -> Result<Option<f64>, String> {
+    let history: BTreeMap<String, f64> = store.load(HISTORY_DOC)?;
+    Ok(history.get(&destination_key(destination)).copied())
+}
+
+/// Fold a finished transfer into the destination's throughput history.
+pub fn record_throughput(store: &Store, destination: &Path, bytes: u64, elapsed: Duration) -> Result<(), String> {
+    if bytes < MIN_RECORDED_BYTES || elapsed < MIN_RECORDED_TIME {
+        return Ok(());
+    }
+    let speed = bytes as f64 / elapsed.as_secs_f64();
+    let mut history: BTreeMap<String, f64> = store.load(HISTORY_DOC)?;
+    let entry = history.entry(destination_key(destination)).or_insert(speed);
+    *entry = (*entry + speed) / 2.0;
+    store.save(HISTORY_DOC, &history)
+}
@@ -0,0 +1,135 @@
+// Content sniffing: compare a file's magic bytes with its extension.
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::index::walk_files;
+use crate::paths::{relative_key, resolve_within};
+use crate::Progress;
+
+// Extensions that name the same format, or formats that are legitimately
+// stored in the container infer reports (zip-based documents and so on)
+const EQUIVALENT: &[&[&str]] = &[
+    &["jpg", "jpeg", "jpe", "jfif"],
+    &["tif", "tiff", "dng", "cr2", "nef", "arw", "orf", "rw2", "pef"],
+    &["mp4", "m4v", "m4a", "m4b", "mov", "3gp", "3g2", "qt"],
+    &["heic", "heif", "avif"],
+    &["mkv", "webm", "mka"],
+    &["mpg", "mpeg", "mpe", "m2v", "vob"],
+    &["htm", "html", "xhtml"],
+    &["ogg", "ogv", "oga", "opus", "spx"],
+    &["xml", "svg", "gpx", "kml", "plist"],
+    &["gz", "tgz"],
+    &[
+        "zip", "docx", "xlsx", "pptx", "odt", "ods", "odp", "epub", "jar", "apk", "cbz", "kmz", "xpi", "ipa", "3mf",
+        "whl", "nupkg", "vsix", "numbers", "pages", "key", "sketch", "ora", "kra",
+    ],
+    &["exe", "dll", "sys", "msi", "scr", "cpl", "ocx"],
+    &["doc", "xls", "ppt", "msg"],
+    &["rar", "cbr"],
+    &["7z", "cb7"],
+    // Illustrator files saved with PDF compatibility, the default
+    &["pdf", "ai"],
+];
+
+#[derive(Serialize)]
+pub struct ExtensionMismatch {
+    pub relative_path: String,
+    // current extension, lowercase, empty when the file has none
+    pub extension: String,
+    pub detected_extension: String,
+    pub detected_mime: String,
+}
+
+#[derive(Serialize)]
+pub struct ExtensionFix {
+    pub from: String,
+    pub to: String,
+}
+
+fn same_format(ext: &str, detected: &str) -> bool {
+    ext == detected
+        || EQUIVALENT
+            .iter()
+            .any(|group| group.contains(&ext) && group.contains(&detected))
+}
+
+/// Detected type of `path` as (extension, mime), None if unrecognized.
+pub fn sniff(path: &Path) -> Option<(String, String)> {
+    infer::get_from_path(path)
+        .ok()
+        .flatten()
+        .map(|t| (t.extension().to_string(), t.mime_type().to_string()))
+}
+
+fn mismatch(root_canon: &Path, path: &Path) -> Option<ExtensionMismatch> {
+    let (detected, mime) = sniff(path)?;
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if same_format(&ext, &detected) {
+        return None;
+    }
+    Some(ExtensionMismatch {
+        relative_path: relative_key(root_canon, path),
+        extension: ext,
+        detected_extension: detected,
+        detected_mime: mime,
+    })
+}
+
+/// Files below `relative_dir` whose content doesn't match their extension.
+/// Files of unrecognized types (plain text, most source code) are skipped.
+pub fn find_extension_mismatches(
+    root: &str,
+    relative_dir: &str,
+    on_progress: Progress,
+) -> Result<Vec<ExtensionMismatch>, String> {
+    let root_path = Path::new(root);
+    let root_canon = root_path
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let dir = resolve_within(root_path, relative_dir)?;
+    let files = walk_files(&dir);
+    let total = files.len() as u64;
+    let mut out = Vec::new();
+    for (n, (p, _)) in files.iter().enumerate() {
+        if let Some(m) = mismatch(&root_canon, p) {
+            out.push(m);
+        }
+        on_progress(n as u64 + 1, total);
+    }
+    out.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(out)
+}
+
+/// Rename each file to carry the extension matching its content. Files that
+/// already match, or whose type can't be detected, are left alone.
+pub fn fix_extensions(root: &str, relative_paths: &[String]) -> Result<Vec<ExtensionFix>, String> {
+    let root_path = Path::new(root);
+    let root_canon = root_path
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let mut fixes = Vec::new();
+    for rel in relative_paths {
+        let src = resolve_within(root_path, rel)?;
+        if !src.is_file() {
+            continue;
+        }
+        let m = match mismatch(&root_canon, &src) {
+            Some(m) => m,
+            None => continue,
+        };
+        let stem = src.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        // "photo.jpg" detected as PNG becomes "photo.png", never overwriting
+        let parent = src.parent().ok_or("File has no parent directory")?;
+        let dest = crate::imaging::free_name(parent, &stem, &m.detected_extension);
+        fs::rename(&src, &dest).map_err(|e| format!("Rename failed: {e}"))?;
+        fixes.push(ExtensionFix {
+            from: m.relative_path,
+            to: relative_key(&root_canon, &dest),
+        });
+    }
+    Ok(fixes)
+}
@@ -0,0 +1,692 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::groups;
+use crate::i18n;
+use crate::paths::{
+    canonical_within, canonical_within_on, is_hidden, normalize_input_path, relative_key, relative_path, resolve_within,
+};
+use crate::shortcuts::{self, Shortcut};
+use crate::sorting::natural_cmp;
+use crate::vfs::{FileSystem, RealFs};
+
+#[derive(Serialize)]
+pub struct MountPoint {
+    pub path: String,
+    pub label: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FileEntry {
+    // absolute path
+    pub path: String,
+    // path relative to the provided root
+    pub relative_path: String,
+    pub size: u64,
+    // set for .lnk and .desktop files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortcut: Option<Shortcut>,
+    // id of the sidecar group the file belongs to, see `groups`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct SelectionSize {
+    // number of top-level items in the selection
+    pub items: usize,
+    pub files: u64,
+    pub dirs: u64,
+    pub bytes: u64,
+}
+
+/// An entry of a single folder, see `list_children_sharded`.
+#[derive(Serialize)]
+pub struct ChildEntry {
+    pub name: String,
+    pub relative_path: String,
+    pub is_dir: bool,
+    // 0 for folders
+    pub size: u64,
+}
+
+#[derive(Serialize, Default)]
+pub struct ShardedChildren {
+    // entries in the requested bucket, in natural name order
+    pub entries: Vec<ChildEntry>,
+    // number of entries in each bucket of the whole folder
+    pub buckets: BTreeMap<String, u64>,
+}
+
+/// Bucket of names starting with a digit.
+pub const DIGITS_BUCKET: &str = "#";
+/// Bucket of names starting with anything but a letter or digit.
+pub const OTHER_BUCKET: &str = "*";
+
+// Report progress every this many entries while sizing big folders
+const SIZE_PROGRESS_EVERY: u64 = 5_000;
+
+pub fn list_candidate_mounts() -> Result<Vec<MountPoint>, String> {
+    let mut mounts: Vec<MountPoint> = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        let user = env::var("USER").unwrap_or_default();
+        let candidates: [&str; 3] = [
+            "/media",
+            "/run/media", // usually /run/media/$USER/<label>
+            "/mnt",
+        ];
+
+        for base in candidates.iter() {
+            let base_path = if *base == "/run/media" && !user.is_empty() {
+                Path::new(base).join(&user)
+            } else {
+                PathBuf::from(base)
+            };
+
+            if base_path.is_dir() {
+                if let Ok(entries) = fs::read_dir(&base_path) {
+                    for e in entries.flatten() {
+                        let p = e.path();
+                        if p.is_dir() {
+                            let label = p
+                                .file_name()
+                                .map(|s| s.to_string_lossy().to_string())
+                                .unwrap_or_else(|| p.display().to_string());
+                            mounts.push(MountPoint {
+                                path: p.display().to_string(),
+                                label,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        // On other OSes, just return empty list for now.
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::path::Path;
+        // Iterate drive letters A:..Z: and return those that exist
+        for letter in b'A'..=b'Z' {
+            let drive = format!("{}:\\", letter as char);
+            let p = Path::new(&drive);
+            if p.exists() && p.is_dir() {
+                mounts.push(MountPoint {
+                    path: p.display().to_string(),
+                    label: drive.clone(),
+                });
+            }
+        }
+    }
+
+    #[cfg(all(not(target_os = "linux"), not(target_os = "windows")))]
+    {
+        // On other OSes, just return empty list for now.
+    }
+
+    Ok(mounts)
+}
+
+/// The user's Documents, Downloads, Pictures, Videos and Desktop folders
+/// (xdg-user-dirs on Linux, Known Folders on Windows), in the same shape as
+/// mounts so they can be offered as roots next to them. Folders that aren't
+/// configured or don't exist are left out.
+pub fn list_standard_dirs() -> Vec<MountPoint> {
+    let dirs = [
+        ("Documents", dirs::document_dir()),
+        ("Downloads", dirs::download_dir()),
+        ("Pictures", dirs::picture_dir()),
+        ("Videos", dirs::video_dir()),
+        ("Desktop", dirs::desktop_dir()),
+    ];
+    let mut out: Vec<MountPoint> = Vec::new();
+    for (label, dir) in dirs {
+        let Some(dir) = dir.filter(|d| d.is_dir()) else {
+            continue;
+        };
+        // Unconfigured XDG folders fall back to the home directory itself
+        if dirs::home_dir().as_ref() == Some(&dir) || out.iter().any(|m| Path::new(&m.path) == dir) {
+            continue;
+        }
+        out.push(MountPoint {
+            path: dir.display().to_string(),
+            label: i18n::tr(label),
+        });
+    }
+    out
+}
+
+pub fn list_files(root: &str, show_hidden: bool) -> Result<Vec<FileEntry>, String> {
+    let root_path = PathBuf::from(root);
+    let root_canon = root_path
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+
+    let mut result: Vec<FileEntry> = Vec::new();
+    let mut stack: Vec<PathBuf> = vec![root_canon.clone()];
+
+    while let Some(dir) = stack.pop() {
+        let read_dir = fs::read_dir(&dir).map_err(|e| format!("Failed to read dir {}: {e}", dir.display()))?;
+        for entry in read_dir.flatten() {
+            let p = entry.path();
+            
+            // Skip hidden files if show_hidden is false
+            if !show_hidden && is_hidden(&p) {
+                continue;
+            }
+            
+            let rel = p.strip_prefix(&root_canon).unwrap_or(&p);
+                if p.is_dir() {
+                    stack.push(p);
+                } else if p.is_file() {
+                    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    // Normalize relative path to use forward slashes for the frontend
+                    let mut rel_str = rel.display().to_string();
+                    if cfg!(windows) {
+                        rel_str = rel_str.replace("\\", "/");
+                    }
+                    result.push(FileEntry {
+                        path: p.display().to_string(),
+                        relative_path: rel_str,
+                        size,
+                        shortcut: shortcuts::resolve(&p),
+                        group: None,
+                    });
+                }
+        }
+    }
+    // Sort by relative_path for stable display
+    result.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    groups::tag_groups(&mut result);
+    Ok(result)
+}
+
+/// A listing where every folder is stored once, as `[parent_id, name]`, and
+/// files refer to their folder by id. Folder 0 is the root. On deep trees
+/// this is several times smaller than `FileEntry`s repeating full paths.
+#[derive(Serialize, Default)]
+pub struct CompactListing {
+    pub root: String,
+    pub dirs: Vec<CompactDir>,
+    pub files: Vec<CompactFile>,
+}
+
+#[derive(Serialize)]
+pub struct CompactDir(pub u32, pub String);
+
+/// `[dir_id, name, size]`
+#[derive(Serialize)]
+pub struct CompactFile(pub u32, pub String, pub u64);
+
+pub fn list_files_compact(root: &str, show_hidden: bool) -> Result<CompactListing, String> {
+    let root_canon = PathBuf::from(root)
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let mut listing = CompactListing {
+        root: root_canon.display().to_string(),
+        dirs: vec![CompactDir(0, String::new())],
+        files: Vec::new(),
+    };
+    let mut stack: Vec<(PathBuf, u32)> = vec![(root_canon, 0)];
+    while let Some((dir, dir_id)) = stack.pop() {
+        let read_dir = fs::read_dir(&dir).map_err(|e| format!("Failed to read dir {}: {e}", dir.display()))?;
+        let mut entries: Vec<fs::DirEntry> = read_dir
+            .flatten()
+            .filter(|e| show_hidden || !is_hidden(&e.path()))
+            .collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let p = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if p.is_dir() {
+                let id = listing.dirs.len() as u32;
+                listing.dirs.push(CompactDir(dir_id, name));
+                stack.push((p, id));
+            } else if p.is_file() {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                listing.files.push(CompactFile(dir_id, name, size));
+            }
+        }
+    }
+    Ok(listing)
+}
+
+pub fn rename_file(root: &str, relative_path: &str, new_name: &str) -> Result<(), String> {
+    rename_file_on(&RealFs, Path::new(root), relative_path, new_name)
+}
+
+/// Whether `path` would replace another file. A change of case alone is
+/// not a conflict, although the old name still answers to the new one on
+/// case-insensitive drives.
+fn taken_by_other(fs: &dyn FileSystem, path: &Path, current: &Path) -> bool {
+    if !fs.exists_no_follow(path) {
+        return false;
+    }
+    let (Some(name), Some(current_name)) = (path.file_name(), current.file_name()) else {
+        return true;
+    };
+    if path.parent() != current.parent()
+        || name.to_string_lossy().to_lowercase() != current_name.to_string_lossy().to_lowercase()
+    {
+        return true;
+    }
+    // Listed under exactly that name means a separate file on a case-sensitive drive
+    fs.read_dir(path.parent().unwrap_or(path))
+        .map(|entries| entries.iter().any(|e| e.file_name() == Some(name) && e != current))
+        .unwrap_or(true)
+}
+
+/// `rename_file` on any file system.
+pub fn rename_file_on(fs: &dyn FileSystem, root: &Path, relative_path: &str, new_name: &str) -> Result<(), String> {
+    // A name with separators or dots would put the file somewhere else
+    if new_name.is_empty() || new_name == "." || new_name == ".." || new_name.contains(['/', '\\']) {
+        return Err("Invalid file name".into());
+    }
+    let rel_norm = normalize_input_path(relative_path);
+    let abs_path = canonical_within_on(fs, root, &root.join(rel_norm))?;
+    if !fs.is_file(&abs_path) {
+        return Err("Target is not a file".into());
+    }
+    let parent = abs_path.parent().ok_or("File has no parent directory")?;
+    let new_path = parent.join(new_name);
+    if taken_by_other(fs, &new_path, &abs_path) {
+        return Err("A file with that name already exists".into());
+    }
+    fs.rename(&abs_path, &new_path)
+        .map_err(|e| format!("Rename failed: {e}"))?;
+    Ok(())
+}
+
+pub fn delete_file(root: &str, relative_path: &str) -> Result<(), String> {
+    delete_file_on(&RealFs, Path::new(root), relative_path)
+}
+
+/// `delete_file` on any file system.
+pub fn delete_file_on(fs: &dyn FileSystem, root: &Path, relative_path: &str) -> Result<(), String> {
+    let rel_norm = normalize_input_path(relative_path);
+    let abs_path = canonical_within_on(fs, root, &root.join(rel_norm))?;
+    if fs.is_file(&abs_path) {
+        fs.remove_file(&abs_path).map_err(|e| format!("Delete failed: {e}"))?;
+        Ok(())
+    } else {
+        Err("Only files can be deleted with this action".into())
+    }
+}
+
+pub fn move_file(root: &str, from_relative: &str, to_relative_dir: &str, create_dir: bool) -> Result<(), String> {
+    move_file_on(&RealFs, Path::new(root), from_relative, to_relative_dir, create_dir)
+}
+
+/// The canonical folder `to_relative_dir` names inside `root`, created
+/// first when missing and `create_dir` is set.
+pub fn destination_dir_on(
+    fs: &dyn FileSystem,
+    root: &Path,
+    to_relative_dir: &str,
+    create_dir: bool,
+) -> Result<PathBuf, String> {
+    let root_canon = fs.canonicalize(root).map_err(|e| format!("Invalid root: {e}"))?;
+
+    // Handle empty or root-relative paths
+    let to_relative_dir = to_relative_dir.trim();
+    let clean_path = to_relative_dir.trim_start_matches('/');
+    let dest_dir = if clean_path.is_empty() || clean_path == "." {
+        // Move to root directory
+        root_canon.clone()
+    } else {
+        root.join(normalize_input_path(clean_path))
+    };
+
+    // Validate destination is within root
+    let dest_canon = if fs.canonicalize(&dest_dir).is_ok() {
+        canonical_within_on(fs, root, &dest_dir)?
+    } else if create_dir {
+        // Nothing may be created outside root: the deepest existing folder
+        // must be inside it and the rest of the path may only go down
+        if Path::new(clean_path).components().any(|c| c == Component::ParentDir) {
+            return Err("Path escapes selected root".into());
+        }
+        let mut existing = dest_dir.as_path();
+        while fs.canonicalize(existing).is_err() {
+            existing = existing.parent().unwrap_or(root);
+        }
+        canonical_within_on(fs, root, existing)?;
+        fs.create_dir_all(&dest_dir)
+            .map_err(|e| format!("Failed to create dir: {e}"))?;
+        canonical_within_on(fs, root, &dest_dir).map_err(|e| format!("Failed to validate created dir: {e}"))?
+    } else {
+        return Err("Destination directory does not exist".into());
+    };
+    if !fs.is_dir(&dest_canon) {
+        return Err("Destination is not a directory".into());
+    }
+    Ok(dest_canon)
+}
+
+/// `move_file` on any file system. Never replaces a file in the destination.
+pub fn move_file_on(
+    fs: &dyn FileSystem,
+    root: &Path,
+    from_relative: &str,
+    to_relative_dir: &str,
+    create_dir: bool,
+) -> Result<(), String> {
+    let from_norm = normalize_input_path(from_relative);
+    let src_abs = canonical_within_on(fs, root, &root.join(from_norm))?;
+    if !fs.is_file(&src_abs) {
+        return Err("Source is not a file".into());
+    }
+    let dest_canon = destination_dir_on(fs, root, to_relative_dir, create_dir)?;
+
+    let file_name = src_abs.file_name().ok_or("Source file has no name")?;
+    let dest_abs = dest_canon.join(file_name);
+    if dest_abs == src_abs {
+        return Ok(());
+    }
+    if fs.exists_no_follow(&dest_abs) {
+        return Err("A file with that name already exists in the destination".into());
+    }
+
+    fs.rename(&src_abs, &dest_abs)
+        .map_err(|e| format!("Move failed: {e}"))?;
+    Ok(())
+}
+
+pub fn create_folder(root: &str, relative_dir: &str) -> Result<(), String> {
+    create_folder_on(&RealFs, Path::new(root), relative_dir)
+}
+
+/// `create_folder` on any file system.
+pub fn create_folder_on(fs: &dyn FileSystem, root: &Path, relative_dir: &str) -> Result<(), String> {
+    let rel_norm = normalize_input_path(relative_dir);
+    let target = root.join(rel_norm);
+    // Ensure target is within root (can't canonicalize new path before it's created, so validate parent)
+    let parent = target.parent().unwrap_or(root);
+    let _ = canonical_within_on(fs, root, parent)?;
+    fs.create_dir_all(&target)
+        .map_err(|e| format!("Create folder failed: {e}"))?;
+    Ok(())
+}
+
+/// Where a new link may go: inside root, in an existing folder, not taken yet.
+fn link_location(root: &Path, link_relative: &str) -> Result<PathBuf, String> {
+    let link = root.join(normalize_input_path(link_relative.trim_start_matches('/')));
+    let parent = canonical_within(root, link.parent().unwrap_or(root))?;
+    let name = link.file_name().ok_or("Invalid link name")?;
+    let link = parent.join(name);
+    if fs::symlink_metadata(&link).is_ok() {
+        return Err("Something with that name already exists".into());
+    }
+    Ok(link)
+}
+
+/// Symbolic link at `link_relative` pointing to `target_relative`. The link
+/// stores a relative path, so it keeps working when the drive is mounted
+/// elsewhere.
+pub fn create_symlink(root: &str, target_relative: &str, link_relative: &str) -> Result<(), String> {
+    let root = PathBuf::from(root);
+    let target = resolve_within(&root, target_relative)?;
+    let link = link_location(&root, link_relative)?;
+    let stored = relative_path(link.parent().unwrap_or(&link), &target);
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(&stored, &link);
+    #[cfg(windows)]
+    let result = if target.is_dir() {
+        std::os::windows::fs::symlink_dir(&stored, &link)
+    } else {
+        std::os::windows::fs::symlink_file(&stored, &link)
+    };
+    result.map_err(|e| format!("Create symlink failed: {e}"))
+}
+
+/// Hard link at `link_relative` to the file `target_relative`: a second name
+/// for the same data, on the same drive.
+pub fn create_hardlink(root: &str, target_relative: &str, link_relative: &str) -> Result<(), String> {
+    let root = PathBuf::from(root);
+    let target = resolve_within(&root, target_relative)?;
+    if !target.is_file() {
+        return Err("Hard links can only point to files".into());
+    }
+    let link = link_location(&root, link_relative)?;
+    fs::hard_link(&target, &link).map_err(|e| format!("Create hard link failed: {e}"))
+}
+
+/// Bucket of a name for the jump bar: its first letter uppercased, or one of
+/// `DIGITS_BUCKET` and `OTHER_BUCKET`.
+fn bucket_of(name: &str) -> String {
+    match name.chars().next() {
+        Some(c) if c.is_alphabetic() => c.to_uppercase().collect(),
+        Some(c) if c.is_numeric() => DIGITS_BUCKET.to_string(),
+        _ => OTHER_BUCKET.to_string(),
+    }
+}
+
+/// The entries of folder `dir` of `root` whose name starts with `prefix`,
+/// plus how many entries each bucket of the folder holds, for an A–Z jump
+/// bar over folders too big to list at once. `prefix` is matched
+/// case-insensitively; a bucket name (`#`, `*`) selects that whole bucket.
+/// Only the matching entries are stat'ed.
+pub fn list_children_sharded(
+    root: &str,
+    dir: &str,
+    prefix: &str,
+    show_hidden: bool,
+) -> Result<ShardedChildren, String> {
+    let root_canon = PathBuf::from(root)
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let dir_path = resolve_within(&root_canon, dir)?;
+    let read_dir = fs::read_dir(&dir_path).map_err(|e| format!("Failed to read dir {}: {e}", dir_path.display()))?;
+    let prefix_lower = prefix.to_lowercase();
+    let mut sharded = ShardedChildren::default();
+    for entry in read_dir.flatten() {
+        let p = entry.path();
+        if !show_hidden && is_hidden(&p) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let bucket = bucket_of(&name);
+        let wanted = if prefix == DIGITS_BUCKET || prefix == OTHER_BUCKET {
+            bucket == prefix
+        } else {
+            name.to_lowercase().starts_with(&prefix_lower)
+        };
+        *sharded.buckets.entry(bucket).or_default() += 1;
+        if !wanted {
+            continue;
+        }
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        let size = if is_dir {
+            0
+        } else {
+            entry.metadata().map(|m| m.len()).unwrap_or(0)
+        };
+        sharded.entries.push(ChildEntry {
+            name,
+            relative_path: relative_key(&root_canon, &p),
+            is_dir,
+            size,
+        });
+    }
+    sharded.entries.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+    Ok(sharded)
+}
+
+/// Recursive size of a mixed selection of files and folders. `on_progress`
+/// gets a running total every few thousand entries for huge folders.
+pub fn selection_size(
+    root: &str,
+    relative_paths: &[String],
+    on_progress: &dyn Fn(&SelectionSize),
+) -> Result<SelectionSize, String> {
+    let root = PathBuf::from(root);
+
+    let mut selected: Vec<PathBuf> = Vec::new();
+    for rel in relative_paths {
+        selected.push(resolve_within(&root, rel)?);
+    }
+    // Drop entries nested inside another selected folder so nothing is counted twice
+    selected.sort();
+    selected.dedup();
+    let mut tops: Vec<PathBuf> = Vec::new();
+    for p in selected {
+        if !tops.iter().any(|t| p.starts_with(t)) {
+            tops.push(p);
+        }
+    }
+
+    let mut total = SelectionSize {
+        items: tops.len(),
+        ..Default::default()
+    };
+    let mut seen: u64 = 0;
+    for top in tops {
+        let mut stack: Vec<PathBuf> = vec![top];
+        while let Some(p) = stack.pop() {
+            // Don't follow symlinks, a link to a parent folder would never finish
+            let meta = match fs::symlink_metadata(&p) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            if meta.is_dir() {
+                total.dirs += 1;
+                if let Ok(read_dir) = fs::read_dir(&p) {
+                    stack.extend(read_dir.flatten().map(|e| e.path()));
+                }
+            } else {
+                total.files += 1;
+                total.bytes += meta.len();
+            }
+            seen += 1;
+            if seen % SIZE_PROGRESS_EVERY == 0 {
+                on_progress(&total);
+            }
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    fn card() -> MemoryFs {
+        let fs = MemoryFs::new();
+        fs.file("/card/DCIM/a.jpg", 10)
+            .file("/card/DCIM/b.jpg", 20)
+            .dir("/card/Inbox")
+            .file("/home/secret.txt", 1)
+            .symlink("/card/escape", "/home");
+        fs
+    }
+
+    const ROOT: &str = "/card";
+
+    #[test]
+    fn rename_stays_in_its_folder() {
+        let fs = card();
+        rename_file_on(&fs, Path::new(ROOT), "DCIM/a.jpg", "c.jpg").unwrap();
+        assert!(fs.is_file(Path::new("/card/DCIM/c.jpg")));
+        for bad in ["", ".", "..", "../c.jpg", "sub/c.jpg", "..\\c.jpg"] {
+            assert!(rename_file_on(&fs, Path::new(ROOT), "DCIM/c.jpg", bad).is_err(), "{bad}");
+        }
+        assert!(fs.is_file(Path::new("/card/DCIM/c.jpg")));
+    }
+
+    #[test]
+    fn rename_never_replaces_a_file() {
+        let fs = card();
+        let err = rename_file_on(&fs, Path::new(ROOT), "DCIM/a.jpg", "b.jpg").unwrap_err();
+        assert!(err.contains("already exists"));
+        assert_eq!(fs.file_len(Path::new("/card/DCIM/b.jpg")).unwrap(), 20);
+    }
+
+    #[test]
+    fn rename_case_only_change() {
+        let fs = card();
+        rename_file_on(&fs, Path::new(ROOT), "DCIM/a.jpg", "A.jpg").unwrap();
+        assert!(fs.is_file(Path::new("/card/DCIM/A.jpg")));
+        // On a case-sensitive drive a.jpg and A.jpg can be two files
+        fs.file("/card/DCIM/a.jpg", 5);
+        assert!(rename_file_on(&fs, Path::new(ROOT), "DCIM/A.jpg", "a.jpg").is_err());
+        assert_eq!(fs.file_len(Path::new("/card/DCIM/a.jpg")).unwrap(), 5);
+    }
+
+    #[test]
+    fn paths_outside_root_are_refused() {
+        let fs = card();
+        let root = Path::new(ROOT);
+        assert!(delete_file_on(&fs, root, "../home/secret.txt").is_err());
+        assert!(delete_file_on(&fs, root, "escape/secret.txt").is_err());
+        assert!(rename_file_on(&fs, root, "escape/secret.txt", "x.txt").is_err());
+        assert!(move_file_on(&fs, root, "escape/secret.txt", "Inbox", false).is_err());
+        assert!(move_file_on(&fs, root, "DCIM/a.jpg", "escape", false).is_err());
+        assert!(move_file_on(&fs, root, "DCIM/a.jpg", "../home", false).is_err());
+        assert!(create_folder_on(&fs, root, "../home/new").is_err());
+        assert!(create_folder_on(&fs, root, "escape/new").is_err());
+        assert!(fs.is_file(Path::new("/home/secret.txt")));
+        assert!(!fs.is_dir(Path::new("/home/new")));
+    }
+
+    #[test]
+    fn delete_only_files() {
+        let fs = card();
+        assert!(delete_file_on(&fs, Path::new(ROOT), "Inbox").is_err());
+        delete_file_on(&fs, Path::new(ROOT), "DCIM/a.jpg").unwrap();
+        assert_eq!(fs.files(), vec!["/card/DCIM/b.jpg", "/home/secret.txt"]);
+    }
+
+    #[test]
+    fn move_into_existing_and_root_folders() {
+        let fs = card();
+        move_file_on(&fs, Path::new(ROOT), "DCIM/a.jpg", "/Inbox", false).unwrap();
+        move_file_on(&fs, Path::new(ROOT), "DCIM/b.jpg", "", false).unwrap();
+        assert!(fs.is_file(Path::new("/card/Inbox/a.jpg")));
+        assert!(fs.is_file(Path::new("/card/b.jpg")));
+        // Moving a file where it already is changes nothing
+        move_file_on(&fs, Path::new(ROOT), "b.jpg", ".", false).unwrap();
+        assert!(fs.is_file(Path::new("/card/b.jpg")));
+    }
+
+    #[test]
+    fn move_never_replaces_a_file() {
+        let fs = card();
+        fs.file("/card/Inbox/a.jpg", 99);
+        assert!(move_file_on(&fs, Path::new(ROOT), "DCIM/a.jpg", "Inbox", false).is_err());
+        assert_eq!(fs.file_len(Path::new("/card/Inbox/a.jpg")).unwrap(), 99);
+        assert!(fs.is_file(Path::new("/card/DCIM/a.jpg")));
+    }
+
+    #[test]
+    fn move_creates_folders_only_when_asked_and_only_inside_root() {
+        let fs = card();
+        let root = Path::new(ROOT);
+        assert!(move_file_on(&fs, root, "DCIM/a.jpg", "2024/05", false).is_err());
+        move_file_on(&fs, root, "DCIM/a.jpg", "2024/05", true).unwrap();
+        assert!(fs.is_file(Path::new("/card/2024/05/a.jpg")));
+        assert!(move_file_on(&fs, root, "DCIM/b.jpg", "new/../../outside", true).is_err());
+        assert!(move_file_on(&fs, root, "DCIM/b.jpg", "escape/new", true).is_err());
+        assert!(!fs.is_dir(Path::new("/outside")));
+        assert!(!fs.is_dir(Path::new("/home/new")));
+        assert!(!fs.is_dir(Path::new("/card/new")));
+        assert!(fs.is_file(Path::new("/card/DCIM/b.jpg")));
+    }
+
+    #[test]
+    fn move_into_a_file_fails() {
+        let fs = card();
+        assert!(move_file_on(&fs, Path::new(ROOT), "DCIM/a.jpg", "DCIM/b.jpg", true).is_err());
+        assert!(fs.is_file(Path::new("/card/DCIM/a.jpg")));
+    }
+}
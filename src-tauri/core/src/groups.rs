@@ -0,0 +1,369 @@
+// Sidecar groups: files in one folder that share a stem, like a camera's
+// IMG_0001.CR2 + IMG_0001.JPG + IMG_0001.CR2.xmp or movie.mkv + movie.en.srt.
+// They are usually meant to travel together, so they can be renamed, moved
+// and deleted as a unit.
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::fsops::{destination_dir_on, FileEntry};
+use crate::paths::{relative_key, resolve_within_on};
+use crate::store::Store;
+use crate::trash;
+use crate::vfs::{FileSystem, RealFs};
+
+// Extensions of sidecars named after the whole file they describe, and the
+// inner extensions they may carry: `IMG_0001.CR2.xmp` belongs to `IMG_0001`
+const SIDECAR_OF_IMAGE: &[&str] = &["xmp", "aae", "pp3", "dop"];
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "heic", "heif", "tif", "tiff", "png", "dng", "cr2", "cr3", "crw", "nef", "nrw", "arw", "srf", "sr2",
+    "raf", "orf", "rw2", "pef", "srw", "x3f", "3fr", "iiq", "erf", "kdc", "mos", "mrw",
+];
+// Subtitles carry a language code instead: `movie.en.srt` belongs to `movie`
+const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "vtt", "sub", "idx"];
+
+fn is_double_extension(inner: &str, outer: &str) -> bool {
+    let inner = inner.to_ascii_lowercase();
+    let outer = outer.to_ascii_lowercase();
+    if SIDECAR_OF_IMAGE.contains(&outer.as_str()) {
+        IMAGE_EXTENSIONS.contains(&inner.as_str())
+    } else if SUBTITLE_EXTENSIONS.contains(&outer.as_str()) {
+        (2..=3).contains(&inner.len()) && inner.chars().all(|c| c.is_ascii_alphabetic())
+    } else {
+        false
+    }
+}
+
+/// `name` split into its stem and its extensions: the last one, plus the
+/// inner one of a known sidecar double extension. The extensions part keeps
+/// its leading dot and is empty when there is none.
+fn split_stem(name: &str) -> (&str, &str) {
+    let Some((base, outer)) = name.rsplit_once('.') else {
+        return (name, "");
+    };
+    let stem = match base.rsplit_once('.') {
+        Some((stem, inner)) if !stem.is_empty() && is_double_extension(inner, outer) => stem,
+        _ => base,
+    };
+    name.split_at(stem.len())
+}
+
+/// What files of a group have in common: the name without its extension
+/// (see `split_stem`), lowercased. Names without an extension and dot files
+/// have none.
+pub fn group_key(name: &str) -> Option<String> {
+    match split_stem(name) {
+        (stem, extensions) if !stem.is_empty() && !extensions.is_empty() => Some(stem.to_lowercase()),
+        _ => None,
+    }
+}
+
+/// Id of the group a file would belong to: its folder plus the group key,
+/// e.g. `DCIM/100CANON/img_0001`.
+pub fn group_id(relative_path: &str) -> Option<String> {
+    let (dir, name) = match relative_path.rsplit_once('/') {
+        Some((dir, name)) => (dir, name),
+        None => ("", relative_path),
+    };
+    let key = group_key(name)?;
+    Some(if dir.is_empty() { key } else { format!("{dir}/{key}") })
+}
+
+/// Ids shared by more than one of `relative_paths`, i.e. actual groups.
+pub fn shared_ids<'a>(relative_paths: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for rel in relative_paths {
+        if let Some(id) = group_id(rel) {
+            *counts.entry(id).or_default() += 1;
+        }
+    }
+    counts.into_iter().filter(|(_, n)| *n > 1).map(|(id, _)| id).collect()
+}
+
+/// Fill in `group` for entries that have siblings sharing their stem.
+pub fn tag_groups(entries: &mut [FileEntry]) {
+    let shared = shared_ids(entries.iter().map(|e| e.relative_path.as_str()));
+    for entry in entries.iter_mut() {
+        entry.group = group_id(&entry.relative_path).filter(|id| shared.contains(id));
+    }
+}
+
+/// The file at `relative_path` and every sibling in its group, sorted.
+fn members(fs: &dyn FileSystem, root: &Path, relative_path: &str) -> Result<Vec<PathBuf>, String> {
+    let file = resolve_within_on(fs, root, relative_path)?;
+    if !fs.is_file(&file) {
+        return Err("Target is not a file".into());
+    }
+    let name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let Some(key) = group_key(&name) else {
+        return Ok(vec![file]);
+    };
+    let dir = file.parent().ok_or("File has no parent directory")?;
+    let entries = fs
+        .read_dir(dir)
+        .map_err(|e| format!("Failed to read dir {}: {e}", dir.display()))?;
+    let mut out: Vec<PathBuf> = entries
+        .into_iter()
+        .filter(|p| fs.is_file(p))
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            group_key(&name).as_deref() == Some(key.as_str())
+        })
+        .collect();
+    out.sort();
+    Ok(out)
+}
+
+fn canonical_root(fs: &dyn FileSystem, root: &Path) -> Result<PathBuf, String> {
+    fs.canonicalize(root).map_err(|e| format!("Invalid root: {e}"))
+}
+
+/// Relative paths of the files grouped with `relative_path`, itself included.
+pub fn file_group(root: &str, relative_path: &str) -> Result<Vec<String>, String> {
+    let root_canon = canonical_root(&RealFs, Path::new(root))?;
+    Ok(members(&RealFs, &root_canon, relative_path)?
+        .iter()
+        .map(|p| relative_key(&root_canon, p))
+        .collect())
+}
+
+/// Rename every `(from, to)` pair or none: targets are checked up front, and
+/// if a rename fails halfway the ones already done are reverted.
+fn rename_all(fs: &dyn FileSystem, pairs: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+    let sources: HashSet<&PathBuf> = pairs.iter().map(|(from, _)| from).collect();
+    for (_, to) in pairs {
+        // A target may be a member itself, e.g. when only the case changes
+        if fs.exists_no_follow(to) && !sources.contains(to) {
+            return Err(format!("{} already exists", to.display()));
+        }
+    }
+    for (i, (from, to)) in pairs.iter().enumerate() {
+        if let Err(e) = fs.rename(from, to) {
+            for (from, to) in pairs[..i].iter().rev() {
+                let _ = fs.rename(to, from);
+            }
+            return Err(format!("Failed to rename {}: {e}", from.display()));
+        }
+    }
+    Ok(())
+}
+
+pub fn rename_group(root: &str, relative_path: &str, new_name: &str) -> Result<Vec<String>, String> {
+    rename_group_on(&RealFs, Path::new(root), relative_path, new_name)
+}
+
+/// Rename a file and its group: `new_name` is the file's new name and the
+/// other members get the same new stem with their own extensions. Returns
+/// the new relative paths.
+pub fn rename_group_on(
+    fs: &dyn FileSystem,
+    root: &Path,
+    relative_path: &str,
+    new_name: &str,
+) -> Result<Vec<String>, String> {
+    let new_stem = split_stem(new_name).0;
+    if new_stem.is_empty() || new_name.contains(['/', '\\']) {
+        return Err("Invalid file name".into());
+    }
+    let root_canon = canonical_root(fs, root)?;
+    let members = members(fs, &root_canon, relative_path)?;
+    let target = resolve_within_on(fs, &root_canon, relative_path)?;
+    let pairs: Vec<(PathBuf, PathBuf)> = members
+        .into_iter()
+        .map(|from| {
+            let name = from.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let to_name = if from == target {
+                new_name.to_string()
+            } else {
+                format!("{new_stem}{}", split_stem(&name).1)
+            };
+            let to = from.with_file_name(to_name);
+            (from, to)
+        })
+        .collect();
+    rename_all(fs, &pairs)?;
+    Ok(pairs.iter().map(|(_, to)| relative_key(&root_canon, to)).collect())
+}
+
+pub fn move_group(
+    root: &str,
+    from_relative: &str,
+    to_relative_dir: &str,
+    create_dir: bool,
+) -> Result<Vec<String>, String> {
+    move_group_on(&RealFs, Path::new(root), from_relative, to_relative_dir, create_dir)
+}
+
+/// Move a file and its group into `to_relative_dir`. Returns the new
+/// relative paths.
+pub fn move_group_on(
+    fs: &dyn FileSystem,
+    root: &Path,
+    from_relative: &str,
+    to_relative_dir: &str,
+    create_dir: bool,
+) -> Result<Vec<String>, String> {
+    let root_canon = canonical_root(fs, root)?;
+    let members = members(fs, &root_canon, from_relative)?;
+    let dest = destination_dir_on(fs, &root_canon, to_relative_dir, create_dir)?;
+    let pairs: Vec<(PathBuf, PathBuf)> = members
+        .into_iter()
+        .map(|from| {
+            let to = dest.join(from.file_name().unwrap_or_default());
+            (from, to)
+        })
+        .filter(|(from, to)| from != to)
+        .collect();
+    rename_all(fs, &pairs)?;
+    Ok(pairs.iter().map(|(_, to)| relative_key(&root_canon, to)).collect())
+}
+
+pub fn delete_group(root: &str, relative_path: &str) -> Result<Vec<String>, String> {
+    delete_group_on(&RealFs, Path::new(root), relative_path)
+}
+
+/// Delete a file and its group. Members are first set aside under hidden
+/// names, so a failure before anything is gone leaves the group intact.
+/// Returns the deleted relative paths.
+pub fn delete_group_on(fs: &dyn FileSystem, root: &Path, relative_path: &str) -> Result<Vec<String>, String> {
+    let root_canon = canonical_root(fs, root)?;
+    let pairs: Vec<(PathBuf, PathBuf)> = members(fs, &root_canon, relative_path)?
+        .into_iter()
+        .map(|from| {
+            let name = from.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let staged = from.with_file_name(format!(".{name}.lfm-delete"));
+            (from, staged)
+        })
+        .collect();
+    rename_all(fs, &pairs)?;
+    let mut deleted = Vec::new();
+    for (from, staged) in &pairs {
+        fs.remove_file(staged).map_err(|e| format!("Delete failed: {e}"))?;
+        deleted.push(relative_key(&root_canon, from));
+    }
+    Ok(deleted)
+}
+
+/// Move a file and its group into the trash of `root`; returns their
+/// relative paths. Members trashed before a failure stay in the trash.
+pub fn trash_group(store: &Store, root: &str, relative_path: &str) -> Result<Vec<String>, String> {
+    let members = file_group(root, relative_path)?;
+    for rel in &members {
+        trash::trash_file(store, root, rel)?;
+    }
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    const ROOT: &str = "/card";
+
+    fn shoot() -> MemoryFs {
+        let fs = MemoryFs::new();
+        fs.file("/card/DCIM/IMG_0001.CR2", 30)
+            .file("/card/DCIM/IMG_0001.JPG", 10)
+            .file("/card/DCIM/IMG_0001.CR2.xmp", 1)
+            .file("/card/DCIM/IMG_0002.JPG", 10)
+            .dir("/card/Keep");
+        fs
+    }
+
+    #[test]
+    fn keys_and_ids() {
+        assert_eq!(group_key("IMG_0001.CR2.xmp").as_deref(), Some("img_0001"));
+        assert_eq!(group_key("movie.en.srt").as_deref(), Some("movie"));
+        assert_eq!(group_key(".hidden"), None);
+        assert_eq!(group_key("README"), None);
+        assert_eq!(group_key("report.v1.docx").as_deref(), Some("report.v1"));
+        assert_eq!(group_key("Doc.final.pdf").as_deref(), Some("doc.final"));
+        assert_eq!(group_id("DCIM/IMG_0001.JPG").as_deref(), Some("DCIM/img_0001"));
+        assert_eq!(group_id("movie.mkv").as_deref(), Some("movie"));
+        let shared = shared_ids(["a/x.jpg", "a/x.cr2", "b/x.jpg", "a/y.jpg"]);
+        assert_eq!(shared.into_iter().collect::<Vec<_>>(), vec!["a/x".to_string()]);
+    }
+
+    #[test]
+    fn dotted_stems_are_not_one_group() {
+        let shared = shared_ids(["report.v1.docx", "report.v2.docx", "Doc.final.pdf", "Doc.draft.pdf"]);
+        assert!(shared.is_empty());
+        let shared = shared_ids(["movie.en.srt", "movie.mkv", "clip.CR3.xmp", "clip.CR3"]);
+        let mut shared: Vec<_> = shared.into_iter().collect();
+        shared.sort();
+        assert_eq!(shared, vec!["clip".to_string(), "movie".to_string()]);
+    }
+
+    #[test]
+    fn rename_gives_members_the_new_stem() {
+        let fs = shoot();
+        let renamed = rename_group_on(&fs, Path::new(ROOT), "DCIM/IMG_0001.JPG", "Sunset.jpg").unwrap();
+        assert_eq!(
+            renamed,
+            vec!["DCIM/Sunset.CR2", "DCIM/Sunset.CR2.xmp", "DCIM/Sunset.jpg"]
+        );
+        assert!(fs.is_file(Path::new("/card/DCIM/IMG_0002.JPG")));
+        assert!(rename_group_on(&fs, Path::new(ROOT), "DCIM/Sunset.jpg", ".jpg").is_err());
+        assert!(rename_group_on(&fs, Path::new(ROOT), "DCIM/Sunset.jpg", "../x.jpg").is_err());
+    }
+
+    #[test]
+    fn rename_conflict_changes_nothing() {
+        let fs = shoot();
+        fs.file("/card/DCIM/IMG_0002.CR2", 30);
+        let before = fs.files();
+        assert!(rename_group_on(&fs, Path::new(ROOT), "DCIM/IMG_0001.JPG", "IMG_0002.JPG").is_err());
+        assert_eq!(fs.files(), before);
+    }
+
+    #[test]
+    fn failed_rename_is_rolled_back() {
+        let fs = shoot();
+        fs.deny("/card/DCIM/IMG_0001.CR2.xmp");
+        let before = fs.files();
+        assert!(rename_group_on(&fs, Path::new(ROOT), "DCIM/IMG_0001.JPG", "Sunset.jpg").is_err());
+        assert_eq!(fs.files(), before);
+    }
+
+    #[test]
+    fn move_takes_the_whole_group() {
+        let fs = shoot();
+        let moved = move_group_on(&fs, Path::new(ROOT), "DCIM/IMG_0001.CR2", "Keep", false).unwrap();
+        assert_eq!(moved.len(), 3);
+        assert_eq!(
+            fs.files(),
+            vec![
+                "/card/DCIM/IMG_0002.JPG",
+                "/card/Keep/IMG_0001.CR2",
+                "/card/Keep/IMG_0001.CR2.xmp",
+                "/card/Keep/IMG_0001.JPG",
+            ]
+        );
+    }
+
+    #[test]
+    fn move_conflict_or_escape_changes_nothing() {
+        let fs = shoot();
+        fs.file("/card/Keep/IMG_0001.CR2.xmp", 2).symlink("/card/out", "/tmp");
+        fs.dir("/tmp");
+        let before = fs.files();
+        assert!(move_group_on(&fs, Path::new(ROOT), "DCIM/IMG_0001.JPG", "Keep", false).is_err());
+        assert!(move_group_on(&fs, Path::new(ROOT), "DCIM/IMG_0001.JPG", "out", false).is_err());
+        assert!(move_group_on(&fs, Path::new(ROOT), "DCIM/IMG_0001.JPG", "../tmp", true).is_err());
+        assert_eq!(fs.files(), before);
+    }
+
+    #[test]
+    fn delete_is_all_or_nothing() {
+        let fs = shoot();
+        fs.deny("/card/DCIM/IMG_0001.JPG");
+        let before = fs.files();
+        assert!(delete_group_on(&fs, Path::new(ROOT), "DCIM/IMG_0001.CR2").is_err());
+        assert_eq!(fs.files(), before);
+
+        let fs = shoot();
+        let deleted = delete_group_on(&fs, Path::new(ROOT), "DCIM/IMG_0001.CR2").unwrap();
+        assert_eq!(deleted.len(), 3);
+        assert_eq!(fs.files(), vec!["/card/DCIM/IMG_0002.JPG"]);
+    }
+}
@@ -0,0 +1,110 @@
+// Rules deletions have to pass, enforced here rather than trusted to the
+// front end: e.g. never delete RAW originals, or ask again before deleting
+// anything over 1 GB.
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::paths::resolve_within;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardAction {
+    // refuse outright
+    Block,
+    // refuse unless the caller passes `confirmed`
+    Confirm,
+}
+
+/// A rule matching files by extension and/or size; a file matches when it
+/// meets every condition that is set.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DeleteGuard {
+    // without the dot, case-insensitive; empty matches any extension
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub min_size_bytes: Option<u64>,
+    pub action: GuardAction,
+}
+
+pub fn validate(guard: &DeleteGuard) -> Result<(), String> {
+    if guard.extensions.is_empty() && guard.min_size_bytes.is_none() {
+        return Err("A deletion rule needs extensions or a minimum size".into());
+    }
+    if guard
+        .extensions
+        .iter()
+        .any(|e| e.trim().is_empty() || e.contains(['.', '/', '\\']))
+    {
+        return Err("Deletion rule extensions are written without the dot, e.g. dng".into());
+    }
+    Ok(())
+}
+
+fn matches(guard: &DeleteGuard, path: &Path, size: u64) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let ext_ok = guard.extensions.is_empty() || guard.extensions.iter().any(|e| e.to_lowercase() == ext);
+    let size_ok = guard.min_size_bytes.is_none_or(|min| size >= min);
+    ext_ok && size_ok
+}
+
+fn describe(guard: &DeleteGuard) -> String {
+    let mut parts = Vec::new();
+    if !guard.extensions.is_empty() {
+        parts.push(format!(".{}", guard.extensions.join(", .")));
+    }
+    if let Some(min) = guard.min_size_bytes {
+        parts.push(format!("{:.1} GB or larger", min as f64 / 1e9));
+    }
+    parts.join(", ")
+}
+
+/// Check the deletion of `paths` against `guards`. Blocking rules always
+/// win; confirmation rules pass once `confirmed` is set.
+pub fn check_delete(guards: &[DeleteGuard], paths: &[&Path], confirmed: bool) -> Result<(), String> {
+    let mut needs_confirmation = None;
+    for path in paths {
+        let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+        for guard in guards.iter().filter(|g| matches(g, path, size)) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            match guard.action {
+                GuardAction::Block => {
+                    return Err(format!("{name} is protected from deletion ({})", describe(guard)));
+                }
+                GuardAction::Confirm if !confirmed && needs_confirmation.is_none() => {
+                    needs_confirmation = Some(format!(
+                        "Deleting {name} needs confirmation ({}); confirm to go ahead anyway",
+                        describe(guard)
+                    ));
+                }
+                GuardAction::Confirm => {}
+            }
+        }
+    }
+    match needs_confirmation {
+        Some(message) => Err(message),
+        None => Ok(()),
+    }
+}
+
+/// `check_delete` for `relative_paths` of `root`.
+pub fn check_delete_within(
+    guards: &[DeleteGuard],
+    root: &str,
+    relative_paths: &[String],
+    confirmed: bool,
+) -> Result<(), String> {
+    if guards.is_empty() {
+        return Ok(());
+    }
+    // Paths that don't resolve fail in the deletion itself
+    let paths: Vec<PathBuf> = relative_paths
+        .iter()
+        .filter_map(|rel| resolve_within(Path::new(root), rel).ok())
+        .collect();
+    let paths: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+    check_delete(guards, &paths, confirmed)
+}
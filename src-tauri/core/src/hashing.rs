@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::Progress;
+
+const HASH_BUF_SIZE: usize = 1024 * 1024;
+// Files at least this big on local drives are memory-mapped and hashed on all cores
+const MMAP_THRESHOLD: u64 = 64 * 1024 * 1024;
+// Progress granularity for memory-mapped files
+const MMAP_CHUNK: usize = 16 * 1024 * 1024;
+// How often the pool re-evaluates its worker count
+const ADAPT_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_ADAPTIVE_THREADS: usize = 16;
+
+static BLOCK_SIZE: AtomicUsize = AtomicUsize::new(HASH_BUF_SIZE);
+
+/// Read files in blocks of `bytes` when hashing them; None restores the
+/// default of 1 MiB. Small blocks suit SD cards, large ones spinning disks.
+pub fn set_block_size(bytes: Option<usize>) {
+    BLOCK_SIZE.store(bytes.unwrap_or(HASH_BUF_SIZE), Ordering::Relaxed);
+}
+
+#[cfg(target_os = "linux")]
+fn unescape_mount_field(s: &str) -> String {
+    // /proc/mounts escapes space, tab, newline and backslash as \ooo
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() && bytes[i + 1..i + 4].iter().all(|b| (b'0'..=b'7').contains(b)) {
+            let v = bytes[i + 1..i + 4].iter().fold(0u32, |acc, b| acc * 8 + (b - b'0') as u32);
+            out.push(v as u8);
+            i += 4;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Whether `path` lives on a local file system. Mapping files on network
+/// mounts is slower than reading them and can fault if the share drops.
+#[cfg(target_os = "linux")]
+fn is_local_fs(path: &Path) -> bool {
+    const NETWORK_FS: [&str; 7] = ["nfs", "nfs4", "cifs", "smb3", "smbfs", "fuse.sshfs", "9p"];
+    let mounts = match fs::read_to_string("/proc/self/mounts") {
+        Ok(m) => m,
+        Err(_) => return false,
+    };
+    let mut best: Option<(usize, String)> = None;
+    for line in mounts.lines() {
+        let mut fields = line.split(' ');
+        let (_, mount_point, fs_type) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(d), Some(m), Some(t)) => (d, unescape_mount_field(m), t),
+            _ => continue,
+        };
+        if path.starts_with(&mount_point) && !matches!(&best, Some((len, _)) if mount_point.len() < *len) {
+            best = Some((mount_point.len(), fs_type.to_string()));
+        }
+    }
+    match best {
+        Some((_, fs_type)) => !NETWORK_FS.contains(&fs_type.as_str()),
+        None => false,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn is_local_fs(path: &Path) -> bool {
+    // Canonical paths look like \\?\C:\... locally and \\?\UNC\server\... on shares
+    let p = path.to_string_lossy();
+    if let Some(rest) = p.strip_prefix(r"\\?\") {
+        return !rest.starts_with(r"UNC\");
+    }
+    !p.starts_with(r"\\")
+}
+
+#[cfg(all(not(target_os = "linux"), not(target_os = "windows")))]
+fn is_local_fs(_path: &Path) -> bool {
+    true
+}
+
+/// BLAKE3 of a file's contents as lowercase hex.
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    hash_file_with(path, &|_, _| {})
+}
+
+/// `hash_file`, reporting (bytes read, file size) as the file is read.
+pub fn hash_file_with(path: &Path, on_read: Progress) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut read = 0u64;
+    if len >= MMAP_THRESHOLD && is_local_fs(path) {
+        // Same caveat as blake3's own update_mmap: a file truncated while
+        // mapped faults instead of returning an error
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        for chunk in map.chunks(MMAP_CHUNK) {
+            hasher.update_rayon(chunk);
+            read += chunk.len() as u64;
+            on_read(read, len);
+        }
+        return Ok(hasher.finalize().to_hex().to_string());
+    }
+
+    let mut buf = vec![0u8; BLOCK_SIZE.load(Ordering::Relaxed)];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        read += n as u64;
+        on_read(read, len);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+#[derive(Hash, PartialEq, Eq, Clone)]
+struct CacheKey {
+    volume: String,
+    path: PathBuf,
+    size: u64,
+    mtime_ms: i64,
+}
+
+/// Session-wide cache of file hashes shared by every feature that hashes
+/// content. A file is only re-hashed once its size or mtime changes.
+#[derive(Default)]
+pub struct HashCache {
+    hashes: Mutex<HashMap<CacheKey, String>>,
+    // st_dev -> filesystem UUID, resolving it means scanning /dev/disk
+    #[cfg(unix)]
+    volumes: Mutex<HashMap<u64, String>>,
+}
+
+impl HashCache {
+    #[cfg(target_os = "linux")]
+    fn volume_id(&self, _path: &Path, meta: &fs::Metadata) -> String {
+        use std::os::unix::fs::MetadataExt;
+        let dev = meta.dev();
+        let mut volumes = self.volumes.lock().unwrap();
+        if let Some(v) = volumes.get(&dev) {
+            return v.clone();
+        }
+        let mut id = format!("dev:{dev}");
+        if let Ok(read_dir) = fs::read_dir("/dev/disk/by-uuid") {
+            for e in read_dir.flatten() {
+                // metadata() follows the symlink to the block device node
+                if let Ok(m) = fs::metadata(e.path()) {
+                    if m.rdev() == dev {
+                        id = e.file_name().to_string_lossy().to_string();
+                        break;
+                    }
+                }
+            }
+        }
+        volumes.insert(dev, id.clone());
+        id
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    fn volume_id(&self, _path: &Path, meta: &fs::Metadata) -> String {
+        use std::os::unix::fs::MetadataExt;
+        format!("dev:{}", meta.dev())
+    }
+
+    #[cfg(not(unix))]
+    fn volume_id(&self, path: &Path, _meta: &fs::Metadata) -> String {
+        // Drive prefix, e.g. "E:"; volume serials need unstable APIs
+        path.components()
+            .next()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    fn key(&self, path: &Path, meta: &fs::Metadata) -> CacheKey {
+        CacheKey {
+            volume: self.volume_id(path, meta),
+            path: path.to_path_buf(),
+            size: meta.len(),
+            mtime_ms: crate::index::mtime_ms(meta),
+        }
+    }
+
+    /// Hash of `path`, computed at most once per (volume, path, size, mtime).
+    pub fn hash(&self, path: &Path) -> io::Result<String> {
+        self.hash_with(path, &|_, _| {})
+    }
+
+    /// `hash`, passing `on_read` to `hash_file_with` when the file is read.
+    pub fn hash_with(&self, path: &Path, on_read: Progress) -> io::Result<String> {
+        let meta = fs::metadata(path)?;
+        let key = self.key(path, &meta);
+        if let Some(h) = self.hashes.lock().unwrap().get(&key) {
+            return Ok(h.clone());
+        }
+        let h = hash_file_with(path, on_read)?;
+        self.hashes.lock().unwrap().insert(key, h.clone());
+        Ok(h)
+    }
+
+    /// Record a hash computed elsewhere (e.g. while copying) for later reuse.
+    pub fn insert(&self, path: &Path, hash: &str) {
+        if let Ok(meta) = fs::metadata(path) {
+            let key = self.key(path, &meta);
+            self.hashes.lock().unwrap().insert(key, hash.to_string());
+        }
+    }
+}
+
+/// Hash `jobs` (path, size) on a worker pool and return results in input order.
+/// `hash` reports the bytes it reads through its progress callback, which is
+/// what the pool measures its throughput by.
+///
+/// With `threads` set the pool runs exactly that many workers. Otherwise it
+/// starts with one and keeps adding workers while throughput improves, backing
+/// off when it drops: an SSD ends up with as many hashers as there are cores,
+/// a spinning disk stays near one or two readers instead of seeking itself to
+/// death.
+pub fn hash_parallel(
+    jobs: &[(PathBuf, u64)],
+    threads: Option<usize>,
+    hash: &(dyn Fn(&Path, Progress) -> io::Result<String> + Sync),
+    on_progress: &(dyn Fn(u64) + Sync),
+) -> Vec<io::Result<String>> {
+    let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    let max = threads.unwrap_or(MAX_ADAPTIVE_THREADS.min(cores * 2)).max(1);
+    let limit = AtomicUsize::new(if threads.is_some() { max } else { 1 });
+    let next = AtomicUsize::new(0);
+    let done = AtomicU64::new(0);
+    let bytes = AtomicU64::new(0);
+    let results: Mutex<Vec<Option<io::Result<String>>>> =
+        Mutex::new((0..jobs.len()).map(|_| None).collect());
+
+    thread::scope(|s| {
+        for id in 0..max {
+            let (limit, next, done, bytes, results) = (&limit, &next, &done, &bytes, &results);
+            s.spawn(move || loop {
+                if next.load(Ordering::Relaxed) >= jobs.len() {
+                    break;
+                }
+                if id >= limit.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(20));
+                    continue;
+                }
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= jobs.len() {
+                    break;
+                }
+                let (path, _) = &jobs[i];
+                let counted = AtomicU64::new(0);
+                let r = hash(path, &|read, _| {
+                    let before = counted.swap(read, Ordering::Relaxed);
+                    bytes.fetch_add(read.saturating_sub(before), Ordering::Relaxed);
+                });
+                results.lock().unwrap()[i] = Some(r);
+                on_progress(done.fetch_add(1, Ordering::Relaxed) + 1);
+            });
+        }
+
+        if threads.is_none() {
+            let mut best_rate = 0.0f64;
+            let mut last_bytes = 0u64;
+            let mut last_tick = Instant::now();
+            while next.load(Ordering::Relaxed) < jobs.len() {
+                thread::sleep(ADAPT_INTERVAL);
+                let now_bytes = bytes.load(Ordering::Relaxed);
+                let rate = (now_bytes - last_bytes) as f64 / last_tick.elapsed().as_secs_f64();
+                last_bytes = now_bytes;
+                last_tick = Instant::now();
+                let current = limit.load(Ordering::Relaxed);
+                if rate > best_rate * 1.1 {
+                    // Still scaling, try one more worker
+                    best_rate = rate;
+                    if current < max {
+                        limit.store(current + 1, Ordering::Relaxed);
+                    }
+                } else if rate < best_rate * 0.8 && current > 1 {
+                    // Contention (typically seeking), back off
+                    limit.store(current - 1, Ordering::Relaxed);
+                    best_rate = rate;
+                }
+            }
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.unwrap_or_else(|| Err(io::Error::other("not hashed"))))
+        .collect()
+}
@@ -0,0 +1,171 @@
+// User-configured shell hooks around operations. Each hook gets the
+// operation's details as JSON on stdin; a failing pre_* hook vetoes the
+// operation, post_* hooks run in the background and can't affect it.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::settings;
+use crate::store::Store;
+
+pub const OPERATIONS: [&str; 5] = ["move", "rename", "delete", "scan", "sync"];
+
+// A hook still running after this is killed and counts as failed
+const HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Post hooks still running, so a short-lived process can wait for them
+static PENDING: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Hook {
+    // "pre_<operation>" or "post_<operation>"
+    pub event: String,
+    // run through the platform shell
+    pub command: String,
+}
+
+pub fn validate(hook: &Hook) -> Result<(), String> {
+    let op = hook
+        .event
+        .strip_prefix("pre_")
+        .or_else(|| hook.event.strip_prefix("post_"))
+        .unwrap_or("");
+    if !OPERATIONS.contains(&op) {
+        return Err(format!("Unknown hook event: {}", hook.event));
+    }
+    if hook.command.trim().is_empty() {
+        return Err(format!("Hook for {} has no command", hook.event));
+    }
+    Ok(())
+}
+
+fn run_hook(hook: &Hook, input: &Value) -> Result<(), String> {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(&hook.command);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(&hook.command);
+        c
+    };
+    let mut child = cmd
+        .env("LFM_EVENT", &hook.event)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {} hook: {e}", hook.event))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores stdin closes the pipe early, that's fine
+        let _ = stdin.write_all(input.to_string().as_bytes());
+    }
+    let mut stderr = child.stderr.take();
+    let reader = thread::spawn(move || {
+        let mut out = String::new();
+        if let Some(stderr) = stderr.as_mut() {
+            let _ = stderr.read_to_string(&mut out);
+        }
+        out
+    });
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= HOOK_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "{} hook timed out after {} seconds",
+                    hook.event,
+                    HOOK_TIMEOUT.as_secs()
+                ));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("Failed to run {} hook: {e}", hook.event)),
+        }
+    };
+    if !status.success() {
+        let stderr = reader.join().unwrap_or_default();
+        return Err(format!("{} hook failed ({status}): {}", hook.event, stderr.trim()));
+    }
+    Ok(())
+}
+
+/// Wait for post hooks started so far. Called before a short-lived process
+/// such as the CLI exits, which would otherwise cut them off.
+pub fn wait_for_post_hooks() {
+    let pending = std::mem::take(&mut *PENDING.lock().unwrap());
+    for handle in pending {
+        let _ = handle.join();
+    }
+}
+
+/// Run `f` wrapped in the pre/post hooks configured for `operation`.
+/// `details` describes the operation and is passed to every hook, post hooks
+/// additionally get the outcome under "result". The outcome is logged too.
+pub fn around<T: Serialize>(
+    store: &Store,
+    operation: &str,
+    details: Value,
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    tracing::debug!(operation, %details, "starting");
+    let logged = details.clone();
+    let result = with_hooks(store, operation, details, f);
+    match &result {
+        Ok(_) => tracing::info!(operation, details = %logged, "done"),
+        Err(e) => tracing::warn!(operation, details = %logged, error = %e, "failed"),
+    }
+    result
+}
+
+fn with_hooks<T: Serialize>(
+    store: &Store,
+    operation: &str,
+    details: Value,
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    let hooks = settings::load(store)?.hooks;
+    if hooks.is_empty() {
+        return f();
+    }
+
+    let pre_event = format!("pre_{operation}");
+    let mut input = if details.is_object() {
+        details
+    } else {
+        json!({ "details": details })
+    };
+    input["event"] = json!(pre_event);
+    for hook in hooks.iter().filter(|h| h.event == pre_event) {
+        run_hook(hook, &input)?;
+    }
+
+    let result = f();
+
+    let post_event = format!("post_{operation}");
+    let post: Vec<Hook> = hooks.into_iter().filter(|h| h.event == post_event).collect();
+    if !post.is_empty() {
+        input["event"] = json!(post_event);
+        input["result"] = match &result {
+            Ok(v) => json!({ "ok": true, "value": v }),
+            Err(e) => json!({ "ok": false, "error": e }),
+        };
+        let handle = thread::spawn(move || {
+            for hook in &post {
+                if let Err(e) = run_hook(hook, &input) {
+                    tracing::warn!(error = %e, "post hook failed");
+                }
+            }
+        });
+        let mut pending = PENDING.lock().unwrap();
+        pending.retain(|h| !h.is_finished());
+        pending.push(handle);
+    }
+    result
+}
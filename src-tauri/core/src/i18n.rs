@@ -0,0 +1,277 @@
+// Translations of the messages the backend returns: errors, confirmation
+// prompts and job summaries. Messages are written in English throughout
+// the code and translated where they leave it, by matching them against
+// the catalog of the selected locale; `{}` in an entry stands for a part
+// that is carried over, such as a path or an OS error.
+use std::sync::RwLock;
+
+/// Locales with a catalog; English needs none.
+pub const LOCALES: &[&str] = &["en", "ru", "uk"];
+
+static LOCALE: RwLock<&str> = RwLock::new("en");
+
+type Catalog = &'static [(&'static str, &'static str)];
+
+const RU: Catalog = &[
+    ("Invalid root: {}", "Недопустимая корневая папка: {}"),
+    ("Target is not a file", "Это не файл"),
+    ("Target is not a directory", "Это не папка"),
+    ("Source is not a file", "Источник не является файлом"),
+    ("Source is not a directory", "Источник не является папкой"),
+    ("Invalid file name", "Недопустимое имя файла"),
+    (
+        "A file with that name already exists",
+        "Файл с таким именем уже существует",
+    ),
+    (
+        "A file with that name already exists in the destination",
+        "В папке назначения уже есть файл с таким именем",
+    ),
+    ("Destination directory does not exist", "Папка назначения не существует"),
+    ("Destination is not a directory", "Назначение не является папкой"),
+    ("Path escapes selected root", "Путь выходит за пределы выбранной папки"),
+    (
+        "Only files can be deleted with this action",
+        "Это действие удаляет только файлы",
+    ),
+    ("File is already in the trash", "Файл уже в корзине"),
+    ("Source and destination overlap", "Источник и назначение пересекаются"),
+    (
+        "The metadata store is locked, unlock it with its passphrase first",
+        "Хранилище метаданных заблокировано, сначала разблокируйте его паролем",
+    ),
+    (
+        "Running operations as administrator is turned off in the settings",
+        "Выполнение операций от имени администратора отключено в настройках",
+    ),
+    ("Rename failed: {}", "Не удалось переименовать: {}"),
+    ("Delete failed: {}", "Не удалось удалить: {}"),
+    ("Move failed: {}", "Не удалось переместить: {}"),
+    ("Create folder failed: {}", "Не удалось создать папку: {}"),
+    ("Failed to create dir: {}", "Не удалось создать папку: {}"),
+    ("Failed to read dir {}: {}", "Не удалось прочитать папку {}: {}"),
+    ("Failed to rename {}: {}", "Не удалось переименовать {}: {}"),
+    ("Failed to canonicalize root: {}", "Корневая папка недоступна: {}"),
+    ("Failed to canonicalize path: {}", "Путь недоступен: {}"),
+    (
+        "{} would grow to {} over its {} limit; confirm to go ahead anyway",
+        "{} вырастет до {}, больше своего лимита {}; подтвердите, чтобы продолжить",
+    ),
+    ("{} already exists", "{} уже существует"),
+    ("{} is protected from deletion ({})", "{} защищён от удаления ({})"),
+    (
+        "Deleting {} needs confirmation ({}); confirm to go ahead anyway",
+        "Удаление {} требует подтверждения ({}); подтвердите, чтобы продолжить",
+    ),
+    ("The drive holding {} was disconnected", "Диск с папкой {} был отключён"),
+    (
+        "{} is not opened: files of this type can run programs",
+        "{} не открыт: файлы этого типа могут запускать программы",
+    ),
+    (
+        "{} is in use by {}; try again once it has finished",
+        "{} занят задачей «{}»; повторите, когда она завершится",
+    ),
+    ("{} finished", "{}: готово"),
+    ("{} failed", "{}: ошибка"),
+    ("Sync", "Синхронизация"),
+    ("Indexing", "Индексация"),
+    ("Verification", "Проверка"),
+    ("Metadata backup", "Резервная копия метаданных"),
+    ("Documents", "Документы"),
+    ("Downloads", "Загрузки"),
+    ("Pictures", "Изображения"),
+    ("Videos", "Видео"),
+    ("Desktop", "Рабочий стол"),
+    (
+        "{} copied, {} updated, {} deleted, {} errors",
+        "скопировано: {}, обновлено: {}, удалено: {}, ошибок: {}",
+    ),
+    ("{}: {} files, {} hashed", "{}: файлов: {}, хешировано: {}"),
+    ("{}: {} checked, {} corrupted", "{}: проверено: {}, повреждено: {}"),
+    ("{} documents written to {}", "документов записано: {}, в {}"),
+    ("Manifest", "Манифест"),
+    ("Manifest check", "Проверка по манифесту"),
+    ("{} files written to {}", "файлов в манифесте: {}, записан в {}"),
+    (
+        "{}: {} intact, {} missing or damaged",
+        "{}: в порядке: {}, отсутствует или повреждено: {}",
+    ),
+    ("No terminal emulator found", "Эмулятор терминала не найден"),
+    ("Failed to open terminal: {}", "Не удалось открыть терминал: {}"),
+    ("Open window", "Открыть окно"),
+    ("No active jobs", "Нет активных задач"),
+    ("{}: waiting for the drive", "{}: ожидание диска"),
+    ("Cancel {}", "Отменить: {}"),
+    ("Quit", "Выход"),
+];
+
+const UK: Catalog = &[
+    ("Invalid root: {}", "Неприпустима коренева тека: {}"),
+    ("Target is not a file", "Це не файл"),
+    ("Target is not a directory", "Це не тека"),
+    ("Source is not a file", "Джерело не є файлом"),
+    ("Source is not a directory", "Джерело не є текою"),
+    ("Invalid file name", "Неприпустиме ім'я файлу"),
+    ("A file with that name already exists", "Файл з таким ім'ям уже існує"),
+    (
+        "A file with that name already exists in the destination",
+        "У теці призначення вже є файл з таким ім'ям",
+    ),
+    ("Destination directory does not exist", "Тека призначення не існує"),
+    ("Destination is not a directory", "Призначення не є текою"),
+    ("Path escapes selected root", "Шлях виходить за межі вибраної теки"),
+    (
+        "Only files can be deleted with this action",
+        "Ця дія видаляє лише файли",
+    ),
+    ("File is already in the trash", "Файл уже в кошику"),
+    ("Source and destination overlap", "Джерело і призначення перетинаються"),
+    (
+        "The metadata store is locked, unlock it with its passphrase first",
+        "Сховище метаданих заблоковане, спершу розблокуйте його паролем",
+    ),
+    (
+        "Running operations as administrator is turned off in the settings",
+        "Виконання операцій від імені адміністратора вимкнено в налаштуваннях",
+    ),
+    ("Rename failed: {}", "Не вдалося перейменувати: {}"),
+    ("Delete failed: {}", "Не вдалося видалити: {}"),
+    ("Move failed: {}", "Не вдалося перемістити: {}"),
+    ("Create folder failed: {}", "Не вдалося створити теку: {}"),
+    ("Failed to create dir: {}", "Не вдалося створити теку: {}"),
+    ("Failed to read dir {}: {}", "Не вдалося прочитати теку {}: {}"),
+    ("Failed to rename {}: {}", "Не вдалося перейменувати {}: {}"),
+    ("Failed to canonicalize root: {}", "Коренева тека недоступна: {}"),
+    ("Failed to canonicalize path: {}", "Шлях недоступний: {}"),
+    (
+        "{} would grow to {} over its {} limit; confirm to go ahead anyway",
+        "{} зросте до {}, більше за свій ліміт {}; підтвердьте, щоб продовжити",
+    ),
+    ("{} already exists", "{} уже існує"),
+    ("{} is protected from deletion ({})", "{} захищений від видалення ({})"),
+    (
+        "Deleting {} needs confirmation ({}); confirm to go ahead anyway",
+        "Видалення {} потребує підтвердження ({}); підтвердьте, щоб продовжити",
+    ),
+    (
+        "The drive holding {} was disconnected",
+        "Диск із текою {} було від'єднано",
+    ),
+    (
+        "{} is not opened: files of this type can run programs",
+        "{} не відкрито: файли цього типу можуть запускати програми",
+    ),
+    (
+        "{} is in use by {}; try again once it has finished",
+        "{} зайнятий завданням «{}»; повторіть, коли воно завершиться",
+    ),
+    ("{} finished", "{}: готово"),
+    ("{} failed", "{}: помилка"),
+    ("Sync", "Синхронізація"),
+    ("Indexing", "Індексування"),
+    ("Verification", "Перевірка"),
+    ("Metadata backup", "Резервна копія метаданих"),
+    ("Documents", "Документи"),
+    ("Downloads", "Завантаження"),
+    ("Pictures", "Зображення"),
+    ("Videos", "Відео"),
+    ("Desktop", "Стільниця"),
+    (
+        "{} copied, {} updated, {} deleted, {} errors",
+        "скопійовано: {}, оновлено: {}, видалено: {}, помилок: {}",
+    ),
+    ("{}: {} files, {} hashed", "{}: файлів: {}, гешовано: {}"),
+    ("{}: {} checked, {} corrupted", "{}: перевірено: {}, пошкоджено: {}"),
+    ("{} documents written to {}", "документів записано: {}, у {}"),
+    ("Manifest", "Маніфест"),
+    ("Manifest check", "Перевірка за маніфестом"),
+    ("{} files written to {}", "файлів у маніфесті: {}, записано в {}"),
+    (
+        "{}: {} intact, {} missing or damaged",
+        "{}: цілих: {}, відсутніх або пошкоджених: {}",
+    ),
+    ("No terminal emulator found", "Емулятор термінала не знайдено"),
+    ("Failed to open terminal: {}", "Не вдалося відкрити термінал: {}"),
+    ("Open window", "Відкрити вікно"),
+    ("No active jobs", "Немає активних завдань"),
+    ("{}: waiting for the drive", "{}: очікування диска"),
+    ("Cancel {}", "Скасувати: {}"),
+    ("Quit", "Вийти"),
+];
+
+fn catalog(locale: &str) -> Catalog {
+    match locale {
+        "ru" => RU,
+        "uk" => UK,
+        _ => &[],
+    }
+}
+
+/// The supported locale for a tag like `ru-RU` or `uk_UA.UTF-8`.
+pub fn normalize(tag: &str) -> Option<&'static str> {
+    let lang = tag.split(['-', '_', '.']).next().unwrap_or("").to_lowercase();
+    LOCALES.iter().copied().find(|l| *l == lang)
+}
+
+pub fn set_locale(tag: &str) -> Result<(), String> {
+    let locale = normalize(tag).ok_or_else(|| format!("Unsupported locale: {tag}"))?;
+    *LOCALE.write().unwrap() = locale;
+    Ok(())
+}
+
+pub fn locale() -> &'static str {
+    *LOCALE.read().unwrap()
+}
+
+/// The parts of `message` standing in for the `{}`s of `pattern`, if it
+/// matches.
+fn captures<'a>(pattern: &str, message: &'a str) -> Option<Vec<&'a str>> {
+    let mut pieces = pattern.split("{}");
+    let mut rest = message.strip_prefix(pieces.next()?)?;
+    let pieces: Vec<&str> = pieces.collect();
+    let mut out = Vec::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        if i == pieces.len() - 1 {
+            out.push(rest.strip_suffix(piece)?);
+            return Some(out);
+        }
+        let at = rest.find(piece)?;
+        out.push(&rest[..at]);
+        rest = &rest[at + piece.len()..];
+    }
+    // No placeholders: the whole message has to match
+    rest.is_empty().then_some(out)
+}
+
+/// `message` in the current locale, or as it is when the catalog has no
+/// entry for it.
+pub fn localize(message: String) -> String {
+    let entries = catalog(locale());
+    // Fixed messages first, so they don't get caught by a pattern like "{} already exists"
+    if let Some((_, translated)) = entries.iter().find(|(english, _)| *english == message) {
+        return translated.to_string();
+    }
+    for (english, translated) in entries {
+        if !english.contains("{}") {
+            continue;
+        }
+        if let Some(parts) = captures(english, &message) {
+            let mut out = String::new();
+            let mut parts = parts.into_iter();
+            for (i, piece) in translated.split("{}").enumerate() {
+                if i > 0 {
+                    out.push_str(parts.next().unwrap_or(""));
+                }
+                out.push_str(piece);
+            }
+            return out;
+        }
+    }
+    message
+}
+
+/// `localize` for a borrowed message, e.g. a job name.
+pub fn tr(message: &str) -> String {
+    localize(message.to_string())
+}
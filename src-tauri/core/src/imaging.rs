@@ -0,0 +1,434 @@
+// Image format conversion for camera dumps. PNG, TIFF, JPEG and WebP are
+// decoded by the `image` crate; HEIC needs the `heic` feature (libheif).
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ExtendedColorType, ImageEncoder};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::fsops::destination_dir_on;
+use crate::paths::{normalize_input_path, relative_key, resolve_within};
+use crate::vfs::RealFs;
+use crate::Progress;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetFormat {
+    Jpeg,
+    Webp,
+}
+
+impl TargetFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            TargetFormat::Jpeg => "jpg",
+            TargetFormat::Webp => "webp",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Output {
+    format: TargetFormat,
+    quality: u8,
+    // fit within this many pixels on the longer side, never upscaling
+    max_dimension: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct ConvertFailure {
+    pub relative_path: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct ConvertReport {
+    // files written; relative to the root, or absolute for exports outside it
+    pub converted: Vec<String>,
+    pub failed: Vec<ConvertFailure>,
+    pub bytes_written: u64,
+}
+
+pub(crate) fn is_heic(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.eq_ignore_ascii_case("heic") || e.eq_ignore_ascii_case("heif"))
+        .unwrap_or(false)
+}
+
+#[cfg(feature = "heic")]
+fn load_heic(path: &Path) -> Result<DynamicImage, String> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let lib = LibHeif::new();
+    let ctx = HeifContext::read_from_file(&path.to_string_lossy()).map_err(|e| e.to_string())?;
+    let handle = ctx.primary_image_handle().map_err(|e| e.to_string())?;
+    let decoded = lib
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgb), None)
+        .map_err(|e| e.to_string())?;
+    let plane = decoded.planes().interleaved.ok_or("HEIC image has no RGB plane")?;
+    let (w, h) = (plane.width as usize, plane.height as usize);
+    // Rows are padded to `stride` bytes
+    let mut rgb = Vec::with_capacity(w * h * 3);
+    for row in plane.data.chunks(plane.stride).take(h) {
+        rgb.extend_from_slice(&row[..w * 3]);
+    }
+    image::RgbImage::from_raw(w as u32, h as u32, rgb)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| "HEIC image has an unexpected layout".to_string())
+}
+
+#[cfg(not(feature = "heic"))]
+fn load_heic(_path: &Path) -> Result<DynamicImage, String> {
+    Err("HEIC support is not enabled in this build".into())
+}
+
+/// EXIF orientation tag (1-8), 1 when absent or unreadable.
+pub fn exif_orientation(path: &Path) -> u32 {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(_) => return 1,
+    };
+    exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()
+        .and_then(|data| {
+            data.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+                .and_then(|f| f.value.get_uint(0))
+        })
+        .unwrap_or(1)
+}
+
+/// Rotate/flip pixels so the image looks upright without its EXIF orientation.
+pub fn apply_orientation(img: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+/// Decode an image with its EXIF orientation baked into the pixels, since
+/// the re-encoded copies don't carry the original metadata.
+pub fn load_image(path: &Path) -> Result<DynamicImage, String> {
+    if is_heic(path) {
+        // libheif already applies the container's rotation when decoding
+        return load_heic(path);
+    }
+    let img = image::ImageReader::open(path)
+        .map_err(|e| e.to_string())?
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .decode()
+        .map_err(|e| e.to_string())?;
+    Ok(apply_orientation(img, exif_orientation(path)))
+}
+
+/// Encode `img` as `format` at `quality` (1-100) into `dest`.
+pub fn write_image(img: &DynamicImage, format: TargetFormat, quality: u8, dest: &Path) -> Result<u64, String> {
+    let (w, h) = (img.width(), img.height());
+    let mut out = BufWriter::new(File::create(dest).map_err(|e| e.to_string())?);
+    match format {
+        TargetFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let rgb = img.to_rgb8();
+            JpegEncoder::new_with_quality(&mut out, quality)
+                .write_image(rgb.as_raw(), w, h, ExtendedColorType::Rgb8)
+                .map_err(|e| e.to_string())?;
+        }
+        TargetFormat::Webp => {
+            let encoded = if img.color().has_alpha() {
+                let rgba = img.to_rgba8();
+                webp::Encoder::from_rgba(rgba.as_raw(), w, h).encode(quality as f32)
+            } else {
+                let rgb = img.to_rgb8();
+                webp::Encoder::from_rgb(rgb.as_raw(), w, h).encode(quality as f32)
+            };
+            out.write_all(&encoded).map_err(|e| e.to_string())?;
+        }
+    }
+    out.flush().map_err(|e| e.to_string())?;
+    drop(out);
+    fs::metadata(dest).map(|m| m.len()).map_err(|e| e.to_string())
+}
+
+/// `dir/stem.ext`, or `dir/stem (n).ext` if that is taken.
+pub fn free_name(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let mut candidate = dir.join(format!("{stem}.{ext}"));
+    let mut n = 1;
+    while candidate.exists() {
+        candidate = dir.join(format!("{stem} ({n}).{ext}"));
+        n += 1;
+    }
+    candidate
+}
+
+fn convert_one(
+    root: &Path,
+    root_canon: &Path,
+    rel: &str,
+    dest_dir: &Path,
+    output: Output,
+    naming: &Mutex<()>,
+) -> Result<(String, u64), String> {
+    let src = resolve_within(root, rel)?;
+    let mut img = load_image(&src)?;
+    if let Some(max) = output.max_dimension {
+        if img.width() > max || img.height() > max {
+            img = img.resize(max, max, FilterType::Lanczos3);
+        }
+    }
+    let stem = src.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let dest = {
+        // Picking output names has to be serialized or two workers could race for one
+        let _guard = naming.lock().unwrap();
+        let dest = free_name(dest_dir, &stem, output.format.extension());
+        File::create(&dest).map_err(|e| e.to_string())?;
+        dest
+    };
+    match write_image(&img, output.format, output.quality, &dest) {
+        Ok(bytes) => Ok((relative_key(root_canon, &dest), bytes)),
+        Err(e) => {
+            let _ = fs::remove_file(&dest);
+            Err(e)
+        }
+    }
+}
+
+fn run_batch(
+    root: &str,
+    relative_paths: &[String],
+    output: Output,
+    dest_relative_dir: &str,
+    on_progress: Progress,
+) -> Result<ConvertReport, String> {
+    if output.quality == 0 || output.quality > 100 {
+        return Err("Quality must be between 1 and 100".into());
+    }
+    let root_path = Path::new(root);
+    let root_canon = root_path
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let dest_dir = destination_dir_on(&RealFs, root_path, dest_relative_dir, true)?;
+
+    let total = relative_paths.len() as u64;
+    let done = AtomicU64::new(0);
+    let naming = Mutex::new(());
+    let results: Vec<(String, Result<(String, u64), String>)> = relative_paths
+        .par_iter()
+        .map(|rel| {
+            let result = convert_one(root_path, &root_canon, rel, &dest_dir, output, &naming);
+            on_progress(done.fetch_add(1, Ordering::Relaxed) + 1, total);
+            (rel.clone(), result)
+        })
+        .collect();
+
+    let mut report = ConvertReport::default();
+    for (rel, result) in results {
+        match result {
+            Ok((written, bytes)) => {
+                report.converted.push(written);
+                report.bytes_written += bytes;
+            }
+            Err(error) => report.failed.push(ConvertFailure {
+                relative_path: rel,
+                error,
+            }),
+        }
+    }
+    Ok(report)
+}
+
+/// Convert images under `root` to `target` in `dest_relative_dir`, on all
+/// cores. Originals are left untouched and existing files never overwritten.
+/// Re-encoded copies carry no EXIF, GPS or other embedded metadata.
+pub fn convert_images(
+    root: &str,
+    relative_paths: &[String],
+    target: TargetFormat,
+    quality: u8,
+    dest_relative_dir: &str,
+    on_progress: Progress,
+) -> Result<ConvertReport, String> {
+    let output = Output {
+        format: target,
+        quality,
+        max_dimension: None,
+    };
+    run_batch(root, relative_paths, output, dest_relative_dir, on_progress)
+}
+
+/// Write downscaled copies that fit within `max_dimension` pixels into
+/// `dest_relative_dir`. Smaller images are re-encoded at their own size.
+pub fn resize_images(
+    root: &str,
+    relative_paths: &[String],
+    max_dimension: u32,
+    target: TargetFormat,
+    quality: u8,
+    dest_relative_dir: &str,
+    on_progress: Progress,
+) -> Result<ConvertReport, String> {
+    if max_dimension == 0 {
+        return Err("Maximum dimension must be positive".into());
+    }
+    let output = Output {
+        format: target,
+        quality,
+        max_dimension: Some(max_dimension),
+    };
+    run_batch(root, relative_paths, output, dest_relative_dir, on_progress)
+}
+
+// Minimal EXIF block carrying only the orientation tag, so stripped JPEGs
+// still display upright
+fn orientation_app1(orientation: u16) -> Vec<u8> {
+    let mut seg = vec![0xFF, 0xE1, 0x00, 34];
+    seg.extend_from_slice(b"Exif\0\0");
+    // big-endian TIFF header, first IFD at offset 8
+    seg.extend_from_slice(&[b'M', b'M', 0x00, 0x2A, 0x00, 0x00, 0x00, 0x08]);
+    // one entry: tag 0x0112 (Orientation), type SHORT, count 1, value
+    seg.extend_from_slice(&[0x00, 0x01, 0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+    seg.extend_from_slice(&orientation.to_be_bytes());
+    seg.extend_from_slice(&[0x00, 0x00]);
+    // no next IFD
+    seg.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+    seg
+}
+
+/// Drop EXIF/XMP (APP1), IPTC (APP13), other application segments and
+/// comments from a JPEG without re-encoding. JFIF, ICC profiles and Adobe
+/// color info are kept.
+pub fn strip_jpeg(data: &[u8], orientation: u32) -> Result<Vec<u8>, String> {
+    if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+        return Err("Not a JPEG file".into());
+    }
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..2]);
+    if orientation > 1 && orientation <= 8 {
+        out.extend_from_slice(&orientation_app1(orientation as u16));
+    }
+    let mut i = 2;
+    while i + 4 <= data.len() {
+        if data[i] != 0xFF {
+            return Err("Malformed JPEG segment".into());
+        }
+        let marker = data[i + 1];
+        if marker == 0xFF {
+            // fill byte
+            i += 1;
+            continue;
+        }
+        if marker == 0xDA {
+            // Start of scan: the compressed image data runs to the end
+            out.extend_from_slice(&data[i..]);
+            return Ok(out);
+        }
+        let len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        let end = i + 2 + len;
+        if len < 2 || end > data.len() {
+            return Err("Truncated JPEG segment".into());
+        }
+        let keep = match marker {
+            0xE0 | 0xE2 | 0xEE => true, // JFIF, ICC profile, Adobe
+            0xE1 | 0xE3..=0xED | 0xEF | 0xFE => false,
+            _ => true,
+        };
+        if keep {
+            out.extend_from_slice(&data[i..end]);
+        }
+        i = end;
+    }
+    Err("JPEG has no image data".into())
+}
+
+/// Drop text, timestamp and EXIF chunks from a PNG.
+pub fn strip_png(data: &[u8]) -> Result<Vec<u8>, String> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    if data.len() < 8 || data[..8] != SIGNATURE {
+        return Err("Not a PNG file".into());
+    }
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&SIGNATURE);
+    let mut i = 8;
+    while i + 12 <= data.len() {
+        let len = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        let end = i + 12 + len;
+        if end > data.len() {
+            return Err("Truncated PNG chunk".into());
+        }
+        let kind = &data[i + 4..i + 8];
+        if !matches!(kind, b"eXIf" | b"tEXt" | b"iTXt" | b"zTXt" | b"tIME") {
+            out.extend_from_slice(&data[i..end]);
+        }
+        if kind == b"IEND" {
+            return Ok(out);
+        }
+        i = end;
+    }
+    Err("PNG has no IEND chunk".into())
+}
+
+fn strip_one(root: &Path, rel: &str, dest: &Path) -> Result<PathBuf, String> {
+    let src = resolve_within(root, rel)?;
+    let data = fs::read(&src).map_err(|e| e.to_string())?;
+    let (stripped, default_ext) = if data.starts_with(&[0xFF, 0xD8]) {
+        (strip_jpeg(&data, exif_orientation(&src))?, "jpg")
+    } else if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+        (strip_png(&data)?, "png")
+    } else {
+        return Err("Only JPEG and PNG can be stripped without re-encoding".into());
+    };
+    let stem = src.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = src
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| default_ext.to_string());
+    let out = free_name(dest, &stem, &ext);
+    fs::write(&out, stripped).map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// Copy images to `dest_dir` (any folder, typically outside the root) with
+/// EXIF, GPS and other personal metadata removed, without re-encoding.
+/// Supports JPEG and PNG; use `convert_images` for other formats.
+pub fn strip_metadata(
+    root: &str,
+    relative_paths: &[String],
+    dest_dir: &str,
+    on_progress: Progress,
+) -> Result<ConvertReport, String> {
+    let root_path = Path::new(root);
+    let dest = PathBuf::from(normalize_input_path(dest_dir));
+    fs::create_dir_all(&dest).map_err(|e| format!("Failed to create dir: {e}"))?;
+    let dest = dest
+        .canonicalize()
+        .map_err(|e| format!("Invalid destination: {e}"))?;
+
+    let mut report = ConvertReport::default();
+    let total = relative_paths.len() as u64;
+    for (n, rel) in relative_paths.iter().enumerate() {
+        let result = strip_one(root_path, rel, &dest);
+        match result {
+            Ok(out) => {
+                report.bytes_written += fs::metadata(&out).map(|m| m.len()).unwrap_or(0);
+                report.converted.push(out.display().to_string());
+            }
+            Err(error) => report.failed.push(ConvertFailure {
+                relative_path: rel.clone(),
+                error,
+            }),
+        }
+        on_progress(n as u64 + 1, total);
+    }
+    Ok(report)
+}
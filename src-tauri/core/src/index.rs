@@ -0,0 +1,396 @@
+// Per-root content index: size, mtime and hash of every file, persisted in
+// the store so later sessions can detect silent corruption.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::encryption;
+use crate::hashing::{self, HashCache};
+use crate::paths::normalize_input_path;
+use crate::settings;
+use crate::store::Store;
+use crate::trash::TRASH_DIR;
+use crate::Progress;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct IndexEntry {
+    pub size: u64,
+    pub mtime_ms: i64,
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct RootIndex {
+    pub root: String,
+    pub indexed_at: i64,
+    pub verified_at: Option<i64>,
+    // keyed by relative path with forward slashes
+    pub entries: BTreeMap<String, IndexEntry>,
+}
+
+#[derive(Serialize, Default)]
+pub struct IndexSummary {
+    pub files: u64,
+    pub hashed: u64,
+    pub reused: u64,
+    pub removed: u64,
+}
+
+#[derive(Serialize)]
+pub struct CorruptFile {
+    pub relative_path: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct VerifyReport {
+    pub checked: u64,
+    // modified since indexing, so a different hash is expected
+    pub changed: Vec<String>,
+    pub missing: Vec<String>,
+    pub unreadable: Vec<String>,
+    pub corrupted: Vec<CorruptFile>,
+}
+
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+pub fn mtime_ms(meta: &fs::Metadata) -> i64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+pub fn canonical_root(root: &str) -> Result<PathBuf, String> {
+    PathBuf::from(root)
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))
+}
+
+pub fn store_key(root_canon: &Path) -> String {
+    let digest = blake3::hash(root_canon.to_string_lossy().as_bytes());
+    format!("index/{}", &digest.to_hex()[..16])
+}
+
+pub fn load_index(store: &Store, root_canon: &Path) -> Result<RootIndex, String> {
+    store.load(&store_key(root_canon))
+}
+
+/// Copy of a root's index kept at the root itself, so the catalog travels
+/// with the drive to other machines.
+pub const SIDECAR_FILE: &str = ".lfm-catalog";
+
+pub fn save_index(store: &Store, root_canon: &Path, index: &RootIndex) -> Result<(), String> {
+    store.save(&store_key(root_canon), index)?;
+    // An encrypted profile's catalogs don't get an unencrypted copy on the drive
+    if settings::load(store)?.catalog_sidecar && !encryption::is_encrypted(store.dir()) {
+        // Read-only or write-protected media simply don't get a sidecar
+        let _ = write_sidecar(root_canon, index);
+    }
+    Ok(())
+}
+
+fn write_sidecar(root_canon: &Path, index: &RootIndex) -> Result<(), String> {
+    let data = serde_json::to_vec(index).map_err(|e| e.to_string())?;
+    let path = root_canon.join(SIDECAR_FILE);
+    let tmp = root_canon.join(format!("{SIDECAR_FILE}.tmp"));
+    fs::write(&tmp, data).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &path).map_err(|e| e.to_string())
+}
+
+/// Adopt the `.lfm-catalog` sidecar of a root when it comes from a later
+/// scan than the stored index, e.g. when the drive was last scanned on
+/// another machine. A later verification alone doesn't make it newer.
+/// Returns whether the stored index was replaced.
+pub fn load_sidecar(store: &Store, root_canon: &Path) -> Result<bool, String> {
+    let Ok(data) = fs::read(root_canon.join(SIDECAR_FILE)) else {
+        return Ok(false);
+    };
+    let Ok(mut sidecar) = serde_json::from_slice::<RootIndex>(&data) else {
+        return Ok(false);
+    };
+    let local = load_index(store, root_canon)?;
+    if local.indexed_at >= sidecar.indexed_at {
+        return Ok(false);
+    }
+    // The drive may be mounted somewhere else here
+    sidecar.root = root_canon.display().to_string();
+    store.save(&store_key(root_canon), &sidecar)?;
+    Ok(true)
+}
+
+/// Every regular file under `root_canon` with its metadata. Symlinks, the
+/// trash and the catalog sidecar are skipped.
+pub fn walk_files(root_canon: &Path) -> Vec<(PathBuf, fs::Metadata)> {
+    let mut out = Vec::new();
+    let mut stack: Vec<PathBuf> = vec![root_canon.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        for entry in read_dir.flatten() {
+            let p = entry.path();
+            let meta = match fs::symlink_metadata(&p) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            if meta.is_dir() {
+                // Deleted files aren't part of the drive's contents anymore
+                if entry.file_name() != TRASH_DIR {
+                    stack.push(p);
+                }
+            } else if meta.is_file() && !(dir == root_canon && entry.file_name() == SIDECAR_FILE) {
+                out.push((p, meta));
+            }
+        }
+    }
+    out
+}
+
+/// Store documents holding the progress of scans in progress, one per root.
+pub const CHECKPOINT_DIR: &str = "scan";
+// Minimum time between two checkpoint writes
+const CHECKPOINT_EVERY: Duration = Duration::from_secs(30);
+// Files hashed between two chances to write a checkpoint
+const HASH_BATCH: usize = 2_000;
+
+/// How far an interrupted `refresh_index` got, so the next one carries on
+/// from there instead of walking and hashing the whole root again.
+#[derive(Serialize, Deserialize, Default)]
+struct ScanCheckpoint {
+    started_at: i64,
+    walk_done: bool,
+    // folders still to walk, relative to the root
+    pending_dirs: Vec<String>,
+    // files found so far, with size and mtime
+    found: BTreeMap<String, (u64, i64)>,
+    // files hashed so far
+    hashed: BTreeMap<String, IndexEntry>,
+}
+
+fn checkpoint_key(root_canon: &Path) -> String {
+    let digest = blake3::hash(root_canon.to_string_lossy().as_bytes());
+    format!("{CHECKPOINT_DIR}/{}", &digest.to_hex()[..16])
+}
+
+/// Write `checkpoint` if the last write was long enough ago.
+fn save_checkpoint(store: &Store, key: &str, checkpoint: &ScanCheckpoint, last: &mut Instant) {
+    if last.elapsed() >= CHECKPOINT_EVERY {
+        // A failed write only costs the resume
+        let _ = store.save(key, checkpoint);
+        *last = Instant::now();
+    }
+}
+
+/// `walk_files` that records its progress in `checkpoint` and picks up from
+/// the folders it has left.
+fn walk_resumable(store: &Store, root_canon: &Path, key: &str, checkpoint: &mut ScanCheckpoint, last: &mut Instant) {
+    let mut stack: Vec<PathBuf> = if checkpoint.found.is_empty() && checkpoint.pending_dirs.is_empty() {
+        vec![root_canon.to_path_buf()]
+    } else {
+        checkpoint
+            .pending_dirs
+            .iter()
+            .map(|d| root_canon.join(normalize_input_path(d)))
+            .collect()
+    };
+    while let Some(dir) = stack.pop() {
+        let Ok(read_dir) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let p = entry.path();
+            let Ok(meta) = fs::symlink_metadata(&p) else {
+                continue;
+            };
+            if meta.is_dir() {
+                if entry.file_name() != TRASH_DIR {
+                    stack.push(p);
+                }
+            } else if meta.is_file() {
+                let rel = crate::paths::relative_key(root_canon, &p);
+                if rel != SIDECAR_FILE {
+                    checkpoint.found.insert(rel, (meta.len(), mtime_ms(&meta)));
+                }
+            }
+        }
+        // Only between folders, so a resumed walk never sees one twice
+        if last.elapsed() >= CHECKPOINT_EVERY {
+            checkpoint.pending_dirs = stack
+                .iter()
+                .map(|d| crate::paths::relative_key(root_canon, d))
+                .collect();
+            save_checkpoint(store, key, checkpoint, last);
+        }
+    }
+    checkpoint.walk_done = true;
+    checkpoint.pending_dirs.clear();
+    let _ = store.save(key, checkpoint);
+    *last = Instant::now();
+}
+
+/// Bring the stored index of a root up to date. Files whose size and mtime
+/// are unchanged keep their previous hash. Progress is checkpointed, so an
+/// interrupted refresh resumes where it stopped the next time.
+pub fn refresh_index(
+    store: &Store,
+    cache: &HashCache,
+    root_canon: &Path,
+    threads: Option<usize>,
+    on_progress: Progress,
+) -> Result<(RootIndex, IndexSummary), String> {
+    load_sidecar(store, root_canon)?;
+    let previous = load_index(store, root_canon)?;
+
+    let key = checkpoint_key(root_canon);
+    let mut checkpoint: ScanCheckpoint = store.load(&key).unwrap_or_default();
+    // A checkpoint older than the index belongs to a scan that was superseded
+    if checkpoint.started_at < previous.indexed_at {
+        checkpoint = ScanCheckpoint::default();
+    }
+    if checkpoint.started_at == 0 {
+        checkpoint.started_at = now_ms();
+    }
+    let mut last = Instant::now();
+    if !checkpoint.walk_done {
+        walk_resumable(store, root_canon, &key, &mut checkpoint, &mut last);
+    }
+
+    let mut summary = IndexSummary::default();
+    let mut entries: BTreeMap<String, IndexEntry> = BTreeMap::new();
+    let mut pending: Vec<(String, IndexEntry)> = Vec::new();
+    let mut jobs: Vec<(PathBuf, u64)> = Vec::new();
+    for (rel, &(size, mtime)) in &checkpoint.found {
+        let same = |old: &&IndexEntry| old.size == size && old.mtime_ms == mtime;
+        if let Some(old) = previous.entries.get(rel).filter(same) {
+            summary.reused += 1;
+            entries.insert(rel.clone(), old.clone());
+        } else if let Some(done) = checkpoint.hashed.get(rel).filter(same) {
+            summary.hashed += 1;
+            entries.insert(rel.clone(), done.clone());
+        } else {
+            pending.push((
+                rel.clone(),
+                IndexEntry {
+                    size,
+                    mtime_ms: mtime,
+                    hash: String::new(),
+                },
+            ));
+            jobs.push((root_canon.join(normalize_input_path(rel)), size));
+        }
+    }
+
+    let total = jobs.len() as u64;
+    let mut offset = 0;
+    for (batch, batch_jobs) in pending.chunks(HASH_BATCH).zip(jobs.chunks(HASH_BATCH)) {
+        let hashes = hashing::hash_parallel(
+            batch_jobs,
+            threads,
+            &|p, on_read| cache.hash_with(p, on_read),
+            &|done| on_progress(offset + done, total),
+        );
+        for ((rel, entry), hash) in batch.iter().zip(hashes) {
+            // Unreadable files are left out and retried on the next refresh
+            if let Ok(hash) = hash {
+                let entry = IndexEntry { hash, ..entry.clone() };
+                checkpoint.hashed.insert(rel.clone(), entry.clone());
+                entries.insert(rel.clone(), entry);
+                summary.hashed += 1;
+            }
+        }
+        offset += batch_jobs.len() as u64;
+        save_checkpoint(store, &key, &checkpoint, &mut last);
+    }
+    summary.files = entries.len() as u64;
+    summary.removed = previous
+        .entries
+        .keys()
+        .filter(|k| !entries.contains_key(*k))
+        .count() as u64;
+
+    let index = RootIndex {
+        root: root_canon.display().to_string(),
+        indexed_at: now_ms(),
+        verified_at: previous.verified_at,
+        entries,
+    };
+    save_index(store, root_canon, &index)?;
+    store.remove(&key)?;
+    Ok((index, summary))
+}
+
+/// Re-hash every indexed file whose size and mtime haven't changed. A hash
+/// mismatch on such a file means the bytes changed underneath the file
+/// system, i.e. likely bit rot.
+pub fn verify_index(
+    store: &Store,
+    cache: &HashCache,
+    root_canon: &Path,
+    threads: Option<usize>,
+    on_progress: Progress,
+) -> Result<VerifyReport, String> {
+    let mut index = load_index(store, root_canon)?;
+    // Only a missing document loads with no scan time; an empty root indexes fine
+    if index.indexed_at == 0 {
+        return Err("Root has not been indexed yet".into());
+    }
+
+    let mut report = VerifyReport::default();
+    let mut candidates: Vec<&str> = Vec::new();
+    let mut jobs: Vec<(PathBuf, u64)> = Vec::new();
+    for (key, entry) in &index.entries {
+        let p = root_canon.join(normalize_input_path(key));
+        let meta = match fs::symlink_metadata(&p) {
+            Ok(m) if m.is_file() => m,
+            _ => {
+                report.missing.push(key.clone());
+                continue;
+            }
+        };
+        if meta.len() != entry.size || mtime_ms(&meta) != entry.mtime_ms {
+            report.changed.push(key.clone());
+            continue;
+        }
+        candidates.push(key);
+        jobs.push((p, entry.size));
+    }
+
+    let total = jobs.len() as u64;
+    // Deliberately bypasses the hash cache: the point is to read the disk again
+    let hashes = hashing::hash_parallel(&jobs, threads, &hashing::hash_file_with, &|done| {
+        on_progress(done, total)
+    });
+    for ((key, (p, _)), hash) in candidates.into_iter().zip(&jobs).zip(hashes) {
+        match hash {
+            Ok(actual) => {
+                // Whatever is on disk now is what later features should see
+                cache.insert(p, &actual);
+                report.checked += 1;
+                let expected = &index.entries[key].hash;
+                if &actual != expected {
+                    report.corrupted.push(CorruptFile {
+                        relative_path: key.to_string(),
+                        expected: expected.clone(),
+                        actual,
+                    });
+                }
+            }
+            Err(_) => report.unreadable.push(key.to_string()),
+        }
+    }
+
+    index.verified_at = Some(now_ms());
+    save_index(store, root_canon, &index)?;
+    Ok(report)
+}
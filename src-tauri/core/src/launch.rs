@@ -0,0 +1,147 @@
+// What opening a file does, by extension: hand it to the system, show it
+// in the app's own preview, or refuse. Programs and scripts are never run by
+// default, so double-clicking a stray `.exe` or `.sh` on an untrusted drive
+// can't launch it.
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::filetype;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenAction {
+    // the system's default application
+    System,
+    // the app's internal preview, nothing is launched
+    Preview,
+    // refuse to open
+    Never,
+}
+
+/// Extensions mapped to an action; the first rule matching a file wins.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OpenRule {
+    // without the dot, case-insensitive
+    pub extensions: Vec<String>,
+    pub action: OpenAction,
+}
+
+/// Files that run code when opened, refused unless a rule says otherwise.
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "app",
+    "appimage",
+    "applescript",
+    "bash",
+    "bat",
+    "chm",
+    "cmd",
+    "com",
+    "command",
+    "cpl",
+    "deb",
+    "desktop",
+    "docm",
+    "exe",
+    "gadget",
+    "hta",
+    "inf",
+    "jar",
+    "jnlp",
+    "js",
+    "jse",
+    "lnk",
+    "msc",
+    "msi",
+    "msix",
+    "msp",
+    "pif",
+    "pkg",
+    "pl",
+    "pptm",
+    "ps1",
+    "ps1xml",
+    "psc1",
+    "psm1",
+    "py",
+    "pyw",
+    "rb",
+    "reg",
+    "rpm",
+    "run",
+    "scpt",
+    "scr",
+    "sct",
+    "sh",
+    "terminal",
+    "url",
+    "vb",
+    "vbe",
+    "vbs",
+    "workflow",
+    "ws",
+    "wsf",
+    "xlsm",
+    "zsh",
+];
+
+// Programs recognized by their magic bytes, whatever they are called
+const EXECUTABLE_MIMES: &[&str] = &[
+    "application/x-executable",
+    "application/x-mach-binary",
+    "application/vnd.microsoft.portable-executable",
+];
+
+pub fn validate(rule: &OpenRule) -> Result<(), String> {
+    if rule.extensions.is_empty() {
+        return Err("An open rule needs at least one extension".into());
+    }
+    if rule
+        .extensions
+        .iter()
+        .any(|e| e.trim().is_empty() || e.contains(['.', '/', '\\']))
+    {
+        return Err("Open rule extensions are written without the dot, e.g. pdf".into());
+    }
+    Ok(())
+}
+
+/// What opening `path` should do under `rules`.
+pub fn open_action(rules: &[OpenRule], path: &Path) -> OpenAction {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let rule = rules
+        .iter()
+        .find(|r| r.extensions.iter().any(|e| e.to_lowercase() == ext));
+    match rule {
+        Some(rule) => rule.action,
+        None if EXECUTABLE_EXTENSIONS.contains(&ext.as_str()) => OpenAction::Never,
+        // Programs on Unix usually have no extension at all
+        None if ext.is_empty() && is_program(path) => OpenAction::Never,
+        None => OpenAction::System,
+    }
+}
+
+/// Whether `path` has an executable bit set or is an executable by content.
+fn is_program(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0) {
+            return true;
+        }
+    }
+    filetype::sniff(path).is_some_and(|(_, mime)| EXECUTABLE_MIMES.contains(&mime.as_str()))
+}
+
+/// `open_action`, as an error when the file must not be opened.
+pub fn check_open(rules: &[OpenRule], path: &Path) -> Result<OpenAction, String> {
+    match open_action(rules, path) {
+        OpenAction::Never => Err(format!(
+            "{} is not opened: files of this type can run programs",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        )),
+        action => Ok(action),
+    }
+}
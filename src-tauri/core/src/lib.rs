@@ -0,0 +1,49 @@
+// Filesystem engine behind sdmanager: listing, file operations, hashing,
+// indexing, duplicate detection and sync. Nothing in here knows about Tauri,
+// the desktop app and the `lfm` CLI are both thin front ends over it.
+pub mod actions;
+pub mod albums;
+pub mod backup;
+pub mod bench;
+pub mod compare;
+pub mod dedup;
+pub mod elevate;
+pub mod encryption;
+pub mod eta;
+pub mod filetype;
+pub mod fsops;
+pub mod groups;
+pub mod guards;
+pub mod hashing;
+pub mod hooks;
+pub mod i18n;
+pub mod imaging;
+pub mod index;
+pub mod launch;
+pub mod listing;
+pub mod manifest;
+pub mod media;
+pub mod ownership;
+pub mod paths;
+pub mod playlists;
+pub mod power;
+pub mod profiles;
+pub mod quota;
+pub mod recent;
+pub mod renumber;
+pub mod reservations;
+pub mod roots;
+pub mod rpc;
+pub mod settings;
+pub mod shortcuts;
+pub mod sorting;
+pub mod stats;
+pub mod store;
+pub mod sync;
+pub mod translit;
+pub mod trash;
+pub mod vfs;
+pub mod workspace;
+
+/// Progress callback receiving (done, total).
+pub type Progress<'a> = &'a (dyn Fn(u64, u64) + Sync);
@@ -0,0 +1,269 @@
+// Disk-backed listings for trees too large to hold in memory. The walk
+// streams entries to a temporary result set (JSON lines plus an offset
+// table) and the front end reads it back a page at a time.
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::fsops::FileEntry;
+use crate::groups;
+use crate::index::now_ms;
+use crate::paths::{is_hidden, relative_key};
+use crate::shortcuts;
+use crate::workspace;
+
+// Result sets nobody closed, e.g. after a crash, are removed after this long
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+const MAX_PAGE: usize = 10_000;
+// Folders read one by one before `estimate_entries` switches to sampling
+const ESTIMATE_DIR_BUDGET: usize = 2_000;
+// Random descents into the folders left over
+const ESTIMATE_PROBES: usize = 200;
+const MAX_PROBE_DEPTH: usize = 64;
+/// Roots with more files than this are worth a warning before a full scan.
+pub const LARGE_ROOT_FILES: u64 = 500_000;
+
+#[derive(Serialize)]
+pub struct ListingInfo {
+    pub id: String,
+    pub total: u64,
+}
+
+#[derive(Serialize)]
+pub struct EntryEstimate {
+    // files under the root, hidden ones included
+    pub files: u64,
+    // every folder was read, so `files` is a count rather than an estimate
+    pub exact: bool,
+    // over `LARGE_ROOT_FILES`: a full scan may take a while
+    pub large: bool,
+}
+
+struct Child {
+    path: PathBuf,
+    is_dir: bool,
+}
+
+fn listings_dir() -> PathBuf {
+    workspace::session_dir().join("listings")
+}
+
+fn listing_paths(id: &str) -> Result<(PathBuf, PathBuf), String> {
+    if id.len() != 16 || !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err("Invalid listing id".into());
+    }
+    let dir = listings_dir();
+    Ok((dir.join(format!("{id}.jsonl")), dir.join(format!("{id}.idx"))))
+}
+
+fn sorted_children(dir: &PathBuf, show_hidden: bool) -> Vec<Child> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut children: Vec<Child> = read_dir
+        .flatten()
+        .filter(|e| show_hidden || !is_hidden(&e.path()))
+        .filter_map(|e| {
+            let file_type = e.file_type().ok()?;
+            let path = e.path();
+            // Symlinked folders aren't followed, so cycles can't make the walk endless
+            let is_dir = file_type.is_dir();
+            (is_dir || path.is_file()).then_some(Child { path, is_dir })
+        })
+        .collect();
+    children.sort_by(|a, b| a.path.file_name().cmp(&b.path.file_name()));
+    children
+}
+
+fn remove_stale() {
+    let Ok(read_dir) = fs::read_dir(listings_dir()) else {
+        return;
+    };
+    let cutoff = SystemTime::now() - STALE_AFTER;
+    for entry in read_dir.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .is_ok_and(|t| t < cutoff);
+        if stale {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Files directly in `dir` and its subfolders. Symlinked folders aren't
+/// followed, as in the walk itself.
+fn scan_dir(dir: &Path) -> (u64, Vec<PathBuf>) {
+    let mut files = 0;
+    let mut dirs = Vec::new();
+    if let Ok(read_dir) = fs::read_dir(dir) {
+        for entry in read_dir.flatten() {
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => dirs.push(entry.path()),
+                Ok(_) => files += 1,
+                Err(_) => {}
+            }
+        }
+    }
+    (files, dirs)
+}
+
+// xorshift64*, plenty for picking folders
+struct Picker(u64);
+
+impl Picker {
+    fn pick(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) % n as u64) as usize
+    }
+}
+
+/// Files under `dir`, estimated from one random path down the tree: each
+/// folder's files are weighted by how many siblings its ancestors had.
+fn probe(dir: &Path, picker: &mut Picker) -> u64 {
+    let mut estimate: u64 = 0;
+    let mut weight: u64 = 1;
+    let mut dir = dir.to_path_buf();
+    for _ in 0..MAX_PROBE_DEPTH {
+        let (files, mut dirs) = scan_dir(&dir);
+        estimate = estimate.saturating_add(weight.saturating_mul(files));
+        if dirs.is_empty() {
+            break;
+        }
+        weight = weight.saturating_mul(dirs.len() as u64);
+        dir = dirs.swap_remove(picker.pick(dirs.len()));
+    }
+    estimate
+}
+
+/// Estimate how many files are under `root` without walking all of it, so
+/// a huge root can be flagged before a full scan. The first folders are
+/// counted exactly; when there are more than that, the rest is estimated
+/// from random descents into the folders not yet read.
+pub fn estimate_entries(root: &str) -> Result<EntryEstimate, String> {
+    let root_canon = PathBuf::from(root)
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let mut counted: u64 = 0;
+    let mut queue = VecDeque::from([root_canon]);
+    let mut read = 0;
+    while read < ESTIMATE_DIR_BUDGET {
+        let Some(dir) = queue.pop_front() else {
+            break;
+        };
+        let (files, dirs) = scan_dir(&dir);
+        counted += files;
+        queue.extend(dirs);
+        read += 1;
+    }
+    let exact = queue.is_empty();
+    let mut files = counted;
+    if !exact {
+        let frontier: Vec<PathBuf> = queue.into_iter().collect();
+        let mut picker = Picker(now_ms() as u64 | 1);
+        let sampled: u64 = (0..ESTIMATE_PROBES)
+            .map(|_| probe(&frontier[picker.pick(frontier.len())], &mut picker))
+            .fold(0, u64::saturating_add);
+        files = files.saturating_add(sampled.saturating_mul(frontier.len() as u64) / ESTIMATE_PROBES as u64);
+    }
+    Ok(EntryEstimate {
+        files,
+        exact,
+        large: files > LARGE_ROOT_FILES,
+    })
+}
+
+/// Walk `root` into a new on-disk result set. Entries come depth first with
+/// the names of each folder sorted, so only the folders along the current
+/// path are ever held in memory.
+pub fn open_listing(root: &str, show_hidden: bool) -> Result<ListingInfo, String> {
+    let root_canon = PathBuf::from(root)
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    remove_stale();
+    fs::create_dir_all(listings_dir()).map_err(|e| format!("Failed to create listing dir: {e}"))?;
+    let seed = format!("{}:{}:{:?}", root_canon.display(), now_ms(), std::thread::current().id());
+    let id = blake3::hash(seed.as_bytes()).to_hex()[..16].to_string();
+    let (data_path, idx_path) = listing_paths(&id)?;
+
+    let write_err = |e: std::io::Error| format!("Failed to write listing: {e}");
+    let mut data = BufWriter::new(File::create(&data_path).map_err(write_err)?);
+    let mut idx = BufWriter::new(File::create(&idx_path).map_err(write_err)?);
+    let mut offset: u64 = 0;
+    let mut total: u64 = 0;
+    // Each level keeps the sidecar groups among its files next to the iterator
+    let level = |children: Vec<Child>| {
+        let rels: Vec<String> = children
+            .iter()
+            .filter(|c| !c.is_dir)
+            .map(|c| relative_key(&root_canon, &c.path))
+            .collect();
+        let shared: HashSet<String> = groups::shared_ids(rels.iter().map(String::as_str));
+        (children.into_iter(), shared)
+    };
+    let mut stack = vec![level(sorted_children(&root_canon, show_hidden))];
+    while let Some((children, shared)) = stack.last_mut() {
+        let Some(child) = children.next() else {
+            stack.pop();
+            continue;
+        };
+        if child.is_dir {
+            stack.push(level(sorted_children(&child.path, show_hidden)));
+            continue;
+        }
+        let relative_path = relative_key(&root_canon, &child.path);
+        let group = groups::group_id(&relative_path).filter(|id| shared.contains(id));
+        let entry = FileEntry {
+            path: child.path.display().to_string(),
+            relative_path,
+            size: fs::metadata(&child.path).map(|m| m.len()).unwrap_or(0),
+            shortcut: shortcuts::resolve(&child.path),
+            group,
+        };
+        let mut line = serde_json::to_vec(&entry).map_err(|e| e.to_string())?;
+        line.push(b'\n');
+        data.write_all(&line).map_err(write_err)?;
+        idx.write_all(&offset.to_le_bytes()).map_err(write_err)?;
+        offset += line.len() as u64;
+        total += 1;
+    }
+    data.flush().map_err(write_err)?;
+    idx.flush().map_err(write_err)?;
+    Ok(ListingInfo { id, total })
+}
+
+/// Up to `limit` entries of a result set starting at entry `offset`.
+pub fn listing_page(id: &str, offset: u64, limit: usize) -> Result<Vec<FileEntry>, String> {
+    let (data_path, idx_path) = listing_paths(id)?;
+    let read_err = |e: std::io::Error| format!("Failed to read listing: {e}");
+    let mut idx = File::open(&idx_path).map_err(|_| "Listing expired or closed".to_string())?;
+    let total = idx.metadata().map_err(read_err)?.len() / 8;
+    if offset >= total {
+        return Ok(Vec::new());
+    }
+    idx.seek(SeekFrom::Start(offset * 8)).map_err(read_err)?;
+    let mut start = [0u8; 8];
+    idx.read_exact(&mut start).map_err(read_err)?;
+
+    let mut data = File::open(&data_path).map_err(read_err)?;
+    data.seek(SeekFrom::Start(u64::from_le_bytes(start))).map_err(read_err)?;
+    let count = limit.min(MAX_PAGE).min((total - offset) as usize);
+    let mut page = Vec::with_capacity(count);
+    for line in BufReader::new(data).lines().take(count) {
+        let line = line.map_err(read_err)?;
+        page.push(serde_json::from_str(&line).map_err(|e| format!("Corrupt listing: {e}"))?);
+    }
+    Ok(page)
+}
+
+pub fn close_listing(id: &str) -> Result<(), String> {
+    let (data_path, idx_path) = listing_paths(id)?;
+    let _ = fs::remove_file(idx_path);
+    let _ = fs::remove_file(data_path);
+    Ok(())
+}
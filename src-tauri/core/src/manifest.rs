@@ -0,0 +1,316 @@
+// Manifests of a list of files or a whole tree, as CSV or JSON, optionally
+// with a BLAKE3 hash per file. Handed over together with a copy of the
+// files, a manifest lets the receiving side check that every file arrived
+// intact.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::hashing::{self, HashCache};
+use crate::index::{self, now_ms, walk_files};
+use crate::paths::{normalize_input_path, relative_key, resolve_within};
+use crate::Progress;
+
+const FORMAT_VERSION: u32 = 1;
+const CSV_HEADER: &str = "path,size";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct ManifestOptions {
+    pub format: ManifestFormat,
+    // hash every file, so the receiver can check contents and not just sizes
+    pub hashes: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ManifestEntry {
+    // relative to the root, forward slashes
+    pub path: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blake3: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    root: String,
+    created_at: i64,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Default)]
+pub struct ManifestSummary {
+    pub files: u64,
+    pub bytes: u64,
+    pub hashed: u64,
+    // files that could not be read for hashing, left out of the manifest
+    pub unreadable: Vec<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct ManifestCheck {
+    // files present with the listed size and, when listed, hash
+    pub intact: u64,
+    // of those, files listed without a hash, so only their size was checked
+    pub size_only: u64,
+    pub missing: Vec<String>,
+    pub wrong_size: Vec<String>,
+    pub corrupted: Vec<String>,
+    pub unreadable: Vec<String>,
+}
+
+/// Files of `root` named by `relative_paths`, with folders expanded; all of
+/// them when the list is empty.
+fn collect(root_canon: &Path, relative_paths: &[String]) -> Result<BTreeMap<String, (PathBuf, u64)>, String> {
+    let mut files = BTreeMap::new();
+    let mut add = |p: PathBuf, size: u64| {
+        files.insert(relative_key(root_canon, &p), (p, size));
+    };
+    if relative_paths.is_empty() {
+        for (p, meta) in walk_files(root_canon) {
+            add(p, meta.len());
+        }
+        return Ok(files);
+    }
+    for rel in relative_paths {
+        let p = resolve_within(root_canon, rel)?;
+        let meta = fs::metadata(&p).map_err(|e| format!("Failed to read {rel}: {e}"))?;
+        if meta.is_dir() {
+            for (p, meta) in walk_files(&p) {
+                add(p, meta.len());
+            }
+        } else {
+            add(p, meta.len());
+        }
+    }
+    Ok(files)
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn to_csv(entries: &[ManifestEntry], hashes: bool) -> String {
+    let mut out = String::from(CSV_HEADER);
+    if hashes {
+        out.push_str(",blake3");
+    }
+    out.push('\n');
+    for e in entries {
+        out.push_str(&csv_field(&e.path));
+        out.push(',');
+        out.push_str(&e.size.to_string());
+        if let Some(hash) = &e.blake3 {
+            out.push(',');
+            out.push_str(hash);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Records of a CSV document; quoted fields may hold commas, quotes and
+/// line breaks.
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+fn parse_csv(text: &str) -> Result<Vec<ManifestEntry>, String> {
+    let mut records = csv_records(text).into_iter();
+    let header = records.next().ok_or("The manifest is empty")?;
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let (Some(path_col), Some(size_col)) = (column("path"), column("size")) else {
+        return Err("Not a manifest: the CSV needs path and size columns".into());
+    };
+    let hash_col = column("blake3");
+    let mut entries = Vec::new();
+    for (line, record) in records.enumerate() {
+        if record.iter().all(|f| f.is_empty()) {
+            continue;
+        }
+        let field = |i: usize| record.get(i).map(String::as_str).unwrap_or("");
+        let size = field(size_col)
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid size on manifest line {}", line + 2))?;
+        entries.push(ManifestEntry {
+            path: field(path_col).to_string(),
+            size,
+            blake3: hash_col.map(|i| field(i).trim().to_string()).filter(|h| !h.is_empty()),
+        });
+    }
+    Ok(entries)
+}
+
+/// Write a manifest of `relative_paths` of `root` (the whole tree when
+/// empty) to `destination`. With `hashes`, every file is hashed first;
+/// progress is reported in files hashed.
+pub fn export_manifest(
+    cache: &HashCache,
+    root: &str,
+    relative_paths: &[String],
+    destination: &Path,
+    options: ManifestOptions,
+    threads: Option<usize>,
+    on_progress: Progress,
+) -> Result<ManifestSummary, String> {
+    let root_canon = index::canonical_root(root)?;
+    let files = collect(&root_canon, relative_paths)?;
+    let mut summary = ManifestSummary::default();
+    let hashes: Vec<Option<io::Result<String>>> = if options.hashes {
+        let jobs: Vec<(PathBuf, u64)> = files.values().cloned().collect();
+        let total = jobs.len() as u64;
+        let results = hashing::hash_parallel(&jobs, threads, &|p, on_read| cache.hash_with(p, on_read), &|done| {
+            on_progress(done, total)
+        });
+        results.into_iter().map(Some).collect()
+    } else {
+        files.values().map(|_| None).collect()
+    };
+    let mut entries = Vec::with_capacity(files.len());
+    for ((rel, (_, size)), hash) in files.into_iter().zip(hashes) {
+        let blake3 = match hash {
+            Some(Ok(hash)) => Some(hash),
+            Some(Err(_)) => {
+                summary.unreadable.push(rel);
+                continue;
+            }
+            None => None,
+        };
+        summary.files += 1;
+        summary.bytes += size;
+        summary.hashed += blake3.is_some() as u64;
+        entries.push(ManifestEntry {
+            path: rel,
+            size,
+            blake3,
+        });
+    }
+    let data = match options.format {
+        ManifestFormat::Csv => to_csv(&entries, options.hashes).into_bytes(),
+        ManifestFormat::Json => {
+            let manifest = Manifest {
+                version: FORMAT_VERSION,
+                root: root_canon.display().to_string(),
+                created_at: now_ms(),
+                files: entries,
+            };
+            serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Failed to serialize manifest: {e}"))?
+        }
+    };
+    fs::write(destination, data).map_err(|e| format!("Failed to write {}: {e}", destination.display()))?;
+    Ok(summary)
+}
+
+fn read_manifest(path: &Path) -> Result<Vec<ManifestEntry>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let entries = if text.trim_start().starts_with('{') {
+        let manifest: Manifest = serde_json::from_str(&text).map_err(|e| format!("Not a manifest: {e}"))?;
+        if manifest.version > FORMAT_VERSION {
+            return Err(format!(
+                "The manifest was written by a newer version (format {})",
+                manifest.version
+            ));
+        }
+        manifest.files
+    } else {
+        parse_csv(&text)?
+    };
+    // A manifest comes from elsewhere; it may only name files inside the root
+    let escapes = |p: &str| {
+        Path::new(&normalize_input_path(p))
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    };
+    if let Some(bad) = entries.iter().find(|e| e.path.is_empty() || escapes(&e.path)) {
+        return Err(format!("Manifest entry {} points outside the destination", bad.path));
+    }
+    Ok(entries)
+}
+
+/// Check that `root` holds every file listed in the manifest at
+/// `manifest_path` (CSV or JSON), with the listed size and hash. Every file
+/// is read again rather than trusting the hash cache, which only sees size
+/// and mtime and so would miss bit rot. Progress is reported in files
+/// checked.
+pub fn verify_manifest(
+    manifest_path: &Path,
+    root: &str,
+    threads: Option<usize>,
+    on_progress: Progress,
+) -> Result<ManifestCheck, String> {
+    let root_canon = index::canonical_root(root)?;
+    let entries = read_manifest(manifest_path)?;
+    let mut check = ManifestCheck::default();
+    let mut to_hash: Vec<(PathBuf, u64)> = Vec::new();
+    let mut expected: Vec<(String, String)> = Vec::new();
+    for entry in entries {
+        let p = root_canon.join(normalize_input_path(&entry.path));
+        match fs::metadata(&p) {
+            Ok(meta) if meta.is_file() && meta.len() == entry.size => match entry.blake3 {
+                Some(hash) => {
+                    to_hash.push((p, entry.size));
+                    expected.push((entry.path, hash));
+                }
+                None => {
+                    check.intact += 1;
+                    check.size_only += 1;
+                }
+            },
+            Ok(meta) if meta.is_file() => check.wrong_size.push(entry.path),
+            _ => check.missing.push(entry.path),
+        }
+    }
+    let total = to_hash.len() as u64;
+    let hashes = hashing::hash_parallel(&to_hash, threads, &hashing::hash_file_with, &|done| {
+        on_progress(done, total)
+    });
+    for ((rel, want), got) in expected.into_iter().zip(hashes) {
+        match got {
+            Ok(got) if got.eq_ignore_ascii_case(&want) => check.intact += 1,
+            Ok(_) => check.corrupted.push(rel),
+            Err(_) => check.unreadable.push(rel),
+        }
+    }
+    Ok(check)
+}
@@ -0,0 +1,196 @@
+// Damage detection for media after card failures or interrupted copies:
+// images are decoded, MP4/MOV box structure is walked, zip archives have
+// their CRCs checked.
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::imaging;
+use crate::index::walk_files;
+use crate::paths::{relative_key, resolve_within};
+use crate::Progress;
+
+#[derive(Serialize)]
+pub struct DamagedFile {
+    pub relative_path: String,
+    // "image", "video" or "archive"
+    pub kind: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct MediaScanReport {
+    pub checked: u64,
+    // files of types this scan doesn't know how to check
+    pub skipped: u64,
+    pub damaged: Vec<DamagedFile>,
+}
+
+enum Kind {
+    Image,
+    Video,
+    Archive,
+}
+
+fn kind_of(path: &Path) -> Option<Kind> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    match ext.as_str() {
+        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tif" | "tiff" | "webp" => Some(Kind::Image),
+        // Without libheif there is no decoder, so these would all look damaged
+        #[cfg(feature = "heic")]
+        "heic" | "heif" => Some(Kind::Image),
+        "mp4" | "m4v" | "mov" | "3gp" | "m4a" => Some(Kind::Video),
+        "zip" | "cbz" | "epub" | "docx" | "xlsx" | "pptx" | "jar" | "apk" => Some(Kind::Archive),
+        _ => None,
+    }
+}
+
+fn check_image(path: &Path, full: bool) -> Result<(), String> {
+    let data_start = {
+        let mut head = [0u8; 2];
+        File::open(path)
+            .and_then(|mut f| f.read_exact(&mut head))
+            .map_err(|e| e.to_string())?;
+        head
+    };
+    if data_start == [0xFF, 0xD8] {
+        // A JPEG cut short by a failing card still decodes (as grey), but
+        // loses its end-of-image marker
+        let mut f = File::open(path).map_err(|e| e.to_string())?;
+        let len = f.metadata().map_err(|e| e.to_string())?.len();
+        if len < 4 {
+            return Err("JPEG is truncated".into());
+        }
+        let mut tail = [0u8; 2];
+        f.seek(SeekFrom::End(-2)).map_err(|e| e.to_string())?;
+        f.read_exact(&mut tail).map_err(|e| e.to_string())?;
+        if tail != [0xFF, 0xD9] {
+            // Some cameras pad after EOI; look for it in the last few KiB
+            let back = len.min(16 * 1024);
+            let mut buf = vec![0u8; back as usize];
+            f.seek(SeekFrom::End(-(back as i64))).map_err(|e| e.to_string())?;
+            f.read_exact(&mut buf).map_err(|e| e.to_string())?;
+            if !buf.windows(2).any(|w| w == [0xFF, 0xD9]) {
+                return Err("JPEG is truncated (no end-of-image marker)".into());
+            }
+        }
+    }
+    // The image crate can't read HEIC headers, so those are always decoded
+    if full || imaging::is_heic(path) {
+        imaging::load_image(path).map(|_| ())
+    } else {
+        image::ImageReader::open(path)
+            .map_err(|e| e.to_string())?
+            .with_guessed_format()
+            .map_err(|e| e.to_string())?
+            .into_dimensions()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+// ISO base media files are a sequence of boxes whose sizes must add up to
+// the file length exactly, with a `moov` box holding the index
+fn check_isobmff(path: &Path) -> Result<(), String> {
+    let mut f = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+    let len = fs::metadata(path).map_err(|e| e.to_string())?.len();
+    let mut pos: u64 = 0;
+    let mut has_moov = false;
+    let mut has_ftyp = false;
+    while pos < len {
+        if len - pos < 8 {
+            return Err(format!("Trailing garbage at offset {pos}"));
+        }
+        let mut header = [0u8; 8];
+        f.seek(SeekFrom::Start(pos)).map_err(|e| e.to_string())?;
+        f.read_exact(&mut header).map_err(|e| e.to_string())?;
+        let kind = &header[4..8];
+        let mut size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        if size == 1 {
+            let mut large = [0u8; 8];
+            f.read_exact(&mut large).map_err(|e| e.to_string())?;
+            size = u64::from_be_bytes(large);
+        } else if size == 0 {
+            // box runs to end of file
+            size = len - pos;
+        }
+        if size < 8 {
+            return Err(format!("Invalid box size at offset {pos}"));
+        }
+        if pos + size > len {
+            return Err(format!(
+                "File is truncated: '{}' box needs {} more bytes",
+                String::from_utf8_lossy(kind),
+                pos + size - len
+            ));
+        }
+        has_moov |= kind == b"moov";
+        has_ftyp |= kind == b"ftyp";
+        pos += size;
+    }
+    if !has_ftyp {
+        return Err("Not an MP4/MOV file (no ftyp box)".into());
+    }
+    if !has_moov {
+        return Err("Missing moov box (recording was never finalized)".into());
+    }
+    Ok(())
+}
+
+fn check_zip(path: &Path, full: bool) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    if full {
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+            let name = entry.name().to_string();
+            // Reading to the end verifies the entry's CRC
+            io::copy(&mut entry, &mut io::sink()).map_err(|e| format!("{name}: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Check media below `relative_dir`. The quick mode reads headers and
+/// structure only; `full_decode` decodes every image and reads every
+/// archive entry.
+pub fn scan_damaged_media(
+    root: &str,
+    relative_dir: &str,
+    full_decode: bool,
+    on_progress: Progress,
+) -> Result<MediaScanReport, String> {
+    let root_path = Path::new(root);
+    let root_canon = root_path
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let dir = resolve_within(root_path, relative_dir)?;
+    let files = walk_files(&dir);
+    let total = files.len() as u64;
+
+    let mut report = MediaScanReport::default();
+    for (n, (p, _)) in files.iter().enumerate() {
+        let checked = match kind_of(p) {
+            Some(Kind::Image) => Some(("image", check_image(p, full_decode))),
+            Some(Kind::Video) => Some(("video", check_isobmff(p))),
+            Some(Kind::Archive) => Some(("archive", check_zip(p, full_decode))),
+            None => None,
+        };
+        match checked {
+            Some((kind, result)) => {
+                report.checked += 1;
+                if let Err(error) = result {
+                    report.damaged.push(DamagedFile {
+                        relative_path: relative_key(&root_canon, p),
+                        kind: kind.to_string(),
+                        error,
+                    });
+                }
+            }
+            None => report.skipped += 1,
+        }
+        on_progress(n as u64 + 1, total);
+    }
+    Ok(report)
+}
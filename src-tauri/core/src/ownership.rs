@@ -0,0 +1,85 @@
+// Make files on a rescued drive editable by the current user: ownership is
+// taken over where the OS allows it (as root, or files already ours) and
+// owner read/write is restored. On Windows the read-only attribute is
+// cleared instead.
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::paths::{relative_key, resolve_within};
+
+#[derive(Serialize, Default)]
+pub struct OwnershipReport {
+    pub changed: u64,
+    pub unchanged: u64,
+    // relative paths that could not be changed, with the reason
+    pub failed: Vec<String>,
+}
+
+#[cfg(unix)]
+extern "C" {
+    fn geteuid() -> u32;
+    fn getegid() -> u32;
+}
+
+/// Whether anything had to change. Symlinks are left alone.
+#[cfg(unix)]
+fn fix(path: &Path, meta: &fs::Metadata) -> std::io::Result<bool> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    // SAFETY: plain libc getters without arguments
+    let (uid, gid) = unsafe { (geteuid(), getegid()) };
+    let mut changed = false;
+    if meta.uid() != uid || meta.gid() != gid {
+        std::os::unix::fs::chown(path, Some(uid), Some(gid))?;
+        changed = true;
+    }
+    let wanted = if meta.is_dir() { 0o700 } else { 0o600 };
+    let mode = meta.permissions().mode();
+    if mode & wanted != wanted {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode | wanted))?;
+        changed = true;
+    }
+    Ok(changed)
+}
+
+#[cfg(not(unix))]
+fn fix(path: &Path, meta: &fs::Metadata) -> std::io::Result<bool> {
+    let mut permissions = meta.permissions();
+    if !permissions.readonly() {
+        return Ok(false);
+    }
+    permissions.set_readonly(false);
+    fs::set_permissions(path, permissions)?;
+    Ok(true)
+}
+
+/// Take ownership of a folder below `root` and the entries directly in it,
+/// or with `recursive` of everything beneath it.
+pub fn take_ownership(root: &str, relative_dir: &str, recursive: bool) -> Result<OwnershipReport, String> {
+    let root_canon = PathBuf::from(root)
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let start = resolve_within(&root_canon, relative_dir)?;
+    let mut report = OwnershipReport::default();
+    let mut stack = vec![(start, 0u32)];
+    while let Some((p, depth)) = stack.pop() {
+        let meta = match fs::symlink_metadata(&p) {
+            Ok(m) if !m.file_type().is_symlink() => m,
+            _ => continue,
+        };
+        match fix(&p, &meta) {
+            Ok(true) => report.changed += 1,
+            Ok(false) => report.unchanged += 1,
+            Err(e) => report
+                .failed
+                .push(format!("{}: {e}", relative_key(&root_canon, &p))),
+        }
+        // Listed after the fix, which may be what makes the folder readable
+        if meta.is_dir() && (recursive || depth == 0) {
+            if let Ok(read_dir) = fs::read_dir(&p) {
+                stack.extend(read_dir.flatten().map(|e| (e.path(), depth + 1)));
+            }
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,127 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::vfs::{FileSystem, RealFs};
+
+pub fn normalize_input_path(s: &str) -> String {
+    #[cfg(target_os = "windows")]
+    {
+        return s.replace('/', "\\");
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        return s.to_string();
+    }
+}
+
+/// Identity of the file system `path` is on, to tell when the drive holding
+/// it was swapped or unplugged. None when `path` can't be reached.
+pub fn volume_of(path: &Path) -> Option<u64> {
+    let meta = std::fs::metadata(path).ok()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(meta.dev())
+    }
+    #[cfg(not(unix))]
+    {
+        // A drive letter going away is enough to notice there
+        let _ = meta;
+        Some(0)
+    }
+}
+
+pub fn is_hidden(path: &Path) -> bool {
+    // Check if filename starts with dot (Unix-style hidden)
+    if let Some(name) = path.file_name() {
+        if let Some(name_str) = name.to_str() {
+            if name_str.starts_with('.') {
+                return true;
+            }
+        }
+    }
+    
+    // On Windows, check hidden attribute
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::MetadataExt;
+        if let Ok(metadata) = path.metadata() {
+            const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+            return (metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN) != 0;
+        }
+    }
+    
+    false
+}
+
+pub fn canonical_within(root: &Path, candidate: &Path) -> Result<PathBuf, String> {
+    canonical_within_on(&RealFs, root, candidate)
+}
+
+/// `canonical_within` on any file system.
+pub fn canonical_within_on(fs: &dyn FileSystem, root: &Path, candidate: &Path) -> Result<PathBuf, String> {
+    let root = fs
+        .canonicalize(root)
+        .map_err(|e| format!("Failed to canonicalize root: {e}"))?;
+    let cand = fs
+        .canonicalize(candidate)
+        .map_err(|e| format!("Failed to canonicalize path: {e}"))?;
+    if cand.starts_with(&root) {
+        Ok(cand)
+    } else {
+        Err("Path escapes selected root".to_string())
+    }
+}
+
+/// Resolve a frontend-style relative path (forward slashes, optional leading
+/// slash) to an existing canonical path inside `root`.
+pub fn resolve_within(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    resolve_within_on(&RealFs, root, relative)
+}
+
+/// `resolve_within` on any file system.
+pub fn resolve_within_on(fs: &dyn FileSystem, root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let rel_norm = normalize_input_path(relative.trim_start_matches('/'));
+    canonical_within_on(fs, root, &root.join(rel_norm))
+}
+
+/// Path of `to` as seen from the folder `from_dir`, both absolute and
+/// normalized, e.g. `../b/c.txt`.
+pub fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from: Vec<_> = from_dir.components().collect();
+    let to_parts: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to_parts).take_while(|(a, b)| a == b).count();
+    let mut out = PathBuf::new();
+    for _ in common..from.len() {
+        out.push("..");
+    }
+    for part in &to_parts[common..] {
+        out.push(part);
+    }
+    out
+}
+
+/// `dir` joined with `rel`, with `.` and `..` resolved without touching the
+/// file system, for paths that may not exist (anymore).
+pub fn lexical_join(dir: &Path, rel: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for part in dir.join(rel).components() {
+        match part {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Path of `p` relative to `root_canon`, with forward slashes for the frontend.
+pub fn relative_key(root_canon: &Path, p: &Path) -> String {
+    let rel = p.strip_prefix(root_canon).unwrap_or(p);
+    let mut rel_str = rel.display().to_string();
+    if cfg!(windows) {
+        rel_str = rel_str.replace("\\", "/");
+    }
+    rel_str
+}
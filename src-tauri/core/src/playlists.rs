@@ -0,0 +1,176 @@
+// Keeps .m3u/.m3u8/.pls playlists on a drive pointing at the right files
+// when media is moved around.
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::fsops;
+use crate::index::walk_files;
+use crate::paths::{lexical_join, relative_key, relative_path, resolve_within};
+
+const PLAYLIST_EXTENSIONS: &[&str] = &["m3u", "m3u8", "pls"];
+
+#[derive(Serialize, Default)]
+pub struct PlaylistUpdate {
+    // relative paths of the playlists that were rewritten
+    pub updated: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+fn is_playlist(p: &Path) -> bool {
+    p.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| PLAYLIST_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+fn is_absolute(reference: &str) -> bool {
+    Path::new(&reference.replace('\\', "/")).is_absolute() || reference.contains(":\\")
+}
+
+// `path` with the separators `reference` uses
+fn in_style_of(reference: &str, path: &Path) -> String {
+    let text = path.display().to_string();
+    if reference.contains('\\') {
+        text.replace('/', "\\")
+    } else {
+        text.replace('\\', "/")
+    }
+}
+
+// The entry a playlist would need to reach `new` from its folder, in the
+// same form as the old one: absolute or relative, with the same separators.
+fn rewritten(reference: &str, playlist_dir: &Path, new: &Path) -> String {
+    if is_absolute(reference) {
+        in_style_of(reference, new)
+    } else {
+        in_style_of(reference, &relative_path(playlist_dir, new))
+    }
+}
+
+fn points_to(reference: &str, playlist_dir: &Path, target: &Path) -> bool {
+    if reference.contains("://") {
+        return false;
+    }
+    let reference = PathBuf::from(reference.replace('\\', "/"));
+    lexical_join(playlist_dir, &reference) == target
+}
+
+// Where the part below the root starts in an absolute `reference` that
+// ends with `target`'s path relative to the root: the drive was mounted
+// elsewhere, e.g. on another machine, when the playlist was written.
+fn root_relative_start(reference: &str, root_canon: &Path, target: &Path) -> Option<usize> {
+    if !is_absolute(reference) || reference.contains("://") {
+        return None;
+    }
+    let relative = target.strip_prefix(root_canon).ok()?.to_str()?.replace('\\', "/");
+    let normalized = reference.replace('\\', "/");
+    if relative.is_empty() || !normalized.ends_with(&relative) {
+        return None;
+    }
+    let start = normalized.len() - relative.len();
+    normalized[..start].ends_with('/').then_some(start)
+}
+
+// The rewritten entry for `reference`, if it points at `old`
+fn rewrite(reference: &str, playlist_dir: &Path, root_canon: &Path, old: &Path, new: &Path) -> Option<String> {
+    if points_to(reference, playlist_dir, old) {
+        return Some(rewritten(reference, playlist_dir, new));
+    }
+    // Keep the other machine's mount point, so the entry still works there
+    let start = root_relative_start(reference, root_canon, old)?;
+    let relative = new.strip_prefix(root_canon).ok()?;
+    Some(format!("{}{}", &reference[..start], in_style_of(reference, relative)))
+}
+
+// A name next to `playlist` that nothing else uses, for writing it anew
+fn temporary_for(playlist: &Path) -> std::io::Result<(PathBuf, fs::File)> {
+    let name = playlist.file_name().unwrap_or_default().to_string_lossy();
+    let mut n = 0u64;
+    loop {
+        let tmp = playlist.with_file_name(format!(".{name}.lfm-{n}.tmp"));
+        n += 1;
+        match fs::OpenOptions::new().write(true).create_new(true).open(&tmp) {
+            Ok(file) => return Ok((tmp, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Rewrite the entries of one playlist that point at `old`. Returns whether
+/// anything changed.
+fn update_playlist(playlist: &Path, root_canon: &Path, old: &Path, new: &Path) -> std::io::Result<bool> {
+    let text = fs::read_to_string(playlist)?;
+    let dir = playlist.parent().unwrap_or(Path::new(""));
+    let pls = playlist.extension().is_some_and(|e| e.eq_ignore_ascii_case("pls"));
+    let mut changed = false;
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[body.len()..];
+        // .pls: FileN=path; .m3u: every line that isn't a #directive
+        let (key, reference) = match body.split_once('=') {
+            Some((key, value)) if pls && key.trim().to_ascii_lowercase().starts_with("file") => (Some(key), value),
+            _ if !pls && !body.trim().is_empty() && !body.starts_with('#') => (None, body),
+            _ => (None, ""),
+        };
+        let new_ref = match reference.trim() {
+            "" => None,
+            reference => rewrite(reference, dir, root_canon, old, new),
+        };
+        if let Some(new_ref) = new_ref {
+            match key {
+                Some(key) => out.push_str(&format!("{key}={new_ref}{ending}")),
+                None => out.push_str(&format!("{new_ref}{ending}")),
+            }
+            changed = true;
+        } else {
+            out.push_str(line);
+        }
+    }
+    if changed {
+        let (tmp, mut file) = temporary_for(playlist)?;
+        let written = file.write_all(out.as_bytes());
+        drop(file);
+        let written = written.and_then(|_| fs::rename(&tmp, playlist));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        written?;
+    }
+    Ok(changed)
+}
+
+/// Point every playlist under `root_canon` that references `old` at `new`.
+pub fn update_references(root_canon: &Path, old: &Path, new: &Path) -> PlaylistUpdate {
+    let mut report = PlaylistUpdate::default();
+    for (p, _) in walk_files(root_canon) {
+        if !is_playlist(&p) {
+            continue;
+        }
+        match update_playlist(&p, root_canon, old, new) {
+            Ok(true) => report.updated.push(relative_key(root_canon, &p)),
+            Ok(false) => {}
+            Err(e) => report.errors.push(format!("{}: {e}", relative_key(root_canon, &p))),
+        }
+    }
+    report
+}
+
+/// `fsops::move_file`, then fix up the playlists on the drive that
+/// referenced the file.
+pub fn move_with_playlists(
+    root: &str,
+    from_relative: &str,
+    to_relative_dir: &str,
+    create_dir: bool,
+) -> Result<PlaylistUpdate, String> {
+    let root_path = Path::new(root);
+    let root_canon = root_path.canonicalize().map_err(|e| format!("Invalid root: {e}"))?;
+    let old = resolve_within(root_path, from_relative)?;
+    fsops::move_file(root, from_relative, to_relative_dir, create_dir)?;
+    let name = old.file_name().ok_or("Source file has no name")?;
+    let new = resolve_within(root_path, to_relative_dir)?.join(name);
+    Ok(update_references(&root_canon, &old, &new))
+}
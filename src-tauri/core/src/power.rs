@@ -0,0 +1,77 @@
+// Keeps the machine awake while long jobs run, so an overnight mirror
+// isn't cut short by the laptop going to sleep. Uses systemd-inhibit on
+// Linux, caffeinate on macOS and SetThreadExecutionState on Windows.
+
+/// Held while sleep is inhibited; dropping it lets the system sleep again.
+pub struct SleepInhibitor {
+    #[cfg(unix)]
+    child: std::process::Child,
+    #[cfg(windows)]
+    _release: std::sync::mpsc::Sender<()>,
+}
+
+/// Prevent idle system sleep until the returned value is dropped. `None`
+/// when the platform mechanism isn't available, e.g. no systemd.
+#[cfg(target_os = "linux")]
+pub fn inhibit(reason: &str) -> Option<SleepInhibitor> {
+    use std::process::{Command, Stdio};
+    let child = Command::new("systemd-inhibit")
+        .args(["--what=sleep:idle", "--who=sdmanager", "--mode=block"])
+        .arg(format!("--why={reason}"))
+        .args(["sleep", "infinity"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    Some(SleepInhibitor { child })
+}
+
+#[cfg(target_os = "macos")]
+pub fn inhibit(_reason: &str) -> Option<SleepInhibitor> {
+    use std::process::{Command, Stdio};
+    // -i: no idle sleep; -w: give up by itself should this process die
+    let child = Command::new("caffeinate")
+        .arg("-i")
+        .arg("-w")
+        .arg(std::process::id().to_string())
+        .stdin(Stdio::null())
+        .spawn()
+        .ok()?;
+    Some(SleepInhibitor { child })
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+pub fn inhibit(_reason: &str) -> Option<SleepInhibitor> {
+    None
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn SetThreadExecutionState(flags: u32) -> u32;
+}
+
+#[cfg(windows)]
+pub fn inhibit(_reason: &str) -> Option<SleepInhibitor> {
+    const ES_CONTINUOUS: u32 = 0x8000_0000;
+    const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+    // The execution state belongs to a thread, so a dedicated one holds it
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
+    std::thread::spawn(move || {
+        // SAFETY: plain Win32 call taking flags by value
+        unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+        // Returns once the sender is dropped
+        let _ = rx.recv();
+        unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+    });
+    Some(SleepInhibitor { _release: tx })
+}
+
+#[cfg(unix)]
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,140 @@
+// Named profiles, each with its own metadata store, for machines shared by
+// people who organize the same drives differently. The default profile is
+// the app data directory itself; others live under `profiles/<name>`.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::store::Store;
+
+pub const DEFAULT_PROFILE: &str = "default";
+/// Document (in the base directory) naming the active profile.
+pub const ACTIVE_DOC: &str = "active_profile";
+/// Directory, relative to the base one, holding the non-default profiles.
+pub const PROFILES_DIR: &str = "profiles";
+
+#[derive(Serialize, Deserialize)]
+struct ActiveProfile {
+    name: String,
+}
+
+impl Default for ActiveProfile {
+    fn default() -> Self {
+        ActiveProfile {
+            name: DEFAULT_PROFILE.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub active: bool,
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let ok = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ' ');
+    if !ok || name.trim() != name {
+        return Err("Profile names may only contain letters, digits, spaces, '-' and '_'".into());
+    }
+    Ok(())
+}
+
+fn profile_dir(base: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        base.to_path_buf()
+    } else {
+        base.join(PROFILES_DIR).join(name)
+    }
+}
+
+// Kept as a plain file rather than a store document: it has to stay
+// readable and writable while the default profile's store is locked.
+fn active_path(base: &Path) -> PathBuf {
+    base.join(format!("{ACTIVE_DOC}.json"))
+}
+
+pub fn active_profile(base: &Path) -> Result<String, String> {
+    let active: ActiveProfile = match fs::read(active_path(base)) {
+        Ok(data) => serde_json::from_slice(&data).unwrap_or_default(),
+        Err(_) => ActiveProfile::default(),
+    };
+    Ok(active.name)
+}
+
+/// Store of the named profile, which must exist.
+pub fn profile_store(base: &Path, name: &str) -> Result<Store, String> {
+    validate_name(name)?;
+    let dir = profile_dir(base, name);
+    if !dir.is_dir() {
+        return Err(format!("No such profile: {name}"));
+    }
+    Store::open(dir)
+}
+
+/// Store of whichever profile is active.
+pub fn active_store(base: &Path) -> Result<Store, String> {
+    let name = active_profile(base)?;
+    // A profile deleted behind our back falls back to the default one
+    profile_store(base, &name).or_else(|_| Store::open(base.to_path_buf()))
+}
+
+pub fn list_profiles(base: &Path) -> Result<Vec<ProfileInfo>, String> {
+    let active = active_profile(base)?;
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    if let Ok(read_dir) = fs::read_dir(base.join(PROFILES_DIR)) {
+        let mut others: Vec<String> = read_dir
+            .flatten()
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        others.sort();
+        names.extend(others);
+    }
+    Ok(names
+        .into_iter()
+        .map(|name| ProfileInfo {
+            active: name == active,
+            name,
+        })
+        .collect())
+}
+
+pub fn create_profile(base: &Path, name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    let dir = profile_dir(base, name);
+    if dir.exists() {
+        return Err(format!("Profile {name} already exists"));
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create profile: {e}"))
+}
+
+pub fn switch_profile(base: &Path, name: &str) -> Result<(), String> {
+    profile_store(base, name)?;
+    let data = serde_json::to_vec(&ActiveProfile {
+        name: name.to_string(),
+    })
+    .map_err(|e| e.to_string())?;
+    fs::write(active_path(base), data).map_err(|e| format!("Failed to switch profile: {e}"))
+}
+
+/// Delete a profile and everything stored in it. The default profile and
+/// the active one can't be deleted.
+pub fn delete_profile(base: &Path, name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    if name == DEFAULT_PROFILE {
+        return Err("The default profile can't be deleted".into());
+    }
+    if active_profile(base)? == name {
+        return Err("Switch to another profile before deleting this one".into());
+    }
+    let dir = profile_dir(base, name);
+    if !dir.is_dir() {
+        return Err(format!("No such profile: {name}"));
+    }
+    fs::remove_dir_all(&dir).map_err(|e| format!("Failed to delete profile: {e}"))
+}
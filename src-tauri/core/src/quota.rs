@@ -0,0 +1,152 @@
+// Soft size limits on folders, e.g. to keep an Inbox/ from swallowing a
+// drive. Operations that would push a folder over its limit are refused
+// unless the caller explicitly overrides.
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use crate::index::walk_files;
+use crate::paths::{normalize_input_path, resolve_within};
+use crate::settings::Settings;
+use crate::store::Store;
+use crate::sync::{self, SyncOptions};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FolderQuota {
+    // absolute path of the folder
+    pub path: String,
+    pub limit_bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct QuotaStatus {
+    pub path: String,
+    pub limit_bytes: u64,
+    pub used_bytes: u64,
+}
+
+pub fn validate(quota: &FolderQuota) -> Result<(), String> {
+    if quota.path.trim().is_empty() || !Path::new(&quota.path).is_absolute() {
+        return Err(format!("Folder limit needs an absolute path: {}", quota.path));
+    }
+    if quota.limit_bytes == 0 {
+        return Err(format!("Folder limit for {} must be above zero", quota.path));
+    }
+    Ok(())
+}
+
+fn folder_size(dir: &Path) -> u64 {
+    walk_files(dir).iter().map(|(_, meta)| meta.len()).sum()
+}
+
+fn gb(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / 1e9)
+}
+
+/// The configured folders that exist here, canonicalized.
+fn present(quotas: &[FolderQuota]) -> Vec<(PathBuf, &FolderQuota)> {
+    quotas
+        .iter()
+        .filter_map(|q| Path::new(&q.path).canonicalize().ok().map(|p| (p, q)))
+        .collect()
+}
+
+/// `path`, or its closest existing parent when it doesn't exist yet (e.g.
+/// a folder a move will create), canonicalized.
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    let mut path = path.to_path_buf();
+    while !path.exists() {
+        if !path.pop() {
+            return None;
+        }
+    }
+    path.canonicalize().ok()
+}
+
+/// Refuse to add `incoming` bytes to `destination` when that would take a
+/// limited folder containing it over its limit. `source` is where the bytes
+/// come from, if they are moved: moves within the limited folder are free.
+pub fn check(quotas: &[FolderQuota], destination: &Path, source: Option<&Path>, incoming: u64) -> Result<(), String> {
+    if quotas.is_empty() || incoming == 0 {
+        return Ok(());
+    }
+    let Some(dest) = existing_ancestor(destination) else {
+        return Ok(());
+    };
+    let source = source.and_then(|s| s.canonicalize().ok());
+    for (folder, quota) in present(quotas) {
+        if !dest.starts_with(&folder) || source.as_ref().is_some_and(|s| s.starts_with(&folder)) {
+            continue;
+        }
+        let used = folder_size(&folder);
+        if used + incoming > quota.limit_bytes {
+            return Err(format!(
+                "{} would grow to {} over its {} limit; confirm to go ahead anyway",
+                quota.path,
+                gb(used + incoming),
+                gb(quota.limit_bytes)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `check` for moving files of `root` into `to_relative_dir`.
+pub fn check_move(
+    quotas: &[FolderQuota],
+    root: &str,
+    from_relatives: &[String],
+    to_relative_dir: &str,
+) -> Result<(), String> {
+    if quotas.is_empty() {
+        return Ok(());
+    }
+    let root = Path::new(root);
+    let destination = root.join(normalize_input_path(to_relative_dir.trim().trim_start_matches('/')));
+    let mut incoming = 0;
+    let mut source = None;
+    for rel in from_relatives {
+        let path = resolve_within(root, rel)?;
+        incoming += path.metadata().map(|m| m.len()).unwrap_or(0);
+        source = path.parent().map(Path::to_path_buf);
+    }
+    check(quotas, &destination, source.as_deref(), incoming)
+}
+
+/// `check` for a sync, sized by a dry run of it. Only walks the trees when
+/// a limited folder is involved.
+pub fn check_sync(
+    store: &Store,
+    settings: &Settings,
+    source: &Path,
+    destination: &Path,
+    options: SyncOptions,
+) -> Result<(), String> {
+    let quotas = &settings.folder_quotas;
+    let applies = existing_ancestor(destination)
+        .is_some_and(|dest| present(quotas).iter().any(|(folder, _)| dest.starts_with(folder)));
+    if options.dry_run || !applies {
+        return Ok(());
+    }
+    let dry_run = SyncOptions {
+        dry_run: true,
+        ..options
+    };
+    let cancel = AtomicBool::new(false);
+    let plan = sync::sync_trees(store, settings, source, destination, dry_run, &cancel, &|_, _| {})?;
+    check(quotas, destination, None, plan.bytes)
+}
+
+/// Limited folders currently over their limit. Folders on drives that
+/// aren't plugged in are left out.
+pub fn over_quota(quotas: &[FolderQuota]) -> Vec<QuotaStatus> {
+    present(quotas)
+        .into_iter()
+        .map(|(folder, quota)| QuotaStatus {
+            path: quota.path.clone(),
+            limit_bytes: quota.limit_bytes,
+            used_bytes: folder_size(&folder),
+        })
+        .filter(|s| s.used_bytes > s.limit_bytes)
+        .collect()
+}
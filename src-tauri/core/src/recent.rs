@@ -0,0 +1,107 @@
+// "What changed on this drive since I last looked": a bounded walk for
+// recently modified files, plus a per-root record of the last visit.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::index::{canonical_root, mtime_ms, now_ms};
+use crate::paths::relative_key;
+use crate::store::Store;
+
+const SEEN_DOC: &str = "recent/seen";
+// Without a recorded visit, look back this far
+const DEFAULT_LOOKBACK_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+// Stop walking after this many entries so a huge NAS can't stall the UI
+const MAX_WALK_ENTRIES: u64 = 500_000;
+
+#[derive(Serialize, Deserialize, Default)]
+struct SeenRoots {
+    // canonical root path -> last visit (ms since epoch)
+    roots: BTreeMap<String, i64>,
+}
+
+#[derive(Serialize)]
+pub struct RecentChange {
+    pub relative_path: String,
+    pub size: u64,
+    pub mtime_ms: i64,
+}
+
+#[derive(Serialize)]
+pub struct RecentChanges {
+    pub since: i64,
+    // newest first
+    pub changes: Vec<RecentChange>,
+    // the walk or the result list hit its limit
+    pub truncated: bool,
+}
+
+/// Record that the user has looked at this root now.
+pub fn mark_root_seen(store: &Store, root: &str) -> Result<(), String> {
+    let root_canon = canonical_root(root)?;
+    let mut seen: SeenRoots = store.load(SEEN_DOC)?;
+    seen.roots.insert(root_canon.display().to_string(), now_ms());
+    store.save(SEEN_DOC, &seen)
+}
+
+/// Files modified after `since` (ms since epoch). Without `since`, the last
+/// recorded visit of the root is used, or the past week.
+pub fn recent_changes(store: &Store, root: &str, since: Option<i64>, limit: usize) -> Result<RecentChanges, String> {
+    let root_canon = canonical_root(root)?;
+    let since = match since {
+        Some(s) => s,
+        None => {
+            let seen: SeenRoots = store.load(SEEN_DOC)?;
+            seen.roots
+                .get(&root_canon.display().to_string())
+                .copied()
+                .unwrap_or_else(|| now_ms() - DEFAULT_LOOKBACK_MS)
+        }
+    };
+
+    let mut changes: Vec<RecentChange> = Vec::new();
+    let mut truncated = false;
+    let mut visited: u64 = 0;
+    let mut stack: Vec<PathBuf> = vec![root_canon.clone()];
+    'walk: while let Some(dir) = stack.pop() {
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        for entry in read_dir.flatten() {
+            visited += 1;
+            if visited > MAX_WALK_ENTRIES {
+                truncated = true;
+                break 'walk;
+            }
+            let p = entry.path();
+            let meta = match fs::symlink_metadata(&p) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            if meta.is_dir() {
+                // A folder's mtime only changes with its direct entries, so
+                // it can't be used to prune the walk
+                stack.push(p);
+            } else if meta.is_file() && mtime_ms(&meta) > since {
+                changes.push(RecentChange {
+                    relative_path: relative_key(&root_canon, &p),
+                    size: meta.len(),
+                    mtime_ms: mtime_ms(&meta),
+                });
+            }
+        }
+    }
+
+    changes.sort_by(|a, b| b.mtime_ms.cmp(&a.mtime_ms));
+    if changes.len() > limit {
+        changes.truncate(limit);
+        truncated = true;
+    }
+    Ok(RecentChanges {
+        since,
+        changes,
+        truncated,
+    })
+}
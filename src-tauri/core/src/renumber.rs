@@ -0,0 +1,196 @@
+// Numbered file sequences like IMG_0001.JPG … IMG_0999.JPG: finding them,
+// closing the gaps left by deletions, and shifting them so two camera dumps
+// can share a folder without colliding.
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::paths::{relative_key, resolve_within};
+
+#[derive(Serialize)]
+pub struct Sequence {
+    pub prefix: String,
+    // digits in the number, zero padded
+    pub width: usize,
+    pub first: u64,
+    pub last: u64,
+    // distinct numbers; a number can cover several files (JPG + RAW + XMP)
+    pub count: u64,
+    pub files: u64,
+}
+
+#[derive(Serialize)]
+pub struct SequenceRename {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize)]
+pub struct RenumberReport {
+    pub renames: Vec<SequenceRename>,
+    pub dry_run: bool,
+}
+
+struct Numbered {
+    name: String,
+    prefix: String,
+    width: usize,
+    number: u64,
+    // everything after the number, from the first dot on
+    rest: String,
+}
+
+fn parse(name: &str) -> Option<Numbered> {
+    let dot = name.find('.').unwrap_or(name.len());
+    let (stem, rest) = name.split_at(dot);
+    let digits_at = stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let digits = &stem[digits_at..];
+    // Cap at 18 digits so the number fits a u64
+    if digits.is_empty() || digits.len() > 18 {
+        return None;
+    }
+    Some(Numbered {
+        name: name.to_string(),
+        prefix: stem[..digits_at].to_string(),
+        width: digits.len(),
+        number: digits.parse().ok()?,
+        rest: rest.to_string(),
+    })
+}
+
+fn numbered_files(dir: &Path) -> Result<Vec<Numbered>, String> {
+    let read_dir = fs::read_dir(dir).map_err(|e| format!("Failed to read dir {}: {e}", dir.display()))?;
+    Ok(read_dir
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .filter_map(|e| parse(&e.file_name().to_string_lossy()))
+        .collect())
+}
+
+/// Numbered sequences directly inside a folder, keyed by prefix and width.
+pub fn find_sequences(root: &str, relative_dir: &str) -> Result<Vec<Sequence>, String> {
+    let dir = resolve_within(Path::new(root), relative_dir)?;
+    let mut groups: BTreeMap<(String, usize), (HashSet<u64>, u64)> = BTreeMap::new();
+    for f in numbered_files(&dir)? {
+        let (numbers, files) = groups.entry((f.prefix, f.width)).or_default();
+        numbers.insert(f.number);
+        *files += 1;
+    }
+    Ok(groups
+        .into_iter()
+        // A lone numbered file is not a sequence
+        .filter(|(_, (numbers, _))| numbers.len() > 1)
+        .map(|((prefix, width), (numbers, files))| Sequence {
+            prefix,
+            width,
+            first: numbers.iter().copied().min().unwrap_or(0),
+            last: numbers.iter().copied().max().unwrap_or(0),
+            count: numbers.len() as u64,
+            files,
+        })
+        .collect())
+}
+
+/// Renumber the `prefix`/`width` sequence in a folder contiguously from
+/// `start` (default: its current first number), keeping the order and
+/// giving files that shared a number the same new one. Nothing is renamed
+/// if any new name is taken by a file outside the sequence, and a failed
+/// rename puts every file back; with `dry_run` the renames are only reported.
+pub fn renumber_sequence(
+    root: &str,
+    relative_dir: &str,
+    prefix: &str,
+    width: usize,
+    start: Option<u64>,
+    dry_run: bool,
+) -> Result<RenumberReport, String> {
+    let root_path = Path::new(root);
+    let root_canon = root_path
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let dir = resolve_within(root_path, relative_dir)?;
+    let mut members: Vec<Numbered> = numbered_files(&dir)?
+        .into_iter()
+        .filter(|f| f.prefix == prefix && f.width == width)
+        .collect();
+    if members.is_empty() {
+        return Err("No such sequence in this folder".into());
+    }
+    members.sort_by(|a, b| (a.number, &a.rest).cmp(&(b.number, &b.rest)));
+
+    let mut next = start.unwrap_or(members[0].number);
+    let mut plan: Vec<(String, String)> = Vec::new();
+    let mut previous = None;
+    for f in &members {
+        if previous.is_some_and(|n| n != f.number) {
+            next += 1;
+        }
+        previous = Some(f.number);
+        let new_name = format!("{prefix}{next:0width$}{}", f.rest);
+        if new_name != f.name {
+            plan.push((f.name.clone(), new_name));
+        }
+    }
+
+    let own: HashSet<&str> = members.iter().map(|f| f.name.as_str()).collect();
+    if let Some((_, taken)) = plan
+        .iter()
+        .find(|(_, to)| !own.contains(to.as_str()) && dir.join(to).exists())
+    {
+        return Err(format!("{taken} already exists and is not part of the sequence"));
+    }
+
+    let key = |name: &str| relative_key(&root_canon, &dir.join(name));
+    let renames = plan
+        .iter()
+        .map(|(from, to)| SequenceRename {
+            from: key(from),
+            to: key(to),
+        })
+        .collect();
+    if !dry_run {
+        rename_via_temporaries(&dir, &plan)?;
+    }
+    Ok(RenumberReport { renames, dry_run })
+}
+
+// New names may be old names of other members, so every file goes through a
+// temporary name first. If any rename fails, all files get their old names back.
+fn rename_via_temporaries(dir: &Path, plan: &[(String, String)]) -> Result<(), String> {
+    let mut temps: Vec<PathBuf> = Vec::with_capacity(plan.len());
+    let mut n = 0u64;
+    for (from, _) in plan {
+        // Skip names left behind by an earlier run that was interrupted
+        let temp = loop {
+            let candidate = dir.join(format!(".lfm-renumber-{n}.tmp"));
+            n += 1;
+            if fs::symlink_metadata(&candidate).is_err() {
+                break candidate;
+            }
+        };
+        if let Err(e) = fs::rename(dir.join(from), &temp) {
+            roll_back(dir, plan, &temps, 0);
+            return Err(format!("Rename of {from} failed: {e}"));
+        }
+        temps.push(temp);
+    }
+    for (i, (_, to)) in plan.iter().enumerate() {
+        if let Err(e) = fs::rename(&temps[i], dir.join(to)) {
+            roll_back(dir, plan, &temps, i);
+            return Err(format!("Rename to {to} failed: {e}"));
+        }
+    }
+    Ok(())
+}
+
+// Undo a failed `rename_via_temporaries`: the first `placed` files already
+// have their new names, the others are still at their entry in `temps`.
+fn roll_back(dir: &Path, plan: &[(String, String)], temps: &[PathBuf], placed: usize) {
+    for ((_, to), temp) in plan[..placed].iter().zip(temps).rev() {
+        let _ = fs::rename(dir.join(to), temp);
+    }
+    for ((from, _), temp) in plan.iter().zip(temps).rev() {
+        let _ = fs::rename(temp, dir.join(from));
+    }
+}
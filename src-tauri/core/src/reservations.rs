@@ -0,0 +1,127 @@
+// Paths a long operation is writing to, shared by every process of the
+// user: the desktop app and `lfm serve` see each other's jobs. A reservation
+// is a JSON record next to a lock file held for as long as the operation
+// runs, so the reservations of a process that died release themselves.
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::paths::normalize_input_path;
+use crate::store;
+
+const DIR: &str = "reservations";
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    label: String,
+    paths: Vec<PathBuf>,
+}
+
+/// Held while an operation writes to its paths; dropping it releases them.
+pub struct Reservation {
+    // None when there is no data directory to share reservations through
+    held: Option<(File, PathBuf)>,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if let Some((_lock, base)) = self.held.take() {
+            let _ = fs::remove_file(base.with_extension("json"));
+            let _ = fs::remove_file(base.with_extension("lock"));
+        }
+    }
+}
+
+fn dir() -> Option<PathBuf> {
+    store::default_dir().map(|d| d.join(DIR))
+}
+
+/// `path` as reservations compare it. Paths about to be created don't
+/// exist yet and are kept as they are.
+pub fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Reserve `paths` (canonical, see `canonical`) for the operation `label`.
+pub fn reserve(label: &str, paths: &[PathBuf]) -> Result<Reservation, String> {
+    let Some(dir) = dir() else {
+        return Ok(Reservation { held: None });
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let base = dir.join(format!("{}-{id}", std::process::id()));
+    let record = Record {
+        label: label.to_string(),
+        paths: paths.to_vec(),
+    };
+    let data = serde_json::to_vec(&record).map_err(|e| e.to_string())?;
+    fs::write(base.with_extension("json"), data).map_err(|e| format!("Failed to reserve paths: {e}"))?;
+    // Locked before it gets its final name, or another process could take
+    // it for the leftover of a dead one
+    let pending = base.with_extension("pending");
+    let lock = File::create(&pending)
+        .and_then(|f| f.lock().map(|_| f))
+        .and_then(|f| fs::rename(&pending, base.with_extension("lock")).map(|_| f))
+        .map_err(|e| {
+            let _ = fs::remove_file(base.with_extension("json"));
+            let _ = fs::remove_file(&pending);
+            format!("Failed to reserve paths: {e}")
+        })?;
+    Ok(Reservation {
+        held: Some((lock, base)),
+    })
+}
+
+/// The live reservation overlapping any of `paths`, as (its label, its
+/// path). Reservations left behind by dead processes are cleared on the way.
+pub fn conflict(paths: &[PathBuf]) -> Option<(String, PathBuf)> {
+    let read_dir = fs::read_dir(dir()?).ok()?;
+    for entry in read_dir.flatten() {
+        let lock_path = entry.path();
+        if lock_path.extension().is_none_or(|e| e != "lock") {
+            continue;
+        }
+        let Ok(lock) = File::open(&lock_path) else { continue };
+        match lock.try_lock_shared() {
+            Ok(()) => {
+                let _ = fs::remove_file(lock_path.with_extension("json"));
+                let _ = fs::remove_file(&lock_path);
+                continue;
+            }
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(_)) => continue,
+        }
+        let Some(record) = fs::read(lock_path.with_extension("json"))
+            .ok()
+            .and_then(|data| serde_json::from_slice::<Record>(&data).ok())
+        else {
+            continue;
+        };
+        let overlapping = record
+            .paths
+            .into_iter()
+            .find(|r| paths.iter().any(|p| p.starts_with(r) || r.starts_with(p)));
+        if let Some(path) = overlapping {
+            return Some((record.label, path));
+        }
+    }
+    None
+}
+
+/// Refuse a quick file operation on `relative_paths` of `root` while a job
+/// of any process is writing there.
+pub fn check_free(root: &str, relative_paths: &[&str]) -> Result<(), String> {
+    let paths: Vec<PathBuf> = relative_paths
+        .iter()
+        .map(|rel| canonical(&Path::new(root).join(normalize_input_path(rel.trim_start_matches('/')))))
+        .collect();
+    match conflict(&paths) {
+        Some((held_by, path)) => Err(format!(
+            "{} is in use by {held_by}; try again once it has finished",
+            path.display()
+        )),
+        None => Ok(()),
+    }
+}
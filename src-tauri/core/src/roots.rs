@@ -0,0 +1,72 @@
+// Pinned roots: folders the user picked once and wants offered again, each
+// with a stable id derived from its path.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::index::{self, now_ms};
+use crate::store::Store;
+
+const ROOTS_DOC: &str = "roots";
+
+/// Stable id of a pinned root, the same on every run for the same folder.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+#[serde(transparent)]
+pub struct RootId(pub String);
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PinnedRoot {
+    pub id: RootId,
+    // canonical path
+    pub path: String,
+    // folder name, for display
+    pub label: String,
+    pub pinned_at: i64,
+}
+
+fn root_id(root_canon: &Path) -> RootId {
+    let digest = blake3::hash(root_canon.to_string_lossy().as_bytes());
+    RootId(digest.to_hex()[..16].to_string())
+}
+
+pub fn list_roots(store: &Store) -> Result<Vec<PinnedRoot>, String> {
+    store.load(ROOTS_DOC)
+}
+
+/// Check that `path` is a readable folder and pin it. Pinning a folder
+/// twice returns the existing entry.
+pub fn register_root(store: &Store, path: &Path) -> Result<PinnedRoot, String> {
+    let root_canon = index::canonical_root(&path.to_string_lossy())?;
+    if !root_canon.is_dir() {
+        return Err("Target is not a directory".into());
+    }
+    // Folders we may not list are of no use as a root
+    fs::read_dir(&root_canon).map_err(|e| format!("Failed to read dir {}: {e}", root_canon.display()))?;
+    let mut roots = list_roots(store)?;
+    let id = root_id(&root_canon);
+    if let Some(existing) = roots.iter().find(|r| r.id == id) {
+        return Ok(existing.clone());
+    }
+    let pinned = PinnedRoot {
+        id,
+        path: root_canon.display().to_string(),
+        label: root_canon
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| root_canon.display().to_string()),
+        pinned_at: now_ms(),
+    };
+    roots.push(pinned.clone());
+    store.save(ROOTS_DOC, &roots)?;
+    Ok(pinned)
+}
+
+pub fn unpin_root(store: &Store, id: &RootId) -> Result<(), String> {
+    let mut roots = list_roots(store)?;
+    let before = roots.len();
+    roots.retain(|r| &r.id != id);
+    if roots.len() == before {
+        return Err("No such root".into());
+    }
+    store.save(ROOTS_DOC, &roots)
+}
@@ -0,0 +1,655 @@
+// JSON-RPC 2.0 over a local socket, one request per line, exposing the same
+// command surface as the desktop app for headless use.
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+
+use crate::hashing::HashCache;
+use crate::imaging::TargetFormat;
+use crate::paths::resolve_within;
+use crate::store::Store;
+use crate::{
+    actions, albums, backup, bench, compare, dedup, filetype, fsops, groups, guards, hooks, i18n, imaging, index,
+    launch, listing, manifest, media, ownership, playlists, quota, recent, renumber, reservations, roots, settings,
+    sorting, stats, sync, translit, trash, workspace,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Implementation-defined range: the command itself returned an error
+const COMMAND_FAILED: i64 = -32000;
+// The connection did not start with the session token
+const UNAUTHORIZED: i64 = -32001;
+
+pub struct Server {
+    store: Store,
+    cache: HashCache,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        RpcError {
+            code: COMMAND_FAILED,
+            message,
+        }
+    }
+}
+
+fn arg<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, RpcError> {
+    let v = params.get(name).cloned().unwrap_or(Value::Null);
+    serde_json::from_value(v).map_err(|e| RpcError {
+        code: INVALID_PARAMS,
+        message: format!("Invalid parameter {name}: {e}"),
+    })
+}
+
+fn to_value<T: Serialize>(v: T) -> Result<Value, RpcError> {
+    serde_json::to_value(v).map_err(|e| RpcError::from(format!("Failed to serialize result: {e}")))
+}
+
+fn no_progress(_: u64, _: u64) {}
+
+/// A random token for `serve_tcp`, 64 hex digits.
+pub fn session_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    blake3::Hash::from(bytes).to_hex().to_string()
+}
+
+// Compares every byte, so the time taken doesn't tell how much of a guess was right
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl Server {
+    pub fn new(store: Store) -> Server {
+        if let Ok(settings) = settings::load(&store) {
+            settings::apply_io(&settings);
+            if let Some(locale) = &settings.locale {
+                let _ = i18n::set_locale(locale);
+            }
+        }
+        Server {
+            store,
+            cache: HashCache::default(),
+        }
+    }
+
+    fn threads(&self) -> Result<Option<usize>, String> {
+        Ok(settings::load(&self.store)?.hash_threads)
+    }
+
+    // Method names and parameter names match the Tauri commands
+    fn call(&self, method: &str, p: &Value) -> Result<Value, RpcError> {
+        match method {
+            "list_candidate_mounts" => to_value(fsops::list_candidate_mounts()?),
+            "list_standard_dirs" => to_value(fsops::list_standard_dirs()),
+            "list_files" => {
+                let mut files = fsops::list_files(
+                    &arg::<String>(p, "root")?,
+                    arg::<Option<bool>>(p, "show_hidden")?.unwrap_or(false),
+                )?;
+                let sort = arg::<Option<Vec<sorting::SortKey>>>(p, "sort")?.unwrap_or_default();
+                sorting::sort_entries(&mut files, &sort);
+                to_value(files)
+            }
+            "list_files_compact" => to_value(fsops::list_files_compact(
+                &arg::<String>(p, "root")?,
+                arg::<Option<bool>>(p, "show_hidden")?.unwrap_or(false),
+            )?),
+            "estimate_entries" => to_value(listing::estimate_entries(&arg::<String>(p, "root")?)?),
+            "open_action" => {
+                let root = arg::<String>(p, "root")?;
+                let path = resolve_within(Path::new(&root), &arg::<String>(p, "relative_path")?)?;
+                to_value(launch::check_open(&settings::load(&self.store)?.open_rules, &path)?)
+            }
+            "list_children_sharded" => to_value(fsops::list_children_sharded(
+                &arg::<String>(p, "root")?,
+                &arg::<Option<String>>(p, "dir")?.unwrap_or_default(),
+                &arg::<Option<String>>(p, "prefix")?.unwrap_or_default(),
+                arg::<Option<bool>>(p, "show_hidden")?.unwrap_or(false),
+            )?),
+            "open_listing" => to_value(listing::open_listing(
+                &arg::<String>(p, "root")?,
+                arg::<Option<bool>>(p, "show_hidden")?.unwrap_or(false),
+            )?),
+            "listing_page" => to_value(listing::listing_page(
+                &arg::<String>(p, "id")?,
+                arg(p, "offset")?,
+                arg(p, "limit")?,
+            )?),
+            "close_listing" => to_value(listing::close_listing(&arg::<String>(p, "id")?)?),
+            "rename_file" => {
+                let (root, rel, new_name) = (
+                    arg::<String>(p, "root")?,
+                    arg::<String>(p, "relative_path")?,
+                    arg::<String>(p, "new_name")?,
+                );
+                reservations::check_free(&root, &[&rel])?;
+                to_value(hooks::around(&self.store, "rename", p.clone(), || {
+                    fsops::rename_file(&root, &rel, &new_name)
+                })?)
+            }
+            "delete_file" => {
+                let (root, rel) = (arg::<String>(p, "root")?, arg::<String>(p, "relative_path")?);
+                reservations::check_free(&root, &[&rel])?;
+                let current = settings::load(&self.store)?;
+                let deleted = [rel.clone()];
+                let confirmed = arg::<Option<bool>>(p, "confirmed")?.unwrap_or(false);
+                guards::check_delete_within(&current.delete_guards, &root, &deleted, confirmed)?;
+                let keep = current.trash_retention_days.is_some();
+                to_value(stats::counting_delete(&self.store, &root, &deleted, keep, || {
+                    hooks::around(&self.store, "delete", p.clone(), || {
+                        if keep {
+                            trash::trash_file(&self.store, &root, &rel)
+                        } else {
+                            fsops::delete_file(&root, &rel)
+                        }
+                    })
+                })?)
+            }
+            "purge_now" => to_value(trash::purge(&self.store, None)?),
+            "get_pending_purge" => {
+                let retention = settings::load(&self.store)?.trash_retention_days;
+                to_value(trash::pending_purge(&self.store, retention)?)
+            }
+            "file_group" => to_value(groups::file_group(
+                &arg::<String>(p, "root")?,
+                &arg::<String>(p, "relative_path")?,
+            )?),
+            "rename_group" => {
+                let (root, rel, new_name) = (
+                    arg::<String>(p, "root")?,
+                    arg::<String>(p, "relative_path")?,
+                    arg::<String>(p, "new_name")?,
+                );
+                reservations::check_free(&root, &[&rel])?;
+                to_value(hooks::around(&self.store, "rename", p.clone(), || {
+                    groups::rename_group(&root, &rel, &new_name)
+                })?)
+            }
+            "delete_group" => {
+                let (root, rel) = (arg::<String>(p, "root")?, arg::<String>(p, "relative_path")?);
+                reservations::check_free(&root, &[&rel])?;
+                let members = groups::file_group(&root, &rel)?;
+                let current = settings::load(&self.store)?;
+                let confirmed = arg::<Option<bool>>(p, "confirmed")?.unwrap_or(false);
+                guards::check_delete_within(&current.delete_guards, &root, &members, confirmed)?;
+                let keep = current.trash_retention_days.is_some();
+                to_value(stats::counting_delete(&self.store, &root, &members, keep, || {
+                    hooks::around(&self.store, "delete", p.clone(), || {
+                        if keep {
+                            groups::trash_group(&self.store, &root, &rel)
+                        } else {
+                            groups::delete_group(&root, &rel)
+                        }
+                    })
+                })?)
+            }
+            "move_group" => {
+                let (root, from, to, create_dir) = (
+                    arg::<String>(p, "root")?,
+                    arg::<String>(p, "from_relative")?,
+                    arg::<String>(p, "to_relative_dir")?,
+                    arg::<Option<bool>>(p, "create_dir")?.unwrap_or(false),
+                );
+                reservations::check_free(&root, &[&from, &to])?;
+                let members = groups::file_group(&root, &from)?;
+                if !arg::<Option<bool>>(p, "override_quota")?.unwrap_or(false) {
+                    let quotas = settings::load(&self.store)?.folder_quotas;
+                    quota::check_move(&quotas, &root, &members, &to)?;
+                }
+                to_value(stats::counting_move(&self.store, &root, &members, || {
+                    hooks::around(&self.store, "move", p.clone(), || {
+                        groups::move_group(&root, &from, &to, create_dir)
+                    })
+                })?)
+            }
+            "move_file" => {
+                let (root, from, to, create_dir) = (
+                    arg::<String>(p, "root")?,
+                    arg::<String>(p, "from_relative")?,
+                    arg::<String>(p, "to_relative_dir")?,
+                    arg::<Option<bool>>(p, "create_dir")?.unwrap_or(false),
+                );
+                reservations::check_free(&root, &[&from, &to])?;
+                let update_playlists = arg::<Option<bool>>(p, "update_playlists")?.unwrap_or(false);
+                let moved = [from.clone()];
+                if !arg::<Option<bool>>(p, "override_quota")?.unwrap_or(false) {
+                    let quotas = settings::load(&self.store)?.folder_quotas;
+                    quota::check_move(&quotas, &root, &moved, &to)?;
+                }
+                to_value(stats::counting_move(&self.store, &root, &moved, || {
+                    hooks::around(&self.store, "move", p.clone(), || {
+                        if update_playlists {
+                            playlists::move_with_playlists(&root, &from, &to, create_dir)
+                        } else {
+                            fsops::move_file(&root, &from, &to, create_dir)
+                                .map(|_| playlists::PlaylistUpdate::default())
+                        }
+                    })
+                })?)
+            }
+            "create_folder" => to_value(fsops::create_folder(
+                &arg::<String>(p, "root")?,
+                &arg::<String>(p, "relative_dir")?,
+            )?),
+            "create_symlink" => to_value(fsops::create_symlink(
+                &arg::<String>(p, "root")?,
+                &arg::<String>(p, "target_relative")?,
+                &arg::<String>(p, "link_relative")?,
+            )?),
+            "create_hardlink" => to_value(fsops::create_hardlink(
+                &arg::<String>(p, "root")?,
+                &arg::<String>(p, "target_relative")?,
+                &arg::<String>(p, "link_relative")?,
+            )?),
+            "selection_size" => to_value(fsops::selection_size(
+                &arg::<String>(p, "root")?,
+                &arg::<Vec<String>>(p, "relative_paths")?,
+                &|_| {},
+            )?),
+            "index_root" => {
+                let root_canon = index::canonical_root(&arg::<String>(p, "root")?)?;
+                let threads = self.threads()?;
+                to_value(hooks::around(&self.store, "scan", p.clone(), || {
+                    index::refresh_index(&self.store, &self.cache, &root_canon, threads, &no_progress)
+                        .map(|(_, summary)| summary)
+                })?)
+            }
+            "verify_root" => {
+                let root_canon = index::canonical_root(&arg::<String>(p, "root")?)?;
+                to_value(index::verify_index(
+                    &self.store,
+                    &self.cache,
+                    &root_canon,
+                    self.threads()?,
+                    &no_progress,
+                )?)
+            }
+            "export_manifest" => to_value(manifest::export_manifest(
+                &self.cache,
+                &arg::<String>(p, "root")?,
+                &arg::<Option<Vec<String>>>(p, "relative_paths")?.unwrap_or_default(),
+                Path::new(&arg::<String>(p, "destination")?),
+                arg::<Option<manifest::ManifestOptions>>(p, "options")?.unwrap_or_default(),
+                self.threads()?,
+                &no_progress,
+            )?),
+            "verify_manifest" => to_value(manifest::verify_manifest(
+                Path::new(&arg::<String>(p, "manifest_path")?),
+                &arg::<String>(p, "root")?,
+                self.threads()?,
+                &no_progress,
+            )?),
+            "list_catalogs" => to_value(dedup::list_catalogs(&self.store)?),
+            "find_duplicates" => to_value(dedup::find_duplicates(
+                &self.store,
+                &self.cache,
+                &arg::<Option<Vec<String>>>(p, "roots")?.unwrap_or_default(),
+                &arg::<Option<Vec<String>>>(p, "catalog_ids")?.unwrap_or_default(),
+                arg::<Option<u64>>(p, "min_size")?.unwrap_or(0),
+                self.threads()?,
+                &no_progress,
+            )?),
+            "accept_duplicate" => to_value(dedup::accept_duplicate(
+                &self.store,
+                arg(p, "hash")?,
+                arg(p, "size")?,
+                arg::<Option<String>>(p, "note")?.unwrap_or_default(),
+            )?),
+            "unaccept_duplicate" => to_value(dedup::unaccept_duplicate(&self.store, &arg::<String>(p, "hash")?)?),
+            "list_accepted_duplicates" => to_value(dedup::list_accepted_duplicates(&self.store)?),
+            "get_settings" => to_value(settings::load(&self.store)?),
+            "update_settings" => {
+                let new = arg(p, "settings")?;
+                settings::save(&self.store, &new)?;
+                settings::apply_io(&new);
+                to_value(())
+            }
+            "register_root" => to_value(roots::register_root(
+                &self.store,
+                Path::new(&arg::<String>(p, "path")?),
+            )?),
+            "list_pinned_roots" => to_value(roots::list_roots(&self.store)?),
+            "unpin_root" => to_value(roots::unpin_root(&self.store, &roots::RootId(arg::<String>(p, "id")?))?),
+            "clean_workspace" => to_value(workspace::clean_workspace()?),
+            "usage_stats" => to_value(stats::usage_stats(&self.store)?),
+            "set_locale" => {
+                let locale = arg::<String>(p, "locale")?;
+                let mut current = settings::load(&self.store)?;
+                current.locale = Some(locale);
+                settings::save(&self.store, &current)?;
+                to_value(i18n::set_locale(current.locale.as_deref().unwrap_or("en"))?)
+            }
+            "folders_over_quota" => to_value(quota::over_quota(&settings::load(&self.store)?.folder_quotas)),
+            "compare_files" => to_value(compare::compare_files(
+                &arg::<compare::FileRef>(p, "a")?,
+                &arg::<compare::FileRef>(p, "b")?,
+            )?),
+            "diff_text_files" => to_value(compare::diff_text_files(
+                &arg::<compare::FileRef>(p, "a")?,
+                &arg::<compare::FileRef>(p, "b")?,
+                arg::<Option<usize>>(p, "context_lines")?.unwrap_or(3),
+            )?),
+            "tree_digest" => to_value(compare::tree_digest(
+                &self.cache,
+                &arg::<String>(p, "root")?,
+                &arg::<Option<String>>(p, "relative_dir")?.unwrap_or_default(),
+            )?),
+            "sync_folders" => {
+                let (source, destination) = (arg::<String>(p, "source")?, arg::<String>(p, "destination")?);
+                let options = arg::<Option<sync::SyncOptions>>(p, "options")?.unwrap_or_default();
+                let current = settings::load(&self.store)?;
+                let dst = reservations::canonical(Path::new(&destination));
+                if let Some((held_by, path)) = reservations::conflict(&[dst.clone()]) {
+                    let path = path.display();
+                    return Err(format!("{path} is in use by {held_by}; try again once it has finished").into());
+                }
+                let _reservation = reservations::reserve(&format!("Sync to {destination}"), &[dst])?;
+                if !arg::<Option<bool>>(p, "override_quota")?.unwrap_or(false) {
+                    let (source, destination) = (Path::new(&source), Path::new(&destination));
+                    quota::check_sync(&self.store, &current, source, destination, options)?;
+                }
+                to_value(hooks::around(&self.store, "sync", p.clone(), || {
+                    sync::sync_trees(
+                        &self.store,
+                        &current,
+                        Path::new(&source),
+                        Path::new(&destination),
+                        options,
+                        &AtomicBool::new(false),
+                        &no_progress,
+                    )
+                })?)
+            }
+            "list_file_actions" => to_value(actions::list_file_actions(
+                &self.store,
+                &arg::<String>(p, "root")?,
+                &arg::<String>(p, "relative_path")?,
+            )?),
+            "run_file_action" => to_value(actions::run_file_action(
+                &self.store,
+                &arg::<String>(p, "root")?,
+                &arg::<String>(p, "relative_path")?,
+                &arg::<String>(p, "action_id")?,
+            )?),
+            "convert_images" => to_value(imaging::convert_images(
+                &arg::<String>(p, "root")?,
+                &arg::<Vec<String>>(p, "relative_paths")?,
+                arg(p, "target_format")?,
+                arg::<Option<u8>>(p, "quality")?.unwrap_or(85),
+                &arg::<String>(p, "dest")?,
+                &no_progress,
+            )?),
+            "resize_images" => to_value(imaging::resize_images(
+                &arg::<String>(p, "root")?,
+                &arg::<Vec<String>>(p, "relative_paths")?,
+                arg(p, "max_dimension")?,
+                arg::<Option<TargetFormat>>(p, "target_format")?.unwrap_or(TargetFormat::Jpeg),
+                arg::<Option<u8>>(p, "quality")?.unwrap_or(85),
+                &arg::<String>(p, "dest")?,
+                &no_progress,
+            )?),
+            "strip_metadata" => to_value(imaging::strip_metadata(
+                &arg::<String>(p, "root")?,
+                &arg::<Vec<String>>(p, "relative_paths")?,
+                &arg::<String>(p, "dest_dir")?,
+                &no_progress,
+            )?),
+            "transliterate_names" => {
+                let (root, paths) = (arg::<String>(p, "root")?, arg::<Vec<String>>(p, "relative_paths")?);
+                let scheme = arg::<Option<translit::Scheme>>(p, "scheme")?.unwrap_or_default();
+                if arg::<bool>(p, "dry_run")? {
+                    to_value(translit::transliterate_names(&root, &paths, scheme, true)?)
+                } else {
+                    to_value(hooks::around(&self.store, "rename", p.clone(), || {
+                        translit::transliterate_names(&root, &paths, scheme, false)
+                    })?)
+                }
+            }
+            "suggest_albums" => to_value(albums::suggest_albums(
+                &arg::<String>(p, "root")?,
+                &arg::<String>(p, "relative_dir")?,
+                arg::<Option<albums::AlbumOptions>>(p, "options")?.unwrap_or_default(),
+            )?),
+            "find_sequences" => to_value(renumber::find_sequences(
+                &arg::<String>(p, "root")?,
+                &arg::<String>(p, "relative_dir")?,
+            )?),
+            "renumber_sequence" => {
+                let (root, dir) = (arg::<String>(p, "root")?, arg::<String>(p, "relative_dir")?);
+                let (prefix, width) = (arg::<String>(p, "prefix")?, arg::<usize>(p, "width")?);
+                let start = arg::<Option<u64>>(p, "start")?;
+                if arg::<bool>(p, "dry_run")? {
+                    to_value(renumber::renumber_sequence(&root, &dir, &prefix, width, start, true)?)
+                } else {
+                    to_value(hooks::around(&self.store, "rename", p.clone(), || {
+                        renumber::renumber_sequence(&root, &dir, &prefix, width, start, false)
+                    })?)
+                }
+            }
+            "find_extension_mismatches" => to_value(filetype::find_extension_mismatches(
+                &arg::<String>(p, "root")?,
+                &arg::<Option<String>>(p, "relative_dir")?.unwrap_or_default(),
+                &no_progress,
+            )?),
+            "fix_extensions" => {
+                let (root, paths) = (arg::<String>(p, "root")?, arg::<Vec<String>>(p, "relative_paths")?);
+                to_value(hooks::around(&self.store, "rename", p.clone(), || {
+                    filetype::fix_extensions(&root, &paths)
+                })?)
+            }
+            "scan_damaged_media" => to_value(media::scan_damaged_media(
+                &arg::<String>(p, "root")?,
+                &arg::<Option<String>>(p, "relative_dir")?.unwrap_or_default(),
+                arg::<Option<bool>>(p, "full_decode")?.unwrap_or(false),
+                &no_progress,
+            )?),
+            "recent_changes" => to_value(recent::recent_changes(
+                &self.store,
+                &arg::<String>(p, "root")?,
+                arg(p, "since")?,
+                arg::<Option<usize>>(p, "limit")?.unwrap_or(1000),
+            )?),
+            "mark_root_seen" => to_value(recent::mark_root_seen(&self.store, &arg::<String>(p, "root")?)?),
+            "take_ownership" => to_value(ownership::take_ownership(
+                &arg::<String>(p, "root")?,
+                &arg::<String>(p, "relative_dir")?,
+                arg(p, "recursive")?,
+            )?),
+            "benchmark_mount" => to_value(bench::benchmark_mount(
+                &arg::<String>(p, "path")?,
+                arg(p, "size_mb")?,
+                &no_progress,
+            )?),
+            "export_metadata" => to_value(backup::export_metadata(
+                &self.store,
+                Path::new(&arg::<String>(p, "destination")?),
+            )?),
+            "import_metadata" => to_value(backup::import_metadata(
+                &self.store,
+                Path::new(&arg::<String>(p, "archive")?),
+            )?),
+            "merge_metadata" => to_value(backup::merge_metadata(
+                &self.store,
+                Path::new(&arg::<String>(p, "archive")?),
+            )?),
+            _ => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Method not found: {method}"),
+            }),
+        }
+    }
+
+    // None for notifications, which get no response
+    fn handle_request(&self, req: &Value) -> Option<Value> {
+        let id = req.get("id").cloned();
+        let method = req.get("method").and_then(Value::as_str);
+        let result = match method {
+            Some(m) if req.get("jsonrpc") == Some(&json!("2.0")) => {
+                let params = req.get("params").cloned().unwrap_or_else(|| json!({}));
+                self.call(m, &params)
+            }
+            _ => Err(RpcError {
+                code: INVALID_REQUEST,
+                message: "Invalid request".into(),
+            }),
+        };
+        let id = id?;
+        Some(match result {
+            Ok(value) => json!({ "jsonrpc": "2.0", "id": id, "result": value }),
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": e.code, "message": i18n::localize(e.message) }
+            }),
+        })
+    }
+
+    /// Handle one line of input, returning the line to write back (if any).
+    pub fn handle_line(&self, line: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(line) {
+            Ok(Value::Array(batch)) => {
+                let responses: Vec<Value> = batch.iter().filter_map(|r| self.handle_request(r)).collect();
+                if responses.is_empty() {
+                    return None;
+                }
+                Value::Array(responses)
+            }
+            Ok(req) => self.handle_request(&req)?,
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": PARSE_ERROR, "message": format!("Parse error: {e}") }
+            }),
+        };
+        Some(response.to_string())
+    }
+
+    /// Answer requests from `reader` until it closes. With a `token`, the
+    /// first line has to be that token; otherwise nothing is dispatched and
+    /// the connection is dropped.
+    fn serve_stream<R: Read, W: Write>(&self, reader: R, mut writer: W, token: Option<&str>) -> io::Result<()> {
+        let mut authenticated = token.is_none();
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !authenticated {
+                if !token.is_some_and(|t| same_token(t, line.trim())) {
+                    let refusal = json!({
+                        "jsonrpc": "2.0",
+                        "id": null,
+                        "error": { "code": UNAUTHORIZED, "message": "Send the session token as the first line" }
+                    });
+                    writer.write_all(refusal.to_string().as_bytes())?;
+                    writer.write_all(b"\n")?;
+                    return writer.flush();
+                }
+                authenticated = true;
+                continue;
+            }
+            if let Some(out) = self.handle_line(&line) {
+                writer.write_all(out.as_bytes())?;
+                writer.write_all(b"\n")?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Listen on a TCP address. Any local program, and any web page through
+    /// the browser, can reach a loopback port, so only loopback addresses
+    /// are accepted and each connection has to start with `token`.
+    pub fn serve_tcp(self: Arc<Self>, addr: &str, token: &str) -> Result<(), String> {
+        if token.len() < 16 {
+            return Err("The session token must be at least 16 characters".into());
+        }
+        let listener = TcpListener::bind(addr).map_err(|e| format!("Failed to listen on {addr}: {e}"))?;
+        let local = listener.local_addr().map_err(|e| format!("Failed to listen on {addr}: {e}"))?;
+        if !local.ip().is_loopback() {
+            return Err("Refusing to listen on a non-loopback address".into());
+        }
+        eprintln!("lfm: listening on {local}");
+        for stream in listener.incoming().flatten() {
+            let server = Arc::clone(&self);
+            let token = token.to_string();
+            thread::spawn(move || {
+                if let Ok(reader) = stream.try_clone() {
+                    let _ = server.serve_stream(reader, stream, Some(&token));
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Listen on a Unix domain socket only the current user can connect to.
+    #[cfg(unix)]
+    pub fn serve_unix(self: Arc<Self>, path: &Path) -> Result<(), String> {
+        use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+        use std::os::unix::net::UnixListener;
+
+        match std::fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_socket() => {
+                std::fs::remove_file(path).map_err(|e| format!("Failed to remove stale socket: {e}"))?;
+            }
+            Ok(_) => return Err(format!("{} exists and is not a socket", path.display())),
+            Err(_) => {}
+        }
+        // Bound inside a folder only this user can enter and moved into
+        // place once restricted, so nobody can connect in between
+        let parent = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let private = parent.join(format!(".lfm-socket-{}", std::process::id()));
+        std::fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&private)
+            .map_err(|e| format!("Failed to create {}: {e}", private.display()))?;
+        let staged = private.join("lfm.sock");
+        let bound = UnixListener::bind(&staged)
+            .and_then(|listener| {
+                std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+                std::fs::rename(&staged, path)?;
+                Ok(listener)
+            })
+            .map_err(|e| format!("Failed to listen on {}: {e}", path.display()));
+        let _ = std::fs::remove_file(&staged);
+        let _ = std::fs::remove_dir(&private);
+        let listener = bound?;
+        eprintln!("lfm: listening on {}", path.display());
+        for stream in listener.incoming().flatten() {
+            let server = Arc::clone(&self);
+            thread::spawn(move || {
+                if let Ok(reader) = stream.try_clone() {
+                    let _ = server.serve_stream(reader, stream, None);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Serve a single client on stdin/stdout, for tools that spawn `lfm serve --stdio`.
+    pub fn serve_stdio(&self) -> Result<(), String> {
+        self.serve_stream(io::stdin().lock(), io::stdout().lock(), None)
+            .map_err(|e| format!("I/O error: {e}"))
+    }
+}
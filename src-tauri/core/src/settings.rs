@@ -0,0 +1,121 @@
+// User-tunable settings, persisted in the store.
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{self, FileAction};
+use crate::guards::{self, DeleteGuard};
+use crate::hashing;
+use crate::hooks::{self, Hook};
+use crate::i18n;
+use crate::launch::{self, OpenRule};
+use crate::quota::{self, FolderQuota};
+use crate::store::Store;
+use crate::sync;
+
+pub const SETTINGS_DOC: &str = "settings";
+
+/// Verbosity levels of the log file, quietest first.
+pub const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    // Fixed number of hashing threads; None lets the pool adapt to the drive
+    pub hash_threads: Option<usize>,
+    pub hooks: Vec<Hook>,
+    // external programs offered as actions on files
+    pub actions: Vec<FileAction>,
+    // write a .lfm-catalog copy of each index to the root of the drive
+    pub catalog_sidecar: bool,
+    // minimum time between two progress events of the same kind; None = 100ms
+    pub event_flush_ms: Option<u64>,
+    // closing the window hides it to the tray instead of quitting
+    pub close_to_tray: bool,
+    // offer retrying failed file operations through the OS admin prompt
+    pub allow_elevation: bool,
+    // deleted files go to the drive's trash and are purged after this many
+    // days; None deletes right away
+    pub trash_retention_days: Option<u64>,
+    // soft size limits; operations that would exceed one need an override
+    pub folder_quotas: Vec<FolderQuota>,
+    // language of returned messages, one of i18n::LOCALES; None = English
+    pub locale: Option<String>,
+    // one of LOG_LEVELS; None = info
+    pub log_level: Option<String>,
+    // rules deletions have to pass
+    pub delete_guards: Vec<DeleteGuard>,
+    // buffer for copying files in syncs; None leaves copying to the OS
+    pub copy_buffer_kb: Option<u64>,
+    // block size files are read in for hashing; None = 1024
+    pub hash_block_kb: Option<u64>,
+    // what opening a file does by extension; programs and scripts are
+    // refused unless a rule allows them
+    pub open_rules: Vec<OpenRule>,
+}
+
+pub fn load(store: &Store) -> Result<Settings, String> {
+    store.load(SETTINGS_DOC)
+}
+
+pub fn save(store: &Store, settings: &Settings) -> Result<(), String> {
+    if let Some(n) = settings.hash_threads {
+        if n == 0 || n > 64 {
+            return Err("Hash threads must be between 1 and 64".into());
+        }
+    }
+    if let Some(ms) = settings.event_flush_ms {
+        if !(10..=10_000).contains(&ms) {
+            return Err("Event flush interval must be between 10 and 10000 ms".into());
+        }
+    }
+    if let Some(kb) = settings.copy_buffer_kb {
+        if !(4..=65_536).contains(&kb) {
+            return Err("Copy buffer must be between 4 and 65536 KB".into());
+        }
+    }
+    if let Some(kb) = settings.hash_block_kb {
+        if !(4..=65_536).contains(&kb) {
+            return Err("Hash block size must be between 4 and 65536 KB".into());
+        }
+    }
+    if let Some(days) = settings.trash_retention_days {
+        if !(1..=3650).contains(&days) {
+            return Err("Trash retention must be between 1 and 3650 days".into());
+        }
+    }
+    if let Some(locale) = &settings.locale {
+        if i18n::normalize(locale).is_none() {
+            return Err(format!("Unsupported locale: {locale}"));
+        }
+    }
+    if let Some(level) = &settings.log_level {
+        if !LOG_LEVELS.contains(&level.as_str()) {
+            return Err(format!("Unknown log level: {level}"));
+        }
+    }
+    for rule in &settings.open_rules {
+        launch::validate(rule)?;
+    }
+    for guard in &settings.delete_guards {
+        guards::validate(guard)?;
+    }
+    for quota in &settings.folder_quotas {
+        quota::validate(quota)?;
+    }
+    for hook in &settings.hooks {
+        hooks::validate(hook)?;
+    }
+    for (i, action) in settings.actions.iter().enumerate() {
+        actions::validate(action)?;
+        if settings.actions[..i].iter().any(|a| a.id == action.id) {
+            return Err(format!("Duplicate file action id: {}", action.id));
+        }
+    }
+    store.save(SETTINGS_DOC, settings)
+}
+
+/// Hand the IO tuning settings to the subsystems that read and copy files.
+pub fn apply_io(settings: &Settings) {
+    let bytes = |kb: Option<u64>| kb.map(|kb| kb as usize * 1024);
+    hashing::set_block_size(bytes(settings.hash_block_kb));
+    sync::set_copy_buffer_size(bytes(settings.copy_buffer_kb));
+}
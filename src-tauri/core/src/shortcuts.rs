@@ -0,0 +1,178 @@
+// Windows .lnk and freedesktop .desktop shortcuts: where they point and
+// whether that still exists, so listings can follow them or flag broken ones.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Shell links are small; anything bigger isn't one
+const MAX_LNK_SIZE: u64 = 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Shortcut {
+    // "lnk" or "desktop"
+    pub kind: String,
+    // as stored in the shortcut
+    pub target: Option<String>,
+    // where the target was found on this machine, if anywhere
+    pub resolved: Option<String>,
+    pub target_exists: bool,
+}
+
+/// Shortcut details for `.lnk` and `.desktop` files, `None` for anything else.
+pub fn resolve(path: &Path) -> Option<Shortcut> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let dir = path.parent()?;
+    let (kind, candidates) = match ext.as_str() {
+        "lnk" => ("lnk", lnk_targets(path).unwrap_or_default()),
+        "desktop" => ("desktop", desktop_target(path).into_iter().collect()),
+        _ => return None,
+    };
+    let target = candidates.last().cloned();
+    let resolved = candidates.iter().find_map(|c| {
+        let p = Path::new(c);
+        let p = if p.is_absolute() { p.to_path_buf() } else { dir.join(p) };
+        p.canonicalize().ok()
+    });
+    Some(Shortcut {
+        kind: kind.to_string(),
+        target,
+        target_exists: resolved.is_some(),
+        resolved: resolved.map(|p| p.display().to_string()),
+    })
+}
+
+fn desktop_target(path: &Path) -> Option<String> {
+    let text = fs::read_to_string(path).ok()?;
+    let mut in_entry = false;
+    let mut url = None;
+    let mut exec = None;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+        } else if in_entry {
+            if let Some(v) = line.strip_prefix("URL=") {
+                url = Some(v.to_string());
+            } else if let Some(v) = line.strip_prefix("Exec=") {
+                exec = Some(v.to_string());
+            }
+        }
+    }
+    if let Some(url) = url {
+        return Some(match url.strip_prefix("file://") {
+            Some(p) => percent_decode(p),
+            None => url,
+        });
+    }
+    // First word of the command line, without field codes like %f
+    let exec = exec?;
+    let program = exec.split_whitespace().next()?.trim_matches('"');
+    Some(program.to_string())
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<usize> {
+    data.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<usize> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+}
+
+fn c_string(data: &[u8], at: usize) -> Option<String> {
+    let rest = data.get(at..)?;
+    let end = rest.iter().position(|b| *b == 0)?;
+    Some(String::from_utf8_lossy(&rest[..end]).to_string())
+}
+
+// Windows paths as seen from here: the relative form works on any OS
+fn from_windows(p: &str) -> String {
+    if cfg!(windows) {
+        p.to_string()
+    } else {
+        p.replace('\\', "/")
+    }
+}
+
+/// Target candidates of a shell link (MS-SHLLINK), most portable first:
+/// the path relative to the link, then the absolute local path.
+fn lnk_targets(path: &Path) -> Option<Vec<String>> {
+    const HEADER_SIZE: usize = 0x4C;
+    const HAS_ID_LIST: usize = 0x1;
+    const HAS_LINK_INFO: usize = 0x2;
+    const HAS_NAME: usize = 0x4;
+    const HAS_RELATIVE_PATH: usize = 0x8;
+    const IS_UNICODE: usize = 0x80;
+
+    if fs::metadata(path).ok()?.len() > MAX_LNK_SIZE {
+        return None;
+    }
+    let data = fs::read(path).ok()?;
+    if u32_at(&data, 0)? != HEADER_SIZE {
+        return None;
+    }
+    let flags = u32_at(&data, 0x14)?;
+    let mut at = HEADER_SIZE;
+    if flags & HAS_ID_LIST != 0 {
+        at += 2 + u16_at(&data, at)?;
+    }
+
+    let mut absolute = None;
+    if flags & HAS_LINK_INFO != 0 {
+        let info = at;
+        let info_size = u32_at(&data, info)?;
+        let info_flags = u32_at(&data, info + 8)?;
+        if info_flags & 1 != 0 {
+            let base = c_string(&data, info + u32_at(&data, info + 0x10)?)?;
+            let suffix = c_string(&data, info + u32_at(&data, info + 0x18)?).unwrap_or_default();
+            absolute = Some(from_windows(&format!("{base}{suffix}")));
+        }
+        at += info_size;
+    }
+
+    let mut relative = None;
+    let unicode = flags & IS_UNICODE != 0;
+    for (flag, keep) in [(HAS_NAME, false), (HAS_RELATIVE_PATH, true)] {
+        if flags & flag == 0 {
+            continue;
+        }
+        let chars = u16_at(&data, at)?;
+        at += 2;
+        let text = if unicode {
+            let units: Vec<u16> = data
+                .get(at..at + chars * 2)?
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            at += chars * 2;
+            String::from_utf16_lossy(&units)
+        } else {
+            let text = String::from_utf8_lossy(data.get(at..at + chars)?).to_string();
+            at += chars;
+            text
+        };
+        if keep {
+            relative = Some(from_windows(&text));
+        }
+    }
+    Some(relative.into_iter().chain(absolute).collect())
+}
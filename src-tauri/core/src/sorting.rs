@@ -0,0 +1,112 @@
+// Ordering of listings by several keys at once, e.g. extension, then name,
+// then size descending, so grouped views come back ready to display.
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::fsops::FileEntry;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    // relative path, byte by byte
+    Path,
+    // file name, case-insensitive with numbers compared by value
+    Name,
+    // folder the file is in, compared like names
+    Folder,
+    // lowercase extension; files without one come first
+    Extension,
+    Size,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct SortKey {
+    pub field: SortField,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        number.push(c);
+    }
+    number
+}
+
+/// Compare names the way people read them: case-insensitive, with runs of
+/// digits compared by value, so `IMG_2` comes before `img_10`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let (x, y) = match (a.peek(), b.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => (*x, *y),
+        };
+        let ord = if x.is_ascii_digit() && y.is_ascii_digit() {
+            let (na, nb) = (take_number(&mut a), take_number(&mut b));
+            let (va, vb) = (na.trim_start_matches('0'), nb.trim_start_matches('0'));
+            // Equal values with more leading zeros go last
+            va.len()
+                .cmp(&vb.len())
+                .then_with(|| va.cmp(vb))
+                .then_with(|| na.len().cmp(&nb.len()))
+        } else {
+            a.next();
+            b.next();
+            x.to_lowercase().cmp(y.to_lowercase())
+        };
+        if ord.is_ne() {
+            return ord;
+        }
+    }
+}
+
+fn split(relative_path: &str) -> (&str, &str) {
+    relative_path.rsplit_once('/').unwrap_or(("", relative_path))
+}
+
+fn extension(name: &str) -> String {
+    match name.rsplit_once('.') {
+        // A leading dot marks a hidden file, not an extension
+        Some((stem, ext)) if !stem.is_empty() => ext.to_lowercase(),
+        _ => String::new(),
+    }
+}
+
+fn compare(field: SortField, a: &FileEntry, b: &FileEntry) -> Ordering {
+    let (dir_a, name_a) = split(&a.relative_path);
+    let (dir_b, name_b) = split(&b.relative_path);
+    match field {
+        SortField::Path => a.relative_path.cmp(&b.relative_path),
+        SortField::Name => natural_cmp(name_a, name_b),
+        SortField::Folder => natural_cmp(dir_a, dir_b),
+        SortField::Extension => extension(name_a).cmp(&extension(name_b)),
+        SortField::Size => a.size.cmp(&b.size),
+    }
+}
+
+/// Sort `entries` by `keys` in order, each breaking the ties of the ones
+/// before it; entries equal on every key stay in path order.
+pub fn sort_entries(entries: &mut [FileEntry], keys: &[SortKey]) {
+    if keys.is_empty() {
+        return;
+    }
+    entries.sort_by(|a, b| {
+        keys.iter()
+            .map(|k| {
+                let ord = compare(k.field, a, b);
+                if k.descending {
+                    ord.reverse()
+                } else {
+                    ord
+                }
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or_else(|| a.relative_path.cmp(&b.relative_path))
+    });
+}
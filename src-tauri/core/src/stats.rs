@@ -0,0 +1,180 @@
+// Local usage statistics: what was moved, deleted and reclaimed, per month.
+// Kept in the store only; nothing is sent anywhere.
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+
+use crate::albums::civil_from_days;
+use crate::index::{self, now_ms};
+use crate::paths::{relative_key, resolve_within};
+use crate::store::Store;
+
+const STATS_DOC: &str = "stats";
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+// Operations finishing together must not lose each other's counts
+static RECORDING: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct Totals {
+    pub files_moved: u64,
+    pub bytes_moved: u64,
+    pub files_deleted: u64,
+    // deleted files with an identical copy left in the drive's index
+    pub duplicates_removed: u64,
+    // space given back to the drive: deletions, or trash purges when the
+    // trash is on
+    pub bytes_reclaimed: u64,
+}
+
+impl Totals {
+    fn add(&mut self, other: &Totals) {
+        self.files_moved += other.files_moved;
+        self.bytes_moved += other.bytes_moved;
+        self.files_deleted += other.files_deleted;
+        self.duplicates_removed += other.duplicates_removed;
+        self.bytes_reclaimed += other.bytes_reclaimed;
+    }
+}
+
+#[derive(Serialize)]
+pub struct MonthTotals {
+    // YYYY-MM, UTC
+    pub month: String,
+    pub totals: Totals,
+}
+
+#[derive(Serialize)]
+pub struct UsageStats {
+    pub all_time: Totals,
+    pub this_year: Totals,
+    // oldest first
+    pub months: Vec<MonthTotals>,
+}
+
+fn month_of(ms: i64) -> String {
+    civil_from_days(ms.div_euclid(DAY_MS))[..7].to_string()
+}
+
+fn record(store: &Store, totals: &Totals) -> Result<(), String> {
+    let _recording = RECORDING.lock().unwrap();
+    let mut months: BTreeMap<String, Totals> = store.load(STATS_DOC)?;
+    months.entry(month_of(now_ms())).or_default().add(totals);
+    store.save(STATS_DOC, &months)
+}
+
+#[derive(Default)]
+struct Measured {
+    files: u64,
+    bytes: u64,
+    duplicates: u64,
+}
+
+/// Files, bytes and duplicates among `relative_paths` of `root`, taken
+/// before they are moved or deleted.
+fn measure(store: &Store, root: &str, relative_paths: &[String]) -> Measured {
+    let mut measured = Measured::default();
+    let Ok(root_canon) = index::canonical_root(root) else {
+        return measured;
+    };
+    let mut keys = HashSet::new();
+    for rel in relative_paths {
+        let Ok(path) = resolve_within(&root_canon, rel) else {
+            continue;
+        };
+        measured.files += 1;
+        measured.bytes += path.metadata().map(|m| m.len()).unwrap_or(0);
+        keys.insert(relative_key(&root_canon, &path));
+    }
+    let catalog = index::load_index(store, &root_canon).unwrap_or_default();
+    let kept: HashSet<&str> = catalog
+        .entries
+        .iter()
+        .filter(|(key, _)| !keys.contains(*key))
+        .map(|(_, entry)| entry.hash.as_str())
+        .collect();
+    measured.duplicates = keys
+        .iter()
+        .filter_map(|key| catalog.entries.get(key))
+        .filter(|entry| kept.contains(entry.hash.as_str()))
+        .count() as u64;
+    measured
+}
+
+/// Run `f`, a move of `relative_paths` of `root`, and count it when it
+/// succeeds.
+pub fn counting_move<T>(
+    store: &Store,
+    root: &str,
+    relative_paths: &[String],
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    let measured = measure(store, root, relative_paths);
+    let result = f()?;
+    // Statistics never get in the way of the operation itself
+    let _ = record(
+        store,
+        &Totals {
+            files_moved: measured.files,
+            bytes_moved: measured.bytes,
+            ..Totals::default()
+        },
+    );
+    Ok(result)
+}
+
+/// Run `f`, a deletion of `relative_paths` of `root`, and count it when it
+/// succeeds. `trashed` deletions free their space only once purged.
+pub fn counting_delete<T>(
+    store: &Store,
+    root: &str,
+    relative_paths: &[String],
+    trashed: bool,
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    let measured = measure(store, root, relative_paths);
+    let result = f()?;
+    let _ = record(
+        store,
+        &Totals {
+            files_deleted: measured.files,
+            duplicates_removed: measured.duplicates,
+            bytes_reclaimed: if trashed { 0 } else { measured.bytes },
+            ..Totals::default()
+        },
+    );
+    Ok(result)
+}
+
+/// Count space freed outside of a deletion, e.g. by purging the trash.
+pub fn record_reclaimed(store: &Store, bytes: u64) -> Result<(), String> {
+    record(
+        store,
+        &Totals {
+            bytes_reclaimed: bytes,
+            ..Totals::default()
+        },
+    )
+}
+
+pub fn usage_stats(store: &Store) -> Result<UsageStats, String> {
+    let months: BTreeMap<String, Totals> = store.load(STATS_DOC)?;
+    let year = &month_of(now_ms())[..4];
+    let mut all_time = Totals::default();
+    let mut this_year = Totals::default();
+    for (month, totals) in &months {
+        all_time.add(totals);
+        if month.starts_with(year) {
+            this_year.add(totals);
+        }
+    }
+    Ok(UsageStats {
+        all_time,
+        this_year,
+        months: months
+            .into_iter()
+            .map(|(month, totals)| MonthTotals { month, totals })
+            .collect(),
+    })
+}
@@ -0,0 +1,161 @@
+// Small JSON document store. Every piece of persisted app metadata goes
+// through here; the desktop app and the CLI share the same directory.
+// Documents are encrypted at rest once encryption is enabled for a store.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::encryption::{self, Key};
+
+const LOCKED: &str = "The metadata store is locked, unlock it with its passphrase first";
+
+/// Bundle identifier of the desktop app, which names its data directory.
+pub const APP_IDENTIFIER: &str = "com.denis.sdmanager";
+
+/// The directory the desktop app keeps its data in, for headless front ends.
+pub fn default_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join(APP_IDENTIFIER))
+}
+
+pub struct Store {
+    dir: PathBuf,
+    // set once an encrypted store has been unlocked
+    key: Option<Key>,
+}
+
+impl Store {
+    pub fn open(dir: PathBuf) -> Result<Store, String> {
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data dir: {e}"))?;
+        Ok(Store { dir, key: None })
+    }
+
+    /// Read and write documents encrypted with `key`.
+    pub fn with_key(self, key: Key) -> Store {
+        Store { key: Some(key), ..self }
+    }
+
+    pub fn key(&self) -> Option<&Key> {
+        self.key.as_ref()
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn doc_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.json"))
+    }
+
+    /// Load a document, falling back to `T::default()` when it was never saved.
+    pub fn load<T: DeserializeOwned + Default>(&self, name: &str) -> Result<T, String> {
+        match self.read_raw(name)? {
+            Some(data) => serde_json::from_slice(&data).map_err(|e| format!("Corrupt store document {name}: {e}")),
+            None => Ok(T::default()),
+        }
+    }
+
+    pub fn save<T: Serialize>(&self, name: &str, value: &T) -> Result<(), String> {
+        let data = serde_json::to_vec(value).map_err(|e| format!("Failed to serialize {name}: {e}"))?;
+        self.write_raw(name, &data)
+    }
+
+    /// Raw (decrypted) bytes of a saved document, or `None` when it was never saved.
+    pub fn read_raw(&self, name: &str) -> Result<Option<Vec<u8>>, String> {
+        let path = self.doc_path(name);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(&path).map_err(|e| format!("Failed to read {name}: {e}"))?;
+        if !encryption::is_sealed(&data) {
+            return Ok(Some(data));
+        }
+        let key = self.key.as_ref().ok_or(LOCKED)?;
+        encryption::open_sealed(key, &data).map(Some)
+    }
+
+    /// Replace a document with bytes that are already known to be valid JSON.
+    pub fn write_raw(&self, name: &str, data: &[u8]) -> Result<(), String> {
+        match &self.key {
+            Some(key) => self.write_unsealed(name, &encryption::seal(key, data)?),
+            // Never put plain metadata into a store meant to be encrypted
+            None if encryption::is_encrypted(&self.dir) => Err(LOCKED.into()),
+            None => self.write_unsealed(name, data),
+        }
+    }
+
+    /// Write bytes as they are, bypassing encryption.
+    pub(crate) fn write_unsealed(&self, name: &str, data: &[u8]) -> Result<(), String> {
+        let path = self.doc_path(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create store dir: {e}"))?;
+        }
+        // Write next to the target and rename so a crash never leaves half a file
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, data).map_err(|e| format!("Failed to write {name}: {e}"))?;
+        fs::rename(&tmp, &path).map_err(|e| format!("Failed to write {name}: {e}"))?;
+        Ok(())
+    }
+
+    /// Delete a document; deleting one that was never saved is fine.
+    pub fn remove(&self, name: &str) -> Result<(), String> {
+        match fs::remove_file(self.doc_path(name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to remove {name}: {e}")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Backdate a document's modification time, e.g. to keep the time it
+    /// was written on another machine.
+    pub fn set_modified(&self, name: &str, modified_ms: i64) -> Result<(), String> {
+        let time = UNIX_EPOCH + Duration::from_millis(modified_ms.max(0) as u64);
+        fs::File::options()
+            .write(true)
+            .open(self.doc_path(name))
+            .and_then(|f| f.set_modified(time))
+            .map_err(|e| format!("Failed to update {name}: {e}"))
+    }
+
+    /// Every saved document with its last modification time, in milliseconds.
+    /// Directories listed in `skip` (relative to the store) are not descended into.
+    pub fn documents(&self, skip: &[&str]) -> Result<Vec<(String, i64)>, String> {
+        let mut out = Vec::new();
+        let mut stack = vec![self.dir.clone()];
+        while let Some(dir) = stack.pop() {
+            let read_dir = fs::read_dir(&dir).map_err(|e| format!("Failed to read store dir: {e}"))?;
+            for entry in read_dir.flatten() {
+                let p = entry.path();
+                let key = crate::paths::relative_key(&self.dir, &p);
+                let Ok(meta) = entry.metadata() else { continue };
+                if meta.is_dir() {
+                    if !skip.contains(&key.as_str()) {
+                        stack.push(p);
+                    }
+                } else if let Some(name) = key.strip_suffix(".json") {
+                    out.push((name.to_string(), crate::index::mtime_ms(&meta)));
+                }
+            }
+        }
+        out.sort();
+        Ok(out)
+    }
+
+    /// Names of the documents saved under `dir` (e.g. "index"), usable with `load`.
+    pub fn list(&self, dir: &str) -> Result<Vec<String>, String> {
+        let base = self.dir.join(dir);
+        if !base.is_dir() {
+            return Ok(Vec::new());
+        }
+        let read_dir = fs::read_dir(&base).map_err(|e| format!("Failed to read store dir {dir}: {e}"))?;
+        let mut names: Vec<String> = read_dir
+            .flatten()
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                name.strip_suffix(".json").map(|stem| format!("{dir}/{stem}"))
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+}
@@ -0,0 +1,238 @@
+// One-way mirror of a folder tree onto another, typically a backup drive.
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::guards;
+use crate::index::{mtime_ms, walk_files};
+use crate::paths::{normalize_input_path, relative_key};
+use crate::settings::Settings;
+use crate::store::Store;
+use crate::{stats, trash, Progress};
+
+// FAT and exFAT store mtimes with 2 second resolution
+const MTIME_TOLERANCE_MS: i64 = 2_000;
+
+// 0 leaves copying to the OS
+static COPY_BUFFER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct SyncOptions {
+    // remove destination files that no longer exist in the source
+    pub delete_extraneous: bool,
+    // only report what would happen
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Default)]
+pub struct SyncReport {
+    pub copied: Vec<String>,
+    pub updated: Vec<String>,
+    pub deleted: Vec<String>,
+    // extraneous files a deletion rule kept from being removed
+    pub protected: Vec<String>,
+    pub unchanged: u64,
+    pub bytes: u64,
+    pub errors: Vec<String>,
+    // stopped early through the cancel flag
+    pub cancelled: bool,
+}
+
+/// Copy files through a buffer of `bytes`; None leaves it to the OS, which
+/// is usually fastest but can stall slow drives with huge writes.
+pub fn set_copy_buffer_size(bytes: Option<usize>) {
+    COPY_BUFFER.store(bytes.unwrap_or(0), Ordering::Relaxed);
+}
+
+fn copy_contents(src: &Path, dst: &Path) -> io::Result<u64> {
+    let size = COPY_BUFFER.load(Ordering::Relaxed);
+    if size == 0 {
+        return fs::copy(src, dst);
+    }
+    let mut from = File::open(src)?;
+    let mut to = File::create(dst)?;
+    let mut buf = vec![0u8; size];
+    let mut copied = 0;
+    loop {
+        let n = from.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        to.write_all(&buf[..n])?;
+        copied += n as u64;
+    }
+    to.set_permissions(from.metadata()?.permissions())?;
+    Ok(copied)
+}
+
+/// Copy through a hidden partial file and rename, carrying the source mtime
+/// over so the next sync sees the pair as unchanged.
+pub fn copy_preserving_mtime(src: &Path, dst: &Path) -> io::Result<u64> {
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let name = dst
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let partial = dst.with_file_name(format!(".{name}.lfm-partial"));
+    let copied = copy_contents(src, &partial)?;
+    let mtime = fs::metadata(src)?.modified()?;
+    File::options().write(true).open(&partial)?.set_modified(mtime)?;
+    fs::rename(&partial, dst)?;
+    Ok(copied)
+}
+
+enum Action {
+    Copy,
+    Update,
+}
+
+struct Planned {
+    rel: String,
+    from: PathBuf,
+    to: PathBuf,
+    size: u64,
+    action: Action,
+}
+
+/// Make `destination` mirror `source`: missing files are copied, files whose
+/// size or mtime differ are overwritten, and with `delete_extraneous` files
+/// absent from the source are removed, except those a deletion rule in
+/// `settings` protects. Removed files go to the destination's trash when the
+/// `trash_retention_days` setting is on. Progress is reported in bytes.
+pub fn sync_trees(
+    store: &Store,
+    settings: &Settings,
+    source: &Path,
+    destination: &Path,
+    options: SyncOptions,
+    cancel: &AtomicBool,
+    on_progress: Progress,
+) -> Result<SyncReport, String> {
+    let src = source
+        .canonicalize()
+        .map_err(|e| format!("Invalid source: {e}"))?;
+    if !src.is_dir() {
+        return Err("Source is not a directory".into());
+    }
+    if !destination.exists() && !options.dry_run {
+        fs::create_dir_all(destination).map_err(|e| format!("Failed to create destination: {e}"))?;
+    }
+    let dst = if destination.exists() {
+        destination
+            .canonicalize()
+            .map_err(|e| format!("Invalid destination: {e}"))?
+    } else {
+        destination.to_path_buf()
+    };
+    if dst.starts_with(&src) || src.starts_with(&dst) {
+        return Err("Source and destination overlap".into());
+    }
+
+    let mut report = SyncReport::default();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut plan: Vec<Planned> = Vec::new();
+    for (p, meta) in walk_files(&src) {
+        let rel = relative_key(&src, &p);
+        let target = dst.join(normalize_input_path(&rel));
+        seen.insert(rel.clone());
+        match fs::symlink_metadata(&target) {
+            Ok(m) if m.is_file() => {
+                let same = m.len() == meta.len()
+                    && (mtime_ms(&m) - mtime_ms(&meta)).abs() <= MTIME_TOLERANCE_MS;
+                if same {
+                    report.unchanged += 1;
+                } else {
+                    plan.push(Planned {
+                        rel,
+                        from: p,
+                        to: target,
+                        size: meta.len(),
+                        action: Action::Update,
+                    });
+                }
+            }
+            Ok(_) => report
+                .errors
+                .push(format!("{rel}: destination exists and is not a file")),
+            Err(_) => plan.push(Planned {
+                rel,
+                from: p,
+                to: target,
+                size: meta.len(),
+                action: Action::Copy,
+            }),
+        }
+    }
+
+    let total: u64 = plan.iter().map(|p| p.size).sum();
+    let mut done: u64 = 0;
+    for Planned { rel, from, to, size, action } in plan {
+        // Checked between files, so a cancelled sync never leaves a partial one behind
+        if cancel.load(Ordering::Relaxed) {
+            report.cancelled = true;
+            return Ok(report);
+        }
+        if !options.dry_run {
+            if let Err(e) = copy_preserving_mtime(&from, &to) {
+                report.errors.push(format!("{rel}: {e}"));
+                continue;
+            }
+        }
+        done += size;
+        report.bytes += size;
+        on_progress(done, total);
+        match action {
+            Action::Copy => report.copied.push(rel),
+            Action::Update => report.updated.push(rel),
+        }
+    }
+
+    if options.delete_extraneous && dst.is_dir() {
+        let mut extraneous = Vec::new();
+        for (p, _) in walk_files(&dst) {
+            let rel = relative_key(&dst, &p);
+            if seen.contains(&rel) {
+                continue;
+            }
+            // Nobody is asked during a sync, so rules that want confirmation protect too
+            if guards::check_delete(&settings.delete_guards, &[&p], false).is_err() {
+                report.protected.push(rel);
+                continue;
+            }
+            extraneous.push(rel);
+        }
+        if options.dry_run {
+            report.deleted = extraneous;
+        } else {
+            remove_extraneous(store, settings, &dst, &extraneous, &mut report);
+        }
+    }
+    Ok(report)
+}
+
+// Delete `extraneous` files of `dst` like any other deletion: into the trash
+// when it is on, and counted in the usage statistics.
+fn remove_extraneous(store: &Store, settings: &Settings, dst: &Path, extraneous: &[String], report: &mut SyncReport) {
+    let keep = settings.trash_retention_days.is_some();
+    let root = dst.display().to_string();
+    let _ = stats::counting_delete(store, &root, extraneous, keep, || {
+        for rel in extraneous {
+            let removed = if keep {
+                trash::trash_file(store, &root, rel)
+            } else {
+                fs::remove_file(dst.join(normalize_input_path(rel))).map_err(|e| e.to_string())
+            };
+            match removed {
+                Ok(()) => report.deleted.push(rel.clone()),
+                Err(e) => report.errors.push(format!("{rel}: {e}")),
+            }
+        }
+        Ok(())
+    });
+}
@@ -0,0 +1,200 @@
+// Transliteration of file names to plain ASCII, for car stereos, old TVs and
+// other devices that can't display anything else.
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::paths::{relative_key, resolve_within};
+
+/// How Cyrillic is spelled out; accented Latin letters are the same in all.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+    /// BGN/PCGN-like, as commonly used for Russian: ж zh, х kh, щ shch, ю yu
+    #[default]
+    Bgn,
+    /// Ukrainian national system: г h, и y, і i, ї i, є ie
+    Ukrainian,
+}
+
+#[derive(Serialize)]
+pub struct TranslitRename {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct TranslitReport {
+    pub renamed: Vec<TranslitRename>,
+    pub errors: Vec<String>,
+    pub dry_run: bool,
+}
+
+fn cyrillic(c: char, scheme: Scheme) -> Option<&'static str> {
+    let ukrainian = scheme == Scheme::Ukrainian;
+    Some(match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' if ukrainian => "h",
+        'г' => "g",
+        'ґ' => "g",
+        'д' => "d",
+        'е' => "e",
+        'ё' => "yo",
+        'є' => "ie",
+        'ж' => "zh",
+        'з' => "z",
+        'и' if ukrainian => "y",
+        'и' => "i",
+        'і' => "i",
+        'ї' => "i",
+        'й' if ukrainian => "i",
+        'й' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' => "",
+        'ы' => "y",
+        'ь' => "",
+        'э' => "e",
+        'ю' => "yu",
+        'я' => "ya",
+        'ў' => "w",
+        _ => return None,
+    })
+}
+
+fn latin(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'ğ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => "i",
+        'ł' | 'ľ' | 'ĺ' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ř' => "r",
+        'ś' | 'š' | 'ş' | 'ș' => "s",
+        'ß' => "ss",
+        'ť' | 'ţ' | 'ț' => "t",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        'þ' => "th",
+        'ð' => "d",
+        _ => return None,
+    })
+}
+
+/// ASCII version of `name`. Letters keep their case (Ж becomes Zh), and
+/// anything without a transliteration becomes `_`.
+pub fn transliterate(name: &str, scheme: Scheme) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii() {
+            out.push(c);
+            continue;
+        }
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        match cyrillic(lower, scheme).or_else(|| latin(lower)) {
+            Some(ascii) if lower != c => {
+                let mut chars = ascii.chars();
+                if let Some(first) = chars.next() {
+                    out.push(first.to_ascii_uppercase());
+                    out.push_str(chars.as_str());
+                }
+            }
+            Some(ascii) => out.push_str(ascii),
+            None => out.push('_'),
+        }
+    }
+    out
+}
+
+// `name`, or `stem (n).ext` if something else already has it or an earlier
+// rename of the same run claimed it
+fn free_sibling(dir: &Path, name: &str, claimed: &HashSet<PathBuf>) -> PathBuf {
+    let taken = |p: &PathBuf| p.exists() || claimed.contains(p);
+    let candidate = dir.join(name);
+    if !taken(&candidate) {
+        return candidate;
+    }
+    let numbered = |n: u32| match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem} ({n}).{ext}"),
+        _ => format!("{name} ({n})"),
+    };
+    (1..)
+        .map(|n| dir.join(numbered(n)))
+        .find(|p| !taken(p))
+        .unwrap_or(candidate)
+}
+
+/// Rename the selected files and folders to ASCII-only names. With
+/// `dry_run`, only report the new names.
+pub fn transliterate_names(
+    root: &str,
+    relative_paths: &[String],
+    scheme: Scheme,
+    dry_run: bool,
+) -> Result<TranslitReport, String> {
+    let root_path = Path::new(root);
+    let root_canon = root_path
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let mut targets = Vec::new();
+    for rel in relative_paths {
+        targets.push(resolve_within(root_path, rel)?);
+    }
+    // Deepest first, so renaming a folder doesn't move selected entries inside it
+    targets.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
+
+    let mut report = TranslitReport {
+        dry_run,
+        ..Default::default()
+    };
+    // A dry run renames nothing, so the names it hands out are kept here
+    let mut claimed = HashSet::new();
+    for src in targets {
+        let Some(name) = src.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let ascii = transliterate(name, scheme);
+        if ascii == name {
+            continue;
+        }
+        let parent = src.parent().ok_or("Entry has no parent directory")?;
+        let dest = free_sibling(parent, &ascii, &claimed);
+        let from = relative_key(&root_canon, &src);
+        if !dry_run {
+            if let Err(e) = fs::rename(&src, &dest) {
+                report.errors.push(format!("{from}: {e}"));
+                continue;
+            }
+        }
+        report.renamed.push(TranslitRename {
+            from,
+            to: relative_key(&root_canon, &dest),
+        });
+        claimed.insert(dest);
+    }
+    Ok(report)
+}
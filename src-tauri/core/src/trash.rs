@@ -0,0 +1,199 @@
+// Recently deleted files. With a retention period set, deleting moves a file
+// into a hidden folder at the root of its drive (a rename, so it's instant
+// and needs no extra space) and it is purged for good once the period ends.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::index::now_ms;
+use crate::paths::{relative_key, resolve_within};
+use crate::stats;
+use crate::store::Store;
+
+/// Folder at the root of a drive holding its deleted files.
+pub const TRASH_DIR: &str = ".lfm-trash";
+// roots that have something in their trash, so purging knows where to look
+const TRASH_DOC: &str = "trash";
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Kept next to each deleted file as `<id>.json`.
+#[derive(Serialize, Deserialize)]
+struct TrashRecord {
+    original: String,
+    deleted_ms: i64,
+    size: u64,
+}
+
+#[derive(Serialize)]
+pub struct TrashedItem {
+    pub root: String,
+    // path the file had before it was deleted, relative to the root
+    pub original: String,
+    pub deleted_ms: i64,
+    pub purge_at_ms: i64,
+    pub size: u64,
+}
+
+#[derive(Serialize, Default)]
+pub struct PendingPurge {
+    pub items: Vec<TrashedItem>,
+    // everything in the trash of the drives currently present
+    pub reclaimable_bytes: u64,
+    // the part of it whose retention period is over
+    pub due_bytes: u64,
+}
+
+#[derive(Serialize, Default)]
+pub struct PurgeReport {
+    pub purged: u64,
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
+
+fn registered_roots(store: &Store) -> Result<BTreeSet<String>, String> {
+    store.load(TRASH_DOC)
+}
+
+/// Move a file into the trash of its root instead of deleting it.
+pub fn trash_file(store: &Store, root: &str, relative_path: &str) -> Result<(), String> {
+    trash_file_with(store, root, relative_path, |from, to| {
+        fs::rename(from, to).map_err(|e| format!("Delete failed: {e}"))
+    })
+}
+
+/// `trash_file` with the file itself moved by `move_file`, e.g. with
+/// elevated rights. The trash folder and record are written as the user.
+pub fn trash_file_with(
+    store: &Store,
+    root: &str,
+    relative_path: &str,
+    move_file: impl FnOnce(&Path, &Path) -> Result<(), String>,
+) -> Result<(), String> {
+    let root_canon = Path::new(root)
+        .canonicalize()
+        .map_err(|e| format!("Invalid root: {e}"))?;
+    let abs_path = resolve_within(&root_canon, relative_path)?;
+    if !abs_path.is_file() {
+        return Err("Only files can be deleted with this action".into());
+    }
+    let original = relative_key(&root_canon, &abs_path);
+    if original.split('/').next() == Some(TRASH_DIR) {
+        return Err("File is already in the trash".into());
+    }
+    let trash = root_canon.join(TRASH_DIR);
+    fs::create_dir_all(&trash).map_err(|e| format!("Failed to create trash folder: {e}"))?;
+    let deleted_ms = now_ms();
+    let id = (0..)
+        .map(|n| format!("{deleted_ms}-{n}"))
+        .find(|id| !trash.join(id).exists())
+        .unwrap_or_default();
+    let size = fs::metadata(&abs_path).map(|m| m.len()).unwrap_or(0);
+    let record = TrashRecord {
+        original,
+        deleted_ms,
+        size,
+    };
+    let data = serde_json::to_vec(&record).map_err(|e| e.to_string())?;
+    fs::write(trash.join(format!("{id}.json")), data).map_err(|e| format!("Failed to write trash record: {e}"))?;
+    move_file(&abs_path, &trash.join(&id)).inspect_err(|_| {
+        let _ = fs::remove_file(trash.join(format!("{id}.json")));
+    })?;
+
+    let mut roots = registered_roots(store)?;
+    if roots.insert(root_canon.display().to_string()) {
+        store.save(TRASH_DOC, &roots)?;
+    }
+    Ok(())
+}
+
+/// Ids and records of the trash of one root.
+fn records(trash: &Path) -> Vec<(PathBuf, TrashRecord)> {
+    let Ok(read_dir) = fs::read_dir(trash) else {
+        return Vec::new();
+    };
+    read_dir
+        .flatten()
+        .filter_map(|e| {
+            let p = e.path();
+            let id = p.file_name()?.to_str()?.strip_suffix(".json")?.to_string();
+            let record: TrashRecord = serde_json::from_slice(&fs::read(&p).ok()?).ok()?;
+            Some((trash.join(id), record))
+        })
+        .collect()
+}
+
+/// What's in the trash of the drives that are plugged in, and when each
+/// file goes. `retention_days` of None means nothing is kept.
+pub fn pending_purge(store: &Store, retention_days: Option<u64>) -> Result<PendingPurge, String> {
+    let retention_ms = retention_days.unwrap_or(0) as i64 * DAY_MS;
+    let now = now_ms();
+    let mut pending = PendingPurge::default();
+    for root in registered_roots(store)? {
+        for (_, record) in records(&Path::new(&root).join(TRASH_DIR)) {
+            let purge_at_ms = record.deleted_ms + retention_ms;
+            pending.reclaimable_bytes += record.size;
+            if purge_at_ms <= now {
+                pending.due_bytes += record.size;
+            }
+            pending.items.push(TrashedItem {
+                root: root.clone(),
+                original: record.original,
+                deleted_ms: record.deleted_ms,
+                purge_at_ms,
+                size: record.size,
+            });
+        }
+    }
+    pending.items.sort_by_key(|i| i.purge_at_ms);
+    Ok(pending)
+}
+
+/// Permanently delete trashed files deleted before `deleted_before_ms`, or
+/// all of them. Drives that aren't plugged in are left for later.
+pub fn purge(store: &Store, deleted_before_ms: Option<i64>) -> Result<PurgeReport, String> {
+    let mut report = PurgeReport::default();
+    let mut roots = registered_roots(store)?;
+    let mut emptied = Vec::new();
+    for root in &roots {
+        let trash = Path::new(root).join(TRASH_DIR);
+        if !Path::new(root).is_dir() {
+            continue;
+        }
+        for (item, record) in records(&trash) {
+            if deleted_before_ms.is_some_and(|before| record.deleted_ms >= before) {
+                continue;
+            }
+            match fs::remove_file(&item) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    report.errors.push(format!("{root}: {}: {e}", record.original));
+                    continue;
+                }
+            }
+            let _ = fs::remove_file(item.with_extension("json"));
+            report.purged += 1;
+            report.bytes += record.size;
+        }
+        // Nothing left: drop the folder and stop checking this root
+        if fs::remove_dir(&trash).is_ok() || !trash.exists() {
+            emptied.push(root.clone());
+        }
+    }
+    if report.bytes > 0 {
+        let _ = stats::record_reclaimed(store, report.bytes);
+    }
+    if !emptied.is_empty() {
+        for root in emptied {
+            roots.remove(&root);
+        }
+        store.save(TRASH_DOC, &roots)?;
+    }
+    Ok(report)
+}
+
+/// Purge the files whose retention period is over.
+pub fn purge_expired(store: &Store, retention_days: u64) -> Result<PurgeReport, String> {
+    purge(store, Some(now_ms() - retention_days as i64 * DAY_MS))
+}
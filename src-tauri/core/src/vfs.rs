@@ -0,0 +1,349 @@
+// The file system calls the file operations are built on, behind a trait so
+// their root containment, conflict and batch rules can be exercised against
+// an in-memory file system in tests.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+#[cfg(test)]
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::ErrorKind,
+    path::Component,
+    sync::Mutex,
+};
+
+pub trait FileSystem {
+    /// Absolute path with symlinks, `.` and `..` resolved; fails when missing.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+    /// Whether `path` is a file or a directory, following symlinks.
+    fn is_file(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    /// Whether anything, a dangling symlink included, has this name.
+    fn exists_no_follow(&self, path: &Path) -> bool;
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+    fn file_len(&self, path: &Path) -> io::Result<u64>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+}
+
+/// The actual file system.
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn exists_no_follow(&self, path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok()
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?.map(|e| e.map(|e| e.path())).collect()
+    }
+
+    fn file_len(&self, path: &Path) -> io::Result<u64> {
+        fs::metadata(path).map(|m| m.len())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+}
+
+#[cfg(test)]
+enum Node {
+    Dir,
+    File(u64),
+    Symlink(PathBuf),
+}
+
+// Deeper symlink chains are treated as loops, like ELOOP
+#[cfg(test)]
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+/// A file system held in memory, for tests. Paths are absolute; `/` always
+/// exists. Renames and removals of paths marked with `deny` fail with
+/// `PermissionDenied`, to exercise error handling halfway through a batch.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryFs {
+    nodes: Mutex<BTreeMap<PathBuf, Node>>,
+    denied: Mutex<HashSet<PathBuf>>,
+}
+
+#[cfg(test)]
+fn not_found() -> io::Error {
+    io::Error::new(ErrorKind::NotFound, "No such file or directory")
+}
+
+#[cfg(test)]
+impl MemoryFs {
+    pub fn new() -> MemoryFs {
+        MemoryFs::default()
+    }
+
+    /// Add a file of `len` bytes, creating its parent folders.
+    pub fn file(&self, path: &str, len: u64) -> &MemoryFs {
+        let path = PathBuf::from(path);
+        if let Some(parent) = path.parent() {
+            self.create_dir_all(parent).expect("parent is not a folder");
+        }
+        self.nodes.lock().unwrap().insert(path, Node::File(len));
+        self
+    }
+
+    pub fn dir(&self, path: &str) -> &MemoryFs {
+        self.create_dir_all(Path::new(path)).expect("path is not a folder");
+        self
+    }
+
+    /// Add a symlink at `path` whose stored target is `target`.
+    pub fn symlink(&self, path: &str, target: &str) -> &MemoryFs {
+        self.nodes
+            .lock()
+            .unwrap()
+            .insert(PathBuf::from(path), Node::Symlink(PathBuf::from(target)));
+        self
+    }
+
+    /// Make renames and removals of `path` fail.
+    pub fn deny(&self, path: &str) -> &MemoryFs {
+        self.denied.lock().unwrap().insert(PathBuf::from(path));
+        self
+    }
+
+    /// Every file, sorted, for comparing whole trees in assertions.
+    pub fn files(&self) -> Vec<String> {
+        self.nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, n)| matches!(n, Node::File(_)))
+            .map(|(p, _)| p.display().to_string())
+            .collect()
+    }
+
+    fn resolve(&self, path: &Path, depth: usize) -> io::Result<PathBuf> {
+        if depth > MAX_SYMLINK_DEPTH {
+            return Err(io::Error::other("Too many levels of symbolic links"));
+        }
+        let mut out = PathBuf::from("/");
+        for part in path.components() {
+            match part {
+                Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+                Component::ParentDir => {
+                    out.pop();
+                }
+                Component::Normal(name) => {
+                    out.push(name);
+                    let target = match self.nodes.lock().unwrap().get(&out) {
+                        Some(Node::Symlink(target)) => Some(target.clone()),
+                        Some(_) => None,
+                        None => return Err(not_found()),
+                    };
+                    if let Some(target) = target {
+                        out.pop();
+                        out = self.resolve(&out.join(target), depth + 1)?;
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn check_allowed(&self, path: &Path) -> io::Result<()> {
+        if self.denied.lock().unwrap().contains(path) {
+            return Err(io::Error::new(ErrorKind::PermissionDenied, "Permission denied"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl FileSystem for MemoryFs {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.resolve(path, 0)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        let Ok(path) = self.resolve(path, 0) else {
+            return false;
+        };
+        matches!(self.nodes.lock().unwrap().get(&path), Some(Node::File(_)))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        let Ok(path) = self.resolve(path, 0) else {
+            return false;
+        };
+        path == Path::new("/") || matches!(self.nodes.lock().unwrap().get(&path), Some(Node::Dir))
+    }
+
+    fn exists_no_follow(&self, path: &Path) -> bool {
+        let Some(parent) = path.parent() else {
+            return true;
+        };
+        let Ok(parent) = self.resolve(parent, 0) else {
+            return false;
+        };
+        let name = path.file_name().unwrap_or_default();
+        self.nodes.lock().unwrap().contains_key(&parent.join(name))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if !self.is_dir(path) {
+            return Err(not_found());
+        }
+        let dir = self.resolve(path, 0)?;
+        Ok(self
+            .nodes
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|p| p.parent() == Some(dir.as_path()))
+            .cloned()
+            .collect())
+    }
+
+    fn file_len(&self, path: &Path) -> io::Result<u64> {
+        let path = self.resolve(path, 0)?;
+        match self.nodes.lock().unwrap().get(&path) {
+            Some(Node::File(len)) => Ok(*len),
+            _ => Err(not_found()),
+        }
+    }
+
+    /// Like POSIX rename(2) for files: a file at `to` is replaced.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.check_allowed(from)?;
+        if !self.is_dir(to.parent().unwrap_or(Path::new("/"))) {
+            return Err(not_found());
+        }
+        let mut nodes = self.nodes.lock().unwrap();
+        if matches!(nodes.get(to), Some(Node::Dir)) {
+            return Err(io::Error::new(ErrorKind::AlreadyExists, "Is a directory"));
+        }
+        let node = nodes.remove(from).ok_or_else(not_found)?;
+        if matches!(node, Node::Dir) {
+            // Carry the folder's contents along
+            let inside: Vec<PathBuf> = nodes.keys().filter(|p| p.starts_with(from)).cloned().collect();
+            for p in inside {
+                let moved = to.join(p.strip_prefix(from).unwrap_or(&p));
+                if let Some(n) = nodes.remove(&p) {
+                    nodes.insert(moved, n);
+                }
+            }
+        }
+        nodes.insert(to.to_path_buf(), node);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.check_allowed(path)?;
+        let mut nodes = self.nodes.lock().unwrap();
+        match nodes.get(path) {
+            Some(Node::Dir) => Err(io::Error::new(ErrorKind::PermissionDenied, "Is a directory")),
+            Some(_) => {
+                nodes.remove(path);
+                Ok(())
+            }
+            None => Err(not_found()),
+        }
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut current = PathBuf::from("/");
+        for part in path.components() {
+            match part {
+                Component::Normal(name) => current.push(name),
+                Component::ParentDir => {
+                    current.pop();
+                    continue;
+                }
+                _ => continue,
+            }
+            let is_link = matches!(self.nodes.lock().unwrap().get(&current), Some(Node::Symlink(_)));
+            if is_link {
+                current = self.resolve(&current, 0)?;
+            }
+            let mut nodes = self.nodes.lock().unwrap();
+            match nodes.get(&current) {
+                Some(Node::Dir) => {}
+                Some(_) => return Err(io::Error::new(ErrorKind::AlreadyExists, "Not a directory")),
+                None if is_link => return Err(not_found()),
+                None => {
+                    nodes.insert(current.clone(), Node::Dir);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonicalize_resolves_dots_and_symlinks() {
+        let fs = MemoryFs::new();
+        fs.file("/card/a/x.jpg", 1)
+            .dir("/card/b")
+            .symlink("/card/b/link", "../a");
+        assert_eq!(
+            fs.canonicalize(Path::new("/card/b/../a/./x.jpg")).unwrap(),
+            Path::new("/card/a/x.jpg")
+        );
+        assert_eq!(
+            fs.canonicalize(Path::new("/card/b/link/x.jpg")).unwrap(),
+            Path::new("/card/a/x.jpg")
+        );
+        assert!(fs.canonicalize(Path::new("/card/missing")).is_err());
+    }
+
+    #[test]
+    fn symlink_loops_fail() {
+        let fs = MemoryFs::new();
+        fs.dir("/card").symlink("/card/a", "b").symlink("/card/b", "a");
+        assert!(fs.canonicalize(Path::new("/card/a")).is_err());
+    }
+
+    #[test]
+    fn rename_replaces_files_and_moves_folder_contents() {
+        let fs = MemoryFs::new();
+        fs.file("/card/a.jpg", 1)
+            .file("/card/b.jpg", 2)
+            .file("/card/d/c.jpg", 3);
+        fs.rename(Path::new("/card/a.jpg"), Path::new("/card/b.jpg")).unwrap();
+        assert_eq!(fs.file_len(Path::new("/card/b.jpg")).unwrap(), 1);
+        fs.rename(Path::new("/card/d"), Path::new("/card/e")).unwrap();
+        assert_eq!(fs.files(), vec!["/card/b.jpg", "/card/e/c.jpg"]);
+    }
+
+    #[test]
+    fn denied_paths_fail() {
+        let fs = MemoryFs::new();
+        fs.file("/card/a.jpg", 1).deny("/card/a.jpg");
+        assert!(fs.rename(Path::new("/card/a.jpg"), Path::new("/card/b.jpg")).is_err());
+        assert!(fs.remove_file(Path::new("/card/a.jpg")).is_err());
+        assert_eq!(fs.files(), vec!["/card/a.jpg"]);
+    }
+}
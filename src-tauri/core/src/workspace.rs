@@ -0,0 +1,164 @@
+// Scratch space for intermediate files: listing result sets, the output of
+// file actions and similar staging. Each run of the app or the CLI gets its
+// own folder under a private base directory in the user's cache dir, removed
+// on exit. A run holds a lock on `<session>.lock` next to its folder for as
+// long as it lives, so folders left behind by crashed runs are told apart
+// from those of running ones and removed the next time the workspace is
+// cleaned. Nothing is ever staged on a root.
+use serde::Serialize;
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use crate::index::now_ms;
+use crate::store::APP_IDENTIFIER;
+
+const SESSION_PREFIX: &str = "session-";
+// Folders without a lock file, from versions that didn't write one, are
+// considered abandoned once untouched for this long
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+static BASE: RwLock<Option<PathBuf>> = RwLock::new(None);
+// The session's name and its held lock, None when the base can't be written
+static SESSION: OnceLock<(String, Option<File>)> = OnceLock::new();
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize, Default)]
+pub struct CleanReport {
+    pub removed_entries: u64,
+    pub removed_bytes: u64,
+}
+
+/// Keep workspaces under `base` from now on. Called once at startup, before
+/// anything is staged.
+pub fn set_base(base: PathBuf) {
+    *BASE.write().unwrap() = Some(base);
+}
+
+// The same folder the desktop app uses, unless the app chose another one
+fn base() -> PathBuf {
+    BASE.read().unwrap().clone().unwrap_or_else(|| {
+        let cache = dirs::cache_dir().unwrap_or_else(env::temp_dir);
+        cache.join(APP_IDENTIFIER).join("workspace")
+    })
+}
+
+// Private to the user: staged files are copies of whatever was on the drives
+fn create_base(base: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(base)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(base, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+fn session_name() -> &'static str {
+    let (name, _) = SESSION.get_or_init(|| {
+        let name = format!("{SESSION_PREFIX}{}-{}", std::process::id(), now_ms());
+        let base = base();
+        // Held until the process exits, however it exits. Locked before it
+        // gets its final name, or a cleaner could take it for a dead run's
+        let pending = base.join(format!("{name}.pending"));
+        let lock = create_base(&base)
+            .and_then(|_| File::create(&pending))
+            .and_then(|f| f.lock().map(|_| f))
+            .and_then(|f| fs::rename(&pending, base.join(format!("{name}.lock"))).map(|_| f))
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&pending);
+            })
+            .ok();
+        (name, lock)
+    });
+    name
+}
+
+/// This run's workspace folder. It may not exist yet.
+pub fn session_dir() -> PathBuf {
+    base().join(session_name())
+}
+
+/// A fresh, empty folder in the workspace, e.g. `action-…` for the output of
+/// one file action.
+pub fn scratch(prefix: &str) -> Result<PathBuf, String> {
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let dir = session_dir().join(format!("{prefix}-{n}"));
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create scratch dir: {e}"))?;
+    Ok(dir)
+}
+
+fn tally(path: &Path, report: &mut CleanReport) {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return;
+    };
+    if meta.is_dir() {
+        if let Ok(read_dir) = fs::read_dir(path) {
+            for entry in read_dir.flatten() {
+                tally(&entry.path(), report);
+            }
+        }
+    } else {
+        report.removed_entries += 1;
+        report.removed_bytes += meta.len();
+    }
+}
+
+fn remove(path: &Path, report: &mut CleanReport) {
+    let mut removed = CleanReport::default();
+    tally(path, &mut removed);
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    if result.is_ok() {
+        report.removed_entries += removed.removed_entries;
+        report.removed_bytes += removed.removed_bytes;
+    }
+}
+
+// Whether the run that owns the session folder `name` is gone: nobody holds
+// its lock, or it never had one and hasn't been touched for a long time.
+fn abandoned(base: &Path, name: &str) -> bool {
+    match File::open(base.join(format!("{name}.lock"))) {
+        Ok(lock) => lock.try_lock().is_ok(),
+        Err(_) => fs::metadata(base.join(name))
+            .and_then(|m| m.modified())
+            .is_ok_and(|t| t < SystemTime::now() - STALE_AFTER),
+    }
+}
+
+/// Remove the workspaces abandoned by runs that crashed. This run's and
+/// those of other instances still running are left alone.
+pub fn clean_workspace() -> Result<CleanReport, String> {
+    let mut report = CleanReport::default();
+    let base = base();
+    let Ok(read_dir) = fs::read_dir(&base) else {
+        return Ok(report);
+    };
+    let own = session_name();
+    for entry in read_dir.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let name = file_name.strip_suffix(".lock").unwrap_or(&file_name);
+        if !name.starts_with(SESSION_PREFIX) || name == own {
+            continue;
+        }
+        // A folder and its lock file are both visited; whichever comes first
+        // removes the pair
+        if entry.path().exists() && abandoned(&base, name) {
+            remove(&base.join(name), &mut report);
+            let _ = fs::remove_file(base.join(format!("{name}.lock")));
+        }
+    }
+    Ok(report)
+}
+
+/// Remove this run's workspace, on exit.
+pub fn remove_session() {
+    let _ = fs::remove_dir_all(session_dir());
+    let _ = fs::remove_file(base().join(format!("{}.lock", session_name())));
+}
@@ -0,0 +1,20 @@
+use lfm_core::actions::{self, ActionOutput, ActionSummary};
+use tauri::AppHandle;
+
+use crate::store;
+
+/// Custom actions configured for this file, for the context menu.
+#[tauri::command]
+pub fn list_file_actions(app: AppHandle, root: String, relative_path: String) -> Result<Vec<ActionSummary>, String> {
+    actions::list_file_actions(&store::open(&app)?, &root, &relative_path)
+}
+
+#[tauri::command(async)]
+pub fn run_file_action(
+    app: AppHandle,
+    root: String,
+    relative_path: String,
+    action_id: String,
+) -> Result<ActionOutput, String> {
+    actions::run_file_action(&store::open(&app)?, &root, &relative_path, &action_id)
+}
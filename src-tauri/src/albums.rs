@@ -0,0 +1,8 @@
+use lfm_core::albums::{self, AlbumOptions, AlbumPlan};
+
+/// Proposed album folders for a flat dump of photos and videos, with the
+/// moves that would get there. Nothing is moved.
+#[tauri::command(async)]
+pub fn suggest_albums(root: String, relative_dir: String, options: Option<AlbumOptions>) -> Result<AlbumPlan, String> {
+    albums::suggest_albums(&root, &relative_dir, options.unwrap_or_default())
+}
@@ -0,0 +1,31 @@
+use lfm_core::backup::{self, ExportSummary, ImportSummary, MergeReport};
+use lfm_core::i18n;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::notify;
+use crate::store;
+
+/// Write the active profile's catalogs, accepted duplicates and settings
+/// into a single archive at `destination`.
+#[tauri::command(async)]
+pub fn export_metadata(app: AppHandle, destination: String) -> Result<ExportSummary, String> {
+    let result = backup::export_metadata(&store::open(&app)?, Path::new(&destination));
+    notify::job_finished(&app, "Metadata backup", &result, |s| {
+        i18n::localize(format!("{} documents written to {destination}", s.documents))
+    });
+    result
+}
+
+/// Load an archive written by `export_metadata` into the active profile.
+#[tauri::command(async)]
+pub fn import_metadata(app: AppHandle, archive: String) -> Result<ImportSummary, String> {
+    backup::import_metadata(&store::open(&app)?, Path::new(&archive))
+}
+
+/// Two-way merge of the active profile with an archive, typically one kept
+/// on a drive shared between machines; the newer copy of each document wins.
+#[tauri::command(async)]
+pub fn merge_metadata(app: AppHandle, archive: String) -> Result<MergeReport, String> {
+    backup::merge_metadata(&store::open(&app)?, Path::new(&archive))
+}
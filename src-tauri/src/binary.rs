@@ -0,0 +1,28 @@
+use lfm_core::{fsops, listing};
+use serde::Serialize;
+use tauri::ipc::Response;
+
+// Raw MessagePack responses for result sets where JSON encoding and parsing
+// dominate: the webview gets an ArrayBuffer to decode with a MessagePack
+// library instead of a giant JSON string.
+fn msgpack<T: Serialize>(value: &T) -> Result<Response, String> {
+    rmp_serde::to_vec_named(value)
+        .map(Response::new)
+        .map_err(|e| format!("Failed to encode response: {e}"))
+}
+
+/// `list_files`, or `list_files_compact` when `compact` is set, as MessagePack.
+#[tauri::command(async)]
+pub fn list_files_binary(root: String, show_hidden: bool, compact: bool) -> Result<Response, String> {
+    if compact {
+        msgpack(&fsops::list_files_compact(&root, show_hidden)?)
+    } else {
+        msgpack(&fsops::list_files(&root, show_hidden)?)
+    }
+}
+
+/// `listing_page` as MessagePack.
+#[tauri::command(async)]
+pub fn listing_page_binary(id: String, offset: u64, limit: usize) -> Result<Response, String> {
+    msgpack(&listing::listing_page(&id, offset, limit)?)
+}
@@ -0,0 +1,23 @@
+use lfm_core::compare::{self, CompareResult, FileRef, TreeDigest};
+use lfm_core::hashing::HashCache;
+use tauri::{AppHandle, Manager};
+
+/// Byte-for-byte comparison of two files, each given by a root and a path
+/// relative to it, stopping at the first difference.
+#[tauri::command(async)]
+pub fn compare_files(a: FileRef, b: FileRef) -> Result<CompareResult, String> {
+    compare::compare_files(&a, &b)
+}
+
+/// Unified diff of two small text files, each given by a root and a path
+/// relative to it.
+#[tauri::command(async)]
+pub fn diff_text_files(a: FileRef, b: FileRef, context_lines: usize) -> Result<String, String> {
+    compare::diff_text_files(&a, &b, context_lines)
+}
+
+/// Merkle digest of a folder tree, equal for identical trees.
+#[tauri::command(async)]
+pub fn tree_digest(app: AppHandle, root: String, relative_dir: String) -> Result<TreeDigest, String> {
+    compare::tree_digest(&app.state::<HashCache>(), &root, &relative_dir)
+}
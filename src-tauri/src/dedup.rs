@@ -0,0 +1,52 @@
+use lfm_core::dedup::{self, AcceptedDuplicate, CatalogInfo, DuplicateCluster};
+use lfm_core::hashing::HashCache;
+use lfm_core::settings;
+use tauri::{AppHandle, Manager};
+
+use crate::events::ProgressEvents;
+use crate::index::emit_hash_progress;
+use crate::store;
+
+#[tauri::command(async)]
+pub fn list_catalogs(app: AppHandle) -> Result<Vec<CatalogInfo>, String> {
+    dedup::list_catalogs(&store::open(&app)?)
+}
+
+/// Group identical files across mounted `roots` and stored `catalog_ids`.
+#[tauri::command(async)]
+pub fn find_duplicates(
+    app: AppHandle,
+    roots: Vec<String>,
+    catalog_ids: Vec<String>,
+    min_size: u64,
+) -> Result<Vec<DuplicateCluster>, String> {
+    let store = store::open(&app)?;
+    let threads = settings::load(&store)?.hash_threads;
+    let cache = app.state::<HashCache>();
+    let events = ProgressEvents::new(&app);
+    dedup::find_duplicates(
+        &store,
+        &cache,
+        &roots,
+        &catalog_ids,
+        min_size,
+        threads,
+        &|done, total| emit_hash_progress(&events, "index://progress", done, total),
+    )
+}
+
+/// Mark a duplicate cluster as intentional so `find_duplicates` skips it.
+#[tauri::command]
+pub fn accept_duplicate(app: AppHandle, hash: String, size: u64, note: String) -> Result<(), String> {
+    dedup::accept_duplicate(&store::open(&app)?, hash, size, note)
+}
+
+#[tauri::command]
+pub fn unaccept_duplicate(app: AppHandle, hash: String) -> Result<(), String> {
+    dedup::unaccept_duplicate(&store::open(&app)?, &hash)
+}
+
+#[tauri::command]
+pub fn list_accepted_duplicates(app: AppHandle) -> Result<Vec<AcceptedDuplicate>, String> {
+    dedup::list_accepted_duplicates(&store::open(&app)?)
+}
@@ -0,0 +1,124 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::jobs::{self, JobGuard};
+
+pub const DEFAULT_FLUSH_MS: u64 = 100;
+
+/// Coalesces progress events so mass operations don't flood the webview:
+/// per event name and job, at most one event per flush interval is emitted.
+/// Progress payloads are cumulative, so the one that goes out summarizes the
+/// ones that were dropped; the latest one held back is sent when the job is
+/// done.
+pub struct EventThrottle {
+    flush_ms: AtomicU64,
+    slots: Mutex<HashMap<(String, u64), Slot>>,
+}
+
+struct Slot {
+    emitted_at: Instant,
+    // the latest payload not emitted yet
+    held: Option<Value>,
+}
+
+impl Default for EventThrottle {
+    fn default() -> Self {
+        EventThrottle {
+            flush_ms: AtomicU64::new(DEFAULT_FLUSH_MS),
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl EventThrottle {
+    pub fn set_flush_ms(&self, ms: Option<u64>) {
+        self.flush_ms.store(ms.unwrap_or(DEFAULT_FLUSH_MS), Ordering::Relaxed);
+    }
+
+    // `payload` when it is due, otherwise it is held for `take_held`
+    fn offer(&self, event: &str, job: u64, payload: Value, last_one: bool) -> Option<Value> {
+        let interval = Duration::from_millis(self.flush_ms.load(Ordering::Relaxed));
+        let now = Instant::now();
+        let mut slots = self.slots.lock().unwrap();
+        let key = (event.to_string(), job);
+        match slots.get_mut(&key) {
+            Some(slot) if !last_one && now.duration_since(slot.emitted_at) < interval => {
+                slot.held = Some(payload);
+                None
+            }
+            _ => {
+                let slot = Slot {
+                    emitted_at: now,
+                    held: None,
+                };
+                slots.insert(key, slot);
+                Some(payload)
+            }
+        }
+    }
+
+    // Forget `job`, returning the events it still has held back
+    fn take_held(&self, job: u64) -> Vec<(String, Value)> {
+        let mut slots = self.slots.lock().unwrap();
+        let keys: Vec<(String, u64)> = slots.keys().filter(|(_, j)| *j == job).cloned().collect();
+        keys.into_iter()
+            .filter_map(|key| {
+                let held = slots.remove(&key)?.held?;
+                Some((key.0, held))
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+struct JobEvent<S> {
+    job: u64,
+    #[serde(flatten)]
+    payload: S,
+}
+
+/// The progress events of one operation, sent through the throttle with the
+/// id of its job in the payload. Operations that don't run as a job get an
+/// id of their own. Dropping it sends what the throttle held back.
+pub struct ProgressEvents<'a> {
+    app: &'a AppHandle,
+    job: u64,
+}
+
+impl<'a> ProgressEvents<'a> {
+    pub fn new(app: &'a AppHandle) -> Self {
+        ProgressEvents {
+            app,
+            job: jobs::new_id(app),
+        }
+    }
+
+    pub fn for_job(app: &'a AppHandle, job: &JobGuard) -> Self {
+        ProgressEvents { app, job: job.id() }
+    }
+
+    /// Emit a progress event. `last_one` marks the final update of an
+    /// operation, which is never held back.
+    pub fn emit<S: Serialize>(&self, event: &str, payload: S, last_one: bool) {
+        let Ok(payload) = serde_json::to_value(JobEvent { job: self.job, payload }) else {
+            return;
+        };
+        let throttle = self.app.state::<EventThrottle>();
+        if let Some(payload) = throttle.offer(event, self.job, payload, last_one) {
+            let _ = self.app.emit(event, payload);
+        }
+    }
+}
+
+impl Drop for ProgressEvents<'_> {
+    fn drop(&mut self) {
+        for (event, payload) in self.app.state::<EventThrottle>().take_held(self.job) {
+            let _ = self.app.emit(&event, payload);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use lfm_core::filetype::{self, ExtensionFix, ExtensionMismatch};
+use lfm_core::media::{self, MediaScanReport};
+use lfm_core::hooks;
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::events::ProgressEvents;
+use crate::index::emit_hash_progress;
+use crate::store;
+
+/// Files whose magic bytes don't match their extension, emitting
+/// `sniff://progress` as files are checked.
+#[tauri::command(async)]
+pub fn find_extension_mismatches(
+    app: AppHandle,
+    root: String,
+    relative_dir: String,
+) -> Result<Vec<ExtensionMismatch>, String> {
+    let events = ProgressEvents::new(&app);
+    filetype::find_extension_mismatches(&root, &relative_dir, &|done, total| {
+        emit_hash_progress(&events, "sniff://progress", done, total)
+    })
+}
+
+/// Rename mislabeled files to the extension matching their content.
+#[tauri::command]
+pub fn fix_extensions(app: AppHandle, root: String, relative_paths: Vec<String>) -> Result<Vec<ExtensionFix>, String> {
+    let details = json!({ "root": root, "relative_paths": relative_paths });
+    hooks::around(&store::open(&app)?, "rename", details, || {
+        filetype::fix_extensions(&root, &relative_paths)
+    })
+}
+
+/// Flag damaged images, videos and archives below a folder, emitting
+/// `media-scan://progress`.
+#[tauri::command(async)]
+pub fn scan_damaged_media(
+    app: AppHandle,
+    root: String,
+    relative_dir: String,
+    full_decode: bool,
+) -> Result<MediaScanReport, String> {
+    let events = ProgressEvents::new(&app);
+    media::scan_damaged_media(&root, &relative_dir, full_decode, &|done, total| {
+        emit_hash_progress(&events, "media-scan://progress", done, total)
+    })
+}
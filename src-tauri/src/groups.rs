@@ -0,0 +1,94 @@
+use lfm_core::groups;
+use lfm_core::guards;
+use lfm_core::hooks;
+use lfm_core::i18n;
+use lfm_core::quota;
+use lfm_core::settings;
+use lfm_core::stats;
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::jobs;
+use crate::store;
+
+/// Relative paths of the files sharing a stem with `relative_path`
+/// (RAW + JPEG + XMP, video + subtitles), itself included.
+#[tauri::command]
+pub fn file_group(root: String, relative_path: String) -> Result<Vec<String>, String> {
+    groups::file_group(&root, &relative_path).map_err(i18n::localize)
+}
+
+/// Rename a file and give the rest of its group the same new stem. All or
+/// nothing; returns the new relative paths.
+#[tauri::command]
+pub fn rename_group(
+    app: AppHandle,
+    root: String,
+    relative_path: String,
+    new_name: String,
+) -> Result<Vec<String>, String> {
+    jobs::check_free(&app, "rename", &root, &[&relative_path])?;
+    let details = json!({ "root": root, "relative_path": relative_path, "new_name": new_name, "group": true });
+    hooks::around(&store::open(&app)?, "rename", details, || {
+        groups::rename_group(&root, &relative_path, &new_name)
+    })
+    .map_err(i18n::localize)
+}
+
+/// Move a file together with its group. All or nothing; returns the new
+/// relative paths. Folder limits apply as for `move_file`.
+#[tauri::command]
+pub fn move_group(
+    app: AppHandle,
+    root: String,
+    from_relative: String,
+    to_relative_dir: String,
+    create_dir: bool,
+    override_quota: Option<bool>,
+) -> Result<Vec<String>, String> {
+    jobs::check_free(&app, "move", &root, &[&from_relative, &to_relative_dir])?;
+    let store = store::open(&app)?;
+    let members = groups::file_group(&root, &from_relative).map_err(i18n::localize)?;
+    if !override_quota.unwrap_or(false) {
+        let quotas = settings::load(&store)?.folder_quotas;
+        quota::check_move(&quotas, &root, &members, &to_relative_dir).map_err(i18n::localize)?;
+    }
+    let details =
+        json!({ "root": root, "from_relative": from_relative, "to_relative_dir": to_relative_dir, "group": true });
+    stats::counting_move(&store, &root, &members, || {
+        hooks::around(&store, "move", details, || {
+            groups::move_group(&root, &from_relative, &to_relative_dir, create_dir)
+        })
+    })
+    .map_err(i18n::localize)
+}
+
+/// Delete a file together with its group, or move them to the drive's trash
+/// when the `trash_retention_days` setting is on; returns their relative
+/// paths. Deletion rules apply to every member, as for `delete_file`.
+#[tauri::command]
+pub fn delete_group(
+    app: AppHandle,
+    root: String,
+    relative_path: String,
+    confirmed: Option<bool>,
+) -> Result<Vec<String>, String> {
+    jobs::check_free(&app, "delete", &root, &[&relative_path])?;
+    let store = store::open(&app)?;
+    let members = groups::file_group(&root, &relative_path).map_err(i18n::localize)?;
+    let settings = settings::load(&store)?;
+    guards::check_delete_within(&settings.delete_guards, &root, &members, confirmed.unwrap_or(false))
+        .map_err(i18n::localize)?;
+    let keep = settings.trash_retention_days.is_some();
+    let details = json!({ "root": root, "relative_path": relative_path, "group": true, "trash": keep });
+    stats::counting_delete(&store, &root, &members, keep, || {
+        hooks::around(&store, "delete", details, || {
+            if keep {
+                groups::trash_group(&store, &root, &relative_path)
+            } else {
+                groups::delete_group(&root, &relative_path)
+            }
+        })
+    })
+    .map_err(i18n::localize)
+}
@@ -0,0 +1,69 @@
+use lfm_core::imaging::{self, ConvertReport, TargetFormat};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::events::ProgressEvents;
+
+#[derive(Serialize)]
+struct ConvertProgress {
+    done: u64,
+    total: u64,
+}
+
+/// Convert the selected images to JPEG or WebP into the `dest` folder
+/// (relative to root), emitting `convert://progress`.
+#[tauri::command(async)]
+pub fn convert_images(
+    app: AppHandle,
+    root: String,
+    relative_paths: Vec<String>,
+    target_format: TargetFormat,
+    quality: u8,
+    dest: String,
+) -> Result<ConvertReport, String> {
+    let events = ProgressEvents::new(&app);
+    imaging::convert_images(&root, &relative_paths, target_format, quality, &dest, &|done, total| {
+        events.emit("convert://progress", ConvertProgress { done, total }, done == total);
+    })
+}
+
+/// Downscaled copies of the selected images for sharing, emitting
+/// `convert://progress`.
+#[tauri::command(async)]
+pub fn resize_images(
+    app: AppHandle,
+    root: String,
+    relative_paths: Vec<String>,
+    max_dimension: u32,
+    target_format: TargetFormat,
+    quality: u8,
+    dest: String,
+) -> Result<ConvertReport, String> {
+    let events = ProgressEvents::new(&app);
+    imaging::resize_images(
+        &root,
+        &relative_paths,
+        max_dimension,
+        target_format,
+        quality,
+        &dest,
+        &|done, total| {
+            events.emit("convert://progress", ConvertProgress { done, total }, done == total);
+        },
+    )
+}
+
+/// Export copies of JPEG/PNG images with EXIF, GPS and text metadata
+/// removed losslessly, emitting `convert://progress`.
+#[tauri::command(async)]
+pub fn strip_metadata(
+    app: AppHandle,
+    root: String,
+    relative_paths: Vec<String>,
+    dest_dir: String,
+) -> Result<ConvertReport, String> {
+    let events = ProgressEvents::new(&app);
+    imaging::strip_metadata(&root, &relative_paths, &dest_dir, &|done, total| {
+        events.emit("convert://progress", ConvertProgress { done, total }, done == total);
+    })
+}
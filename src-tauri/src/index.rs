@@ -0,0 +1,98 @@
+use lfm_core::hashing::HashCache;
+use lfm_core::hooks;
+use lfm_core::i18n;
+use lfm_core::index::{self, IndexSummary, VerifyReport};
+use lfm_core::settings;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::events::ProgressEvents;
+use crate::jobs;
+use crate::notify;
+use crate::store;
+
+/// Roots whose catalog sidecar was already looked at, per store, so
+/// listing a folder doesn't read it again.
+#[derive(Default)]
+pub struct LoadedSidecars(Mutex<HashSet<(PathBuf, PathBuf)>>);
+
+/// Pick up the catalog that travelled with the drive of `root_canon` from
+/// another machine, once per root and profile.
+pub fn load_sidecar_once(app: &AppHandle, root_canon: &Path) {
+    let Ok(store) = store::open(app) else {
+        return;
+    };
+    let key = (store.dir().to_path_buf(), root_canon.to_path_buf());
+    let loaded = app.state::<LoadedSidecars>();
+    if loaded.0.lock().unwrap().contains(&key) {
+        return;
+    }
+    // A locked store is tried again once it is unlocked
+    if index::load_sidecar(&store, root_canon).is_ok() {
+        loaded.0.lock().unwrap().insert(key);
+    }
+}
+
+#[derive(Serialize)]
+pub struct HashProgress {
+    done: u64,
+    total: u64,
+}
+
+pub fn emit_hash_progress(events: &ProgressEvents, event: &str, done: u64, total: u64) {
+    events.emit(event, HashProgress { done, total }, done == total);
+}
+
+/// Build or refresh the hash index of a root.
+#[tauri::command(async)]
+pub fn index_root(app: AppHandle, root: String) -> Result<IndexSummary, String> {
+    let store = store::open(&app)?;
+    let threads = settings::load(&store)?.hash_threads;
+    let root_canon = index::canonical_root(&root)?;
+    let cache = app.state::<HashCache>();
+    let job = jobs::start(&app, format!("Indexing {root}"), false);
+    job.depends_on(&[&root_canon]);
+    let events = ProgressEvents::for_job(&app, &job);
+    let result = hooks::around(&store, "scan", json!({ "root": root }), || {
+        index::refresh_index(&store, &cache, &root_canon, threads, &|done, total| {
+            job.progress(done, total);
+            emit_hash_progress(&events, "index://progress", done, total)
+        })
+        .map(|(_, summary)| summary)
+    })
+    .map_err(|e| job.explain(e));
+    notify::job_finished(&app, "Indexing", &result, |s| {
+        i18n::localize(format!("{root}: {} files, {} hashed", s.files, s.hashed))
+    });
+    result
+}
+
+/// Re-hash every indexed file whose size and mtime haven't changed and
+/// report the ones whose contents did, i.e. likely bit rot.
+#[tauri::command(async)]
+pub fn verify_root(app: AppHandle, root: String) -> Result<VerifyReport, String> {
+    let store = store::open(&app)?;
+    let threads = settings::load(&store)?.hash_threads;
+    let root_canon = index::canonical_root(&root)?;
+    let cache = app.state::<HashCache>();
+    let job = jobs::start(&app, format!("Verifying {root}"), false);
+    job.depends_on(&[&root_canon]);
+    let events = ProgressEvents::for_job(&app, &job);
+    let result = index::verify_index(&store, &cache, &root_canon, threads, &|done, total| {
+        job.progress(done, total);
+        emit_hash_progress(&events, "verify://progress", done, total)
+    })
+    .map_err(|e| job.explain(e));
+    notify::job_finished(&app, "Verification", &result, |r| {
+        i18n::localize(format!(
+            "{root}: {} checked, {} corrupted",
+            r.checked,
+            r.corrupted.len()
+        ))
+    });
+    result
+}
@@ -0,0 +1,383 @@
+use lfm_core::i18n;
+use lfm_core::paths::{normalize_input_path, volume_of};
+use lfm_core::power::{self, SleepInhibitor};
+use lfm_core::reservations;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::tray;
+
+#[derive(Serialize, Clone)]
+pub struct JobInfo {
+    pub id: u64,
+    pub label: String,
+    pub done: u64,
+    pub total: u64,
+    pub cancellable: bool,
+    // waiting for an unplugged drive to come back
+    pub suspended: bool,
+}
+
+struct Job {
+    info: JobInfo,
+    cancel: Arc<AtomicBool>,
+}
+
+/// A path a job is writing to, with everything under it.
+struct Reservation {
+    job: u64,
+    label: String,
+    path: PathBuf,
+}
+
+/// Emitted as `jobs://contention` when an operation runs into a path held
+/// by a job.
+#[derive(Serialize, Clone)]
+struct Contention {
+    label: String,
+    path: String,
+    held_by: String,
+    // the operation waits for the job instead of failing
+    waiting: bool,
+}
+
+// How often a job waiting for a reservation checks whether it was cancelled
+const WAIT_POLL: Duration = Duration::from_millis(200);
+// How often a running job checks that its drives are still there
+const ROOT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Payload of `root://lost` and `root://restored`.
+#[derive(Serialize, Clone)]
+struct RootEvent {
+    root: String,
+    job: String,
+}
+
+/// A folder a job reads or writes, with the file system it was on.
+struct WatchedRoot {
+    path: PathBuf,
+    volume: u64,
+}
+
+impl WatchedRoot {
+    fn is_lost(&self) -> bool {
+        match volume_of(&self.path) {
+            None => true,
+            Some(v) if v == self.volume => false,
+            // Another file system: either the drive came back under a new
+            // device number, or this is the bare mount point it left behind
+            Some(_) => !fs::read_dir(&self.path).is_ok_and(|mut d| d.next().is_some()),
+        }
+    }
+}
+
+/// Long-running operations currently in progress, shown in the tray menu
+/// and kept alive when the window is closed. System sleep is inhibited
+/// while any of them runs.
+#[derive(Default)]
+pub struct Jobs {
+    next_id: AtomicU64,
+    running: Mutex<BTreeMap<u64, Job>>,
+    awake: Mutex<Option<SleepInhibitor>>,
+    reserved: Mutex<Vec<Reservation>>,
+    released: Condvar,
+}
+
+impl Jobs {
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.running.lock().unwrap().values().map(|j| j.info.clone()).collect()
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.running.lock().unwrap().is_empty()
+    }
+
+    pub fn cancel(&self, id: u64) -> Result<(), String> {
+        match self.running.lock().unwrap().get(&id) {
+            Some(job) if job.info.cancellable => {
+                job.cancel.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            Some(_) => Err("This job can't be cancelled".into()),
+            None => Err("No such job".into()),
+        }
+    }
+}
+
+/// A registered job; dropping it marks the job as finished.
+pub struct JobGuard {
+    app: AppHandle,
+    id: u64,
+    cancel: Arc<AtomicBool>,
+    roots: Mutex<Vec<WatchedRoot>>,
+    last_check: Mutex<Instant>,
+    // the job's paths as seen by other processes, e.g. `lfm serve`
+    shared: Option<reservations::Reservation>,
+}
+
+/// A fresh id, unique among jobs and the operations that tag their events
+/// with one (see `events::ProgressEvents`).
+pub fn new_id(app: &AppHandle) -> u64 {
+    app.state::<Jobs>().next_id.fetch_add(1, Ordering::Relaxed)
+}
+
+pub fn start(app: &AppHandle, label: impl Into<String>, cancellable: bool) -> JobGuard {
+    let jobs = app.state::<Jobs>();
+    let id = new_id(app);
+    let cancel = Arc::new(AtomicBool::new(false));
+    let info = JobInfo {
+        id,
+        label: label.into(),
+        done: 0,
+        total: 0,
+        cancellable,
+        suspended: false,
+    };
+    let mut running = jobs.running.lock().unwrap();
+    running.insert(
+        id,
+        Job {
+            info,
+            cancel: cancel.clone(),
+        },
+    );
+    if running.len() == 1 {
+        *jobs.awake.lock().unwrap() = power::inhibit("Copying or hashing files");
+    }
+    drop(running);
+    changed(app);
+    JobGuard {
+        app: app.clone(),
+        id,
+        cancel,
+        roots: Mutex::new(Vec::new()),
+        last_check: Mutex::new(Instant::now()),
+        shared: None,
+    }
+}
+
+/// The reservation overlapping any of `paths`, as (job label, its path),
+/// whether held by a job here or in another process.
+fn conflict(reserved: &[Reservation], paths: &[PathBuf]) -> Option<(String, PathBuf)> {
+    reserved
+        .iter()
+        .find(|r| paths.iter().any(|p| p.starts_with(&r.path) || r.path.starts_with(p)))
+        .map(|r| (r.label.clone(), r.path.clone()))
+        .or_else(|| reservations::conflict(paths))
+}
+
+fn report_contention(app: &AppHandle, label: &str, held_by: String, path: &Path, waiting: bool) {
+    tracing::warn!(label, held_by, path = %path.display(), waiting, "path in use by a job");
+    let event = Contention {
+        label: label.to_string(),
+        path: path.display().to_string(),
+        held_by,
+        waiting,
+    };
+    let _ = app.emit("jobs://contention", event);
+}
+
+/// `start`, for a job that writes to `paths`. Jobs writing to overlapping
+/// paths run one after the other: this waits until earlier ones are done
+/// (or the job is cancelled) before reserving the paths.
+pub fn start_reserving(app: &AppHandle, label: impl Into<String>, cancellable: bool, paths: &[&Path]) -> JobGuard {
+    let label = label.into();
+    let mut guard = start(app, label.clone(), cancellable);
+    let paths: Vec<PathBuf> = paths.iter().map(|p| reservations::canonical(p)).collect();
+    let jobs = app.state::<Jobs>();
+    let mut reserved = jobs.reserved.lock().unwrap();
+    let mut reported = false;
+    while let Some((held_by, path)) = conflict(&reserved, &paths) {
+        if guard.cancel.load(Ordering::Relaxed) {
+            return guard;
+        }
+        if !reported {
+            report_contention(app, &label, held_by, &path, true);
+            reported = true;
+        }
+        reserved = jobs.released.wait_timeout(reserved, WAIT_POLL).unwrap().0;
+    }
+    // Without a shared reservation the job still runs; only other
+    // processes can't see it
+    guard.shared = reservations::reserve(&label, &paths).ok();
+    reserved.extend(paths.into_iter().map(|path| Reservation {
+        job: guard.id,
+        label: label.clone(),
+        path,
+    }));
+    guard
+}
+
+/// Refuse a quick file operation on `relative_paths` of `root` while a job
+/// is writing there.
+pub fn check_free(app: &AppHandle, label: &str, root: &str, relative_paths: &[&str]) -> Result<(), String> {
+    let paths: Vec<PathBuf> = relative_paths
+        .iter()
+        .map(|rel| reservations::canonical(&Path::new(root).join(normalize_input_path(rel.trim_start_matches('/')))))
+        .collect();
+    let jobs = app.state::<Jobs>();
+    let reserved = jobs.reserved.lock().unwrap();
+    match conflict(&reserved, &paths) {
+        Some((held_by, path)) => {
+            drop(reserved);
+            let message = i18n::localize(format!(
+                "{} is in use by {held_by}; try again once it has finished",
+                path.display()
+            ));
+            report_contention(app, label, held_by, &path, false);
+            Err(message)
+        }
+        None => Ok(()),
+    }
+}
+
+fn changed(app: &AppHandle) {
+    tray::refresh(app);
+    let _ = app.emit("jobs://changed", app.state::<Jobs>().list());
+}
+
+fn percent(done: u64, total: u64) -> u64 {
+    (done * 100).checked_div(total).unwrap_or(0)
+}
+
+impl JobGuard {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn cancel_flag(&self) -> &AtomicBool {
+        &self.cancel
+    }
+
+    /// Watch the drives holding `roots` while the job runs; see `progress`.
+    pub fn depends_on(&self, roots: &[&Path]) {
+        let mut watched = self.roots.lock().unwrap();
+        for root in roots {
+            if let Some(volume) = volume_of(root) {
+                watched.push(WatchedRoot {
+                    path: root.to_path_buf(),
+                    volume,
+                });
+            }
+        }
+    }
+
+    /// A watched root whose drive is gone.
+    pub fn lost_root(&self) -> Option<PathBuf> {
+        self.roots
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.is_lost())
+            .map(|r| r.path.clone())
+    }
+
+    /// `error`, or a clear message when it came from the drive going away.
+    pub fn explain(&self, error: String) -> String {
+        match self.lost_root() {
+            Some(root) => {
+                self.emit_root("root://lost", &root);
+                i18n::localize(format!("The drive holding {} was disconnected", root.display()))
+            }
+            None => error,
+        }
+    }
+
+    fn emit_root(&self, event: &str, root: &Path) {
+        let label = match self.app.state::<Jobs>().running.lock().unwrap().get(&self.id) {
+            Some(job) => job.info.label.clone(),
+            None => String::new(),
+        };
+        tracing::warn!(event, root = %root.display(), job = label, "drive availability changed");
+        let payload = RootEvent {
+            root: root.display().to_string(),
+            job: label,
+        };
+        let _ = self.app.emit(event, payload);
+    }
+
+    fn set_suspended(&self, suspended: bool) {
+        if let Some(job) = self.app.state::<Jobs>().running.lock().unwrap().get_mut(&self.id) {
+            job.info.suspended = suspended;
+        }
+        changed(&self.app);
+    }
+
+    /// Hold the job while one of its drives is unplugged: it shows as
+    /// suspended and `root://lost` is emitted; once the drive is back and its
+    /// folder can be listed again, `root://restored` is emitted and the job
+    /// carries on where it was.
+    fn hold_while_lost(&self) {
+        {
+            let mut last = self.last_check.lock().unwrap();
+            if last.elapsed() < ROOT_CHECK_INTERVAL {
+                return;
+            }
+            *last = Instant::now();
+        }
+        let Some(root) = self.lost_root() else {
+            return;
+        };
+        self.set_suspended(true);
+        self.emit_root("root://lost", &root);
+        while self.lost_root().is_some() && !self.cancel.load(Ordering::Relaxed) {
+            thread::sleep(ROOT_CHECK_INTERVAL);
+        }
+        self.set_suspended(false);
+        if !self.cancel.load(Ordering::Relaxed) {
+            self.emit_root("root://restored", &root);
+        }
+    }
+
+    /// Record progress. Called between files, so this is also where the job
+    /// waits for an unplugged drive.
+    pub fn progress(&self, done: u64, total: u64) {
+        self.hold_while_lost();
+        let jobs = self.app.state::<Jobs>();
+        let moved = match jobs.running.lock().unwrap().get_mut(&self.id) {
+            Some(job) => {
+                let before = percent(job.info.done, job.info.total);
+                job.info.done = done;
+                job.info.total = total;
+                before != percent(done, total)
+            }
+            None => false,
+        };
+        // The tray menu only shows whole percents
+        if moved {
+            tray::refresh(&self.app);
+        }
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        let jobs = self.app.state::<Jobs>();
+        let mut running = jobs.running.lock().unwrap();
+        running.remove(&self.id);
+        if running.is_empty() {
+            jobs.awake.lock().unwrap().take();
+        }
+        drop(running);
+        jobs.reserved.lock().unwrap().retain(|r| r.job != self.id);
+        jobs.released.notify_all();
+        changed(&self.app);
+    }
+}
+
+#[tauri::command]
+pub fn list_jobs(app: AppHandle) -> Vec<JobInfo> {
+    app.state::<Jobs>().list()
+}
+
+#[tauri::command]
+pub fn cancel_job(app: AppHandle, id: u64) -> Result<(), String> {
+    app.state::<Jobs>().cancel(id)
+}
@@ -1,7 +1,50 @@
-use serde::Serialize;
+use lfm_core::bench::{self, BenchmarkReport};
+use lfm_core::elevate::{self, ElevatedOp};
+use lfm_core::fsops::{self, CompactListing, FileEntry, MountPoint, SelectionSize, ShardedChildren};
+use lfm_core::guards;
+use lfm_core::hashing::HashCache;
+use lfm_core::hooks;
+use lfm_core::i18n;
+use lfm_core::launch::{self, OpenAction};
+use lfm_core::ownership::{self, OwnershipReport};
+use lfm_core::paths::resolve_within;
+use lfm_core::playlists::{self, PlaylistUpdate};
+use lfm_core::quota;
+use lfm_core::sorting::{self, SortKey};
+use lfm_core::stats;
+use serde_json::json;
 use std::env;
-use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
+use std::process::Command;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+
+mod actions;
+mod albums;
+mod backup;
+mod binary;
+mod compare;
+mod dedup;
+mod events;
+mod filetype;
+mod groups;
+mod imaging;
+mod index;
+mod jobs;
+mod listing;
+mod logging;
+mod manifest;
+mod notify;
+mod par2;
+mod recent;
+mod rename;
+mod roots;
+mod settings;
+mod store;
+mod sync;
+mod trash;
+mod tray;
+mod workspace;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -9,275 +52,293 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[derive(Serialize)]
-struct MountPoint {
-    path: String,
-    label: String,
+// The file operations themselves live in lfm-core so the CLI shares them
+
+#[tauri::command]
+fn list_candidate_mounts() -> Result<Vec<MountPoint>, String> {
+    fsops::list_candidate_mounts()
 }
 
-#[derive(Serialize)]
-struct FileEntry {
-    // absolute path
-    path: String,
-    // path relative to the provided root
-    relative_path: String,
-    size: u64,
+/// Documents, Downloads, Pictures, Videos and Desktop, to offer as roots
+/// alongside the mounts.
+#[tauri::command]
+fn list_standard_dirs() -> Vec<MountPoint> {
+    fsops::list_standard_dirs()
 }
 
-fn normalize_input_path(s: &str) -> String {
-    #[cfg(target_os = "windows")]
-    {
-        return s.replace('/', "\\");
-    }
-    #[cfg(not(target_os = "windows"))]
-    {
-        return s.to_string();
-    }
+/// One bucket of a huge folder plus the size of every bucket.
+#[tauri::command(async)]
+fn list_children_sharded(
+    root: String,
+    dir: String,
+    prefix: String,
+    show_hidden: bool,
+) -> Result<ShardedChildren, String> {
+    fsops::list_children_sharded(&root, &dir, &prefix, show_hidden).map_err(i18n::localize)
 }
 
-fn is_hidden(path: &Path) -> bool {
-    // Check if filename starts with dot (Unix-style hidden)
-    if let Some(name) = path.file_name() {
-        if let Some(name_str) = name.to_str() {
-            if name_str.starts_with('.') {
-                return true;
-            }
-        }
-    }
-    
-    // On Windows, check hidden attribute
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::fs::MetadataExt;
-        if let Ok(metadata) = path.metadata() {
-            const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
-            return (metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN) != 0;
-        }
+/// Files under `root`, by path or in the order of the `sort` keys.
+#[tauri::command]
+fn list_files(
+    app: AppHandle,
+    root: &str,
+    show_hidden: bool,
+    sort: Option<Vec<SortKey>>,
+) -> Result<Vec<FileEntry>, String> {
+    if let Ok(root_canon) = lfm_core::index::canonical_root(root) {
+        index::load_sidecar_once(&app, &root_canon);
     }
-    
-    false
+    let mut files = fsops::list_files(root, show_hidden).map_err(i18n::localize)?;
+    sorting::sort_entries(&mut files, &sort.unwrap_or_default());
+    Ok(files)
 }
 
-fn canonical_within(root: &Path, candidate: &Path) -> Result<PathBuf, String> {
-    let root = root
-        .canonicalize()
-        .map_err(|e| format!("Failed to canonicalize root: {e}"))?;
-    let cand = candidate
-        .canonicalize()
-        .map_err(|e| format!("Failed to canonicalize path: {e}"))?;
-    if cand.starts_with(&root) {
-        Ok(cand)
-    } else {
-        Err("Path escapes selected root".to_string())
-    }
+/// Sequential and random read/write test on a temporary file in `path`,
+/// emitting `benchmark://progress` after each of its four phases.
+#[tauri::command(async)]
+fn benchmark_mount(app: AppHandle, path: String, size_mb: Option<u64>) -> Result<BenchmarkReport, String> {
+    let events = events::ProgressEvents::new(&app);
+    bench::benchmark_mount(&path, size_mb, &|done, total| {
+        index::emit_hash_progress(&events, "benchmark://progress", done, total)
+    })
 }
 
-#[tauri::command]
-fn list_candidate_mounts() -> Result<Vec<MountPoint>, String> {
-    let mut mounts: Vec<MountPoint> = Vec::new();
+/// `list_files` in the compact folder-table format, for huge listings.
+#[tauri::command(async)]
+fn list_files_compact(root: String, show_hidden: bool) -> Result<CompactListing, String> {
+    fsops::list_files_compact(&root, show_hidden)
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        let user = env::var("USER").unwrap_or_default();
-        let candidates: [&str; 3] = [
-            "/media",
-            "/run/media", // usually /run/media/$USER/<label>
-            "/mnt",
-        ];
+#[tauri::command]
+fn rename_file(app: AppHandle, root: &str, relative_path: &str, new_name: &str) -> Result<(), String> {
+    jobs::check_free(&app, "rename", root, &[relative_path])?;
+    let details = json!({ "root": root, "relative_path": relative_path, "new_name": new_name });
+    hooks::around(&store::open(&app)?, "rename", details, || {
+        fsops::rename_file(root, relative_path, new_name)
+    })
+    .map_err(i18n::localize)
+}
 
-        for base in candidates.iter() {
-            let base_path = if *base == "/run/media" && !user.is_empty() {
-                Path::new(base).join(&user)
+/// Delete a file, or move it to the drive's trash when the
+/// `trash_retention_days` setting is on. Deletion rules that ask for
+/// confirmation pass with `confirmed`.
+#[tauri::command]
+fn delete_file(app: AppHandle, root: &str, relative_path: &str, confirmed: Option<bool>) -> Result<(), String> {
+    jobs::check_free(&app, "delete", root, &[relative_path])?;
+    let store = store::open(&app)?;
+    let settings = lfm_core::settings::load(&store)?;
+    let deleted = [relative_path.to_string()];
+    guards::check_delete_within(&settings.delete_guards, root, &deleted, confirmed.unwrap_or(false))
+        .map_err(i18n::localize)?;
+    let keep = settings.trash_retention_days.is_some();
+    let details = json!({ "root": root, "relative_path": relative_path, "trash": keep });
+    stats::counting_delete(&store, root, &deleted, keep, || {
+        hooks::around(&store, "delete", details, || {
+            if keep {
+                lfm_core::trash::trash_file(&store, root, relative_path)
             } else {
-                PathBuf::from(base)
-            };
-
-            if base_path.is_dir() {
-                if let Ok(entries) = fs::read_dir(&base_path) {
-                    for e in entries.flatten() {
-                        let p = e.path();
-                        if p.is_dir() {
-                            let label = p
-                                .file_name()
-                                .map(|s| s.to_string_lossy().to_string())
-                                .unwrap_or_else(|| p.display().to_string());
-                            mounts.push(MountPoint {
-                                path: p.display().to_string(),
-                                label,
-                            });
-                        }
-                    }
-                }
+                fsops::delete_file(root, relative_path)
             }
-        }
-    }
+        })
+    })
+    .map_err(i18n::localize)
+}
 
-    #[cfg(not(target_os = "linux"))]
-    {
-        // On other OSes, just return empty list for now.
+/// Move a file; with `update_playlists`, playlists on the drive that
+/// referenced it are rewritten to its new location. Refused when it would
+/// take a limited folder over its limit, unless `override_quota` is set.
+#[tauri::command]
+fn move_file(
+    app: AppHandle,
+    root: &str,
+    from_relative: &str,
+    to_relative_dir: &str,
+    create_dir: bool,
+    update_playlists: Option<bool>,
+    override_quota: Option<bool>,
+) -> Result<PlaylistUpdate, String> {
+    jobs::check_free(&app, "move", root, &[from_relative, to_relative_dir])?;
+    let store = store::open(&app)?;
+    let moved = [from_relative.to_string()];
+    if !override_quota.unwrap_or(false) {
+        let quotas = lfm_core::settings::load(&store)?.folder_quotas;
+        quota::check_move(&quotas, root, &moved, to_relative_dir).map_err(i18n::localize)?;
     }
-
-    #[cfg(target_os = "windows")]
-    {
-        use std::path::Path;
-        // Iterate drive letters A:..Z: and return those that exist
-        for letter in b'A'..=b'Z' {
-            let drive = format!("{}:\\", letter as char);
-            let p = Path::new(&drive);
-            if p.exists() && p.is_dir() {
-                mounts.push(MountPoint {
-                    path: p.display().to_string(),
-                    label: drive.clone(),
-                });
+    let details = json!({ "root": root, "from_relative": from_relative, "to_relative_dir": to_relative_dir });
+    stats::counting_move(&store, root, &moved, || {
+        hooks::around(&store, "move", details, || {
+            if update_playlists.unwrap_or(false) {
+                playlists::move_with_playlists(root, from_relative, to_relative_dir, create_dir)
+            } else {
+                fsops::move_file(root, from_relative, to_relative_dir, create_dir).map(|_| PlaylistUpdate::default())
             }
-        }
-    }
-
-    #[cfg(all(not(target_os = "linux"), not(target_os = "windows")))]
-    {
-        // On other OSes, just return empty list for now.
-    }
+        })
+    })
+    .map_err(i18n::localize)
+}
 
-    Ok(mounts)
+#[tauri::command]
+fn create_symlink(root: &str, target_relative: &str, link_relative: &str) -> Result<(), String> {
+    fsops::create_symlink(root, target_relative, link_relative).map_err(i18n::localize)
 }
 
 #[tauri::command]
-fn list_files(root: &str, show_hidden: bool) -> Result<Vec<FileEntry>, String> {
-    let root_path = PathBuf::from(root);
-    let root_canon = root_path
-        .canonicalize()
-        .map_err(|e| format!("Invalid root: {e}"))?;
+fn create_hardlink(root: &str, target_relative: &str, link_relative: &str) -> Result<(), String> {
+    fsops::create_hardlink(root, target_relative, link_relative).map_err(i18n::localize)
+}
 
-    let mut result: Vec<FileEntry> = Vec::new();
-    let mut stack: Vec<PathBuf> = vec![root_canon.clone()];
+/// Make a folder of a rescued drive editable by the current user: take
+/// ownership where permitted and restore owner read/write.
+#[tauri::command(async)]
+fn take_ownership(root: String, relative_dir: String, recursive: bool) -> Result<OwnershipReport, String> {
+    ownership::take_ownership(&root, &relative_dir, recursive)
+}
 
-    while let Some(dir) = stack.pop() {
-        let read_dir = fs::read_dir(&dir).map_err(|e| format!("Failed to read dir {}: {e}", dir.display()))?;
-        for entry in read_dir.flatten() {
-            let p = entry.path();
-            
-            // Skip hidden files if show_hidden is false
-            if !show_hidden && is_hidden(&p) {
-                continue;
-            }
-            
-            let rel = p.strip_prefix(&root_canon).unwrap_or(&p);
-                if p.is_dir() {
-                    stack.push(p);
-                } else if p.is_file() {
-                    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
-                    // Normalize relative path to use forward slashes for the frontend
-                    let mut rel_str = rel.display().to_string();
-                    if cfg!(windows) {
-                        rel_str = rel_str.replace("\\", "/");
+/// Retry a file operation that failed for lack of permissions through the
+/// OS elevation prompt. Only available with the `allow_elevation` setting;
+/// otherwise it is held to the same checks as the regular command: busy
+/// paths, deletion rules, the trash and folder limits.
+#[tauri::command(async)]
+fn run_elevated(
+    app: AppHandle,
+    root: String,
+    operation: ElevatedOp,
+    confirmed: Option<bool>,
+    override_quota: Option<bool>,
+) -> Result<(), String> {
+    let store = store::open(&app)?;
+    let settings = lfm_core::settings::load(&store)?;
+    if !settings.allow_elevation {
+        return Err(i18n::tr(
+            "Running operations as administrator is turned off in the settings",
+        ));
+    }
+    let details = json!({ "root": root, "operation": operation, "elevated": true });
+    match &operation {
+        ElevatedOp::Delete { relative_path } => {
+            jobs::check_free(&app, "delete", &root, &[relative_path])?;
+            let deleted = [relative_path.clone()];
+            guards::check_delete_within(&settings.delete_guards, &root, &deleted, confirmed.unwrap_or(false))
+                .map_err(i18n::localize)?;
+            let keep = settings.trash_retention_days.is_some();
+            stats::counting_delete(&store, &root, &deleted, keep, || {
+                hooks::around(&store, "delete", details, || {
+                    if keep {
+                        elevate::trash_elevated(&store, &root, relative_path)
+                    } else {
+                        elevate::run_elevated(&root, &operation)
                     }
-                    result.push(FileEntry {
-                        path: p.display().to_string(),
-                        relative_path: rel_str,
-                        size,
-                    });
-                }
+                })
+            })
         }
+        ElevatedOp::Rename { relative_path, .. } => {
+            jobs::check_free(&app, "rename", &root, &[relative_path])?;
+            hooks::around(&store, "rename", details, || elevate::run_elevated(&root, &operation))
+        }
+        ElevatedOp::Move {
+            relative_path,
+            to_relative_dir,
+        } => {
+            jobs::check_free(&app, "move", &root, &[relative_path, to_relative_dir])?;
+            let moved = [relative_path.clone()];
+            if !override_quota.unwrap_or(false) {
+                quota::check_move(&settings.folder_quotas, &root, &moved, to_relative_dir).map_err(i18n::localize)?;
+            }
+            stats::counting_move(&store, &root, &moved, || {
+                hooks::around(&store, "move", details, || elevate::run_elevated(&root, &operation))
+            })
+        }
+        ElevatedOp::CreateFolder { .. } => elevate::run_elevated(&root, &operation),
     }
-    // Sort by relative_path for stable display
-    result.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
-    Ok(result)
+    .map_err(i18n::localize)
 }
 
 #[tauri::command]
-fn rename_file(root: &str, relative_path: &str, new_name: &str) -> Result<(), String> {
-    let root = PathBuf::from(root);
-    let rel_norm = normalize_input_path(relative_path);
-    let abs_path = canonical_within(&root, &root.join(rel_norm))?;
-    if !abs_path.is_file() {
-        return Err("Target is not a file".into());
-    }
-    let parent = abs_path.parent().ok_or("File has no parent directory")?;
-    let new_path = parent.join(new_name);
-    // Ensure destination stays within root
-    let _ = parent; // parent is already within root since abs_path is
-    // For rename, we can't canonicalize non-existent dest; instead, check that parent starts with root
-    let root_canon = PathBuf::from(root).canonicalize().map_err(|e| format!("Invalid root: {e}"))?;
-    if !parent.starts_with(&root_canon) {
-        return Err("Destination escapes root".into());
-    }
-    fs::rename(&abs_path, &new_path).map_err(|e| format!("Rename failed: {e}"))?;
-    Ok(())
+fn create_folder(root: &str, relative_dir: &str) -> Result<(), String> {
+    fsops::create_folder(root, relative_dir).map_err(i18n::localize)
 }
 
-#[tauri::command]
-fn delete_file(root: &str, relative_path: &str) -> Result<(), String> {
-    let root = PathBuf::from(root);
-    let rel_norm = normalize_input_path(relative_path);
-    let abs_path = canonical_within(&root, &root.join(rel_norm))?;
-    if abs_path.is_file() {
-        fs::remove_file(&abs_path).map_err(|e| format!("Delete failed: {e}"))?;
-        Ok(())
-    } else {
-        Err("Only files can be deleted with this action".into())
-    }
+#[tauri::command(async)]
+fn selection_size(app: AppHandle, root: String, relative_paths: Vec<String>) -> Result<SelectionSize, String> {
+    let events = events::ProgressEvents::new(&app);
+    fsops::selection_size(&root, &relative_paths, &|total| {
+        // The command's result carries the final total
+        events.emit("selection-size://progress", total, false);
+    })
 }
 
 #[tauri::command]
-fn move_file(root: &str, from_relative: &str, to_relative_dir: &str, create_dir: bool) -> Result<(), String> {
-    let root = PathBuf::from(root);
-    let root_canon = root.canonicalize().map_err(|e| format!("Invalid root: {e}"))?;
-    
-    let from_norm = normalize_input_path(from_relative);
-    let src_abs = canonical_within(&root, &root.join(from_norm))?;
-    if !src_abs.is_file() {
-        return Err("Source is not a file".into());
+fn open_terminal(root: &str, relative_dir: &str) -> Result<(), String> {
+    let dir = resolve_within(Path::new(root), relative_dir).map_err(i18n::localize)?;
+    if !dir.is_dir() {
+        return Err(i18n::tr("Target is not a directory"));
     }
-    
-    // Handle empty or root-relative paths
-    let to_relative_dir = to_relative_dir.trim();
-    let dest_dir = if to_relative_dir.is_empty() || to_relative_dir == "/" || to_relative_dir == "." {
-        // Move to root directory
-        root_canon.clone()
-    } else {
-        // Remove leading slash if present
-        let clean_path = to_relative_dir.trim_start_matches('/');
-        let clean_norm = normalize_input_path(clean_path);
-        root.join(clean_norm)
-    };
-    
-    // Validate destination is within root
-    let dest_canon = if dest_dir.exists() {
-        canonical_within(&root, &dest_dir)?
-    } else {
-        // For non-existent paths, validate the parent
-        if create_dir {
-            let parent = dest_dir.parent().unwrap_or(&root);
-            if parent.exists() {
-                canonical_within(&root, parent)?;
+
+    #[cfg(target_os = "linux")]
+    {
+        // Respect $TERMINAL first, then fall back to the usual suspects
+        let mut candidates: Vec<String> = Vec::new();
+        if let Ok(t) = env::var("TERMINAL") {
+            if !t.is_empty() {
+                candidates.push(t);
             }
-            fs::create_dir_all(&dest_dir).map_err(|e| format!("Failed to create dir: {e}"))?;
-            dest_dir.canonicalize().map_err(|e| format!("Failed to validate created dir: {e}"))?
-        } else {
-            return Err("Destination directory does not exist".into());
         }
-    };
-    
-    let file_name = src_abs
-        .file_name()
-        .ok_or("Source file has no name")?
-        .to_os_string();
-    let dest_abs = dest_canon.join(file_name);
-    
-    fs::rename(&src_abs, &dest_abs).map_err(|e| format!("Move failed: {e}"))?;
-    Ok(())
+        for t in ["x-terminal-emulator", "gnome-terminal", "konsole", "xfce4-terminal", "kitty", "alacritty", "xterm"] {
+            candidates.push(t.to_string());
+        }
+        for term in candidates {
+            if Command::new(&term).current_dir(&dir).spawn().is_ok() {
+                return Ok(());
+            }
+        }
+        Err(i18n::tr("No terminal emulator found"))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        Command::new("open")
+            .args(["-a", "Terminal"])
+            .arg(&dir)
+            .spawn()
+            .map_err(|e| i18n::localize(format!("Failed to open terminal: {e}")))?;
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // Prefer Windows Terminal, fall back to a plain console
+        if Command::new("wt").arg("-d").arg(&dir).spawn().is_ok() {
+            return Ok(());
+        }
+        Command::new("cmd")
+            .args(["/C", "start", "cmd"])
+            .current_dir(&dir)
+            .spawn()
+            .map_err(|e| i18n::localize(format!("Failed to open terminal: {e}")))?;
+        Ok(())
+    }
+
+    #[cfg(all(not(target_os = "linux"), not(target_os = "macos"), not(target_os = "windows")))]
+    {
+        Err("Opening a terminal is not supported on this platform".into())
+    }
 }
 
+/// Open a file as the `open_rules` setting says: with the system's default
+/// application, or not at all. `Preview` is returned for files the front end
+/// should show in its own preview instead.
 #[tauri::command]
-fn create_folder(root: &str, relative_dir: &str) -> Result<(), String> {
-    let root = PathBuf::from(root);
-    let rel_norm = normalize_input_path(relative_dir);
-    let target = root.join(rel_norm);
-    // Ensure target is within root (can't canonicalize new path before it's created, so validate parent)
-    let parent = target.parent().unwrap_or(&root);
-    let _ = canonical_within(&root, parent)?;
-    fs::create_dir_all(&target).map_err(|e| format!("Create folder failed: {e}"))?;
-    Ok(())
+fn open_file(app: AppHandle, root: &str, relative_path: &str) -> Result<OpenAction, String> {
+    let path = resolve_within(Path::new(root), relative_path)?;
+    let rules = lfm_core::settings::load(&store::open(&app)?)?.open_rules;
+    let action = launch::check_open(&rules, &path).map_err(i18n::localize)?;
+    if action == OpenAction::System {
+        tracing::info!(path = %path.display(), "opening with the system");
+        app.opener()
+            .open_path(path.display().to_string(), None::<&str>)
+            .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    }
+    Ok(action)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -285,15 +346,133 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(HashCache::default())
+        .manage(store::StoreKey::default())
+        .manage(events::EventThrottle::default())
+        .manage(index::LoadedSidecars::default())
+        .manage(jobs::Jobs::default())
+        .setup(|app| {
+            // An encrypted store can't be read yet; `unlock_store` applies its settings
+            let settings = store::open(app.handle()).and_then(|s| lfm_core::settings::load(&s));
+            if let Ok(settings) = &settings {
+                let _ = settings::apply(app.handle(), settings);
+            }
+            let level = settings.ok().and_then(|s| s.log_level);
+            // The app works without a log file, e.g. on a read-only profile
+            match logging::init(app.handle(), level.as_deref()) {
+                Ok(logging) => {
+                    app.manage(logging);
+                }
+                Err(e) => eprintln!("{e}"),
+            }
+            tracing::info!(version = env!("CARGO_PKG_VERSION"), "started");
+            workspace::init(app.handle());
+            tray::create(app.handle())?;
+            trash::spawn_purger(app.handle().clone());
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                // Jobs keep running with the window hidden; the tray brings it back
+                if tray::keep_running(window.app_handle()) {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             list_candidate_mounts,
+            list_standard_dirs,
             list_files,
+            list_files_compact,
+            list_children_sharded,
+            benchmark_mount,
+            listing::estimate_entries,
+            listing::open_listing,
+            listing::listing_page,
+            listing::close_listing,
+            binary::list_files_binary,
+            binary::listing_page_binary,
             rename_file,
             delete_file,
             move_file,
-            create_folder
+            groups::file_group,
+            groups::rename_group,
+            groups::move_group,
+            groups::delete_group,
+            create_folder,
+            create_symlink,
+            create_hardlink,
+            run_elevated,
+            take_ownership,
+            selection_size,
+            open_terminal,
+            open_file,
+            par2::create_par2,
+            par2::verify_par2,
+            par2::repair_par2,
+            index::index_root,
+            index::verify_root,
+            manifest::export_manifest,
+            manifest::verify_manifest,
+            dedup::list_catalogs,
+            dedup::find_duplicates,
+            dedup::accept_duplicate,
+            dedup::unaccept_duplicate,
+            dedup::list_accepted_duplicates,
+            settings::get_settings,
+            settings::update_settings,
+            settings::folders_over_quota,
+            settings::set_locale,
+            settings::usage_stats,
+            logging::set_log_level,
+            logging::get_recent_logs,
+            compare::compare_files,
+            compare::diff_text_files,
+            compare::tree_digest,
+            sync::sync_folders,
+            actions::list_file_actions,
+            actions::run_file_action,
+            imaging::convert_images,
+            imaging::resize_images,
+            imaging::strip_metadata,
+            filetype::find_extension_mismatches,
+            filetype::fix_extensions,
+            rename::transliterate_names,
+            rename::find_sequences,
+            rename::renumber_sequence,
+            albums::suggest_albums,
+            filetype::scan_damaged_media,
+            recent::recent_changes,
+            recent::mark_root_seen,
+            jobs::list_jobs,
+            jobs::cancel_job,
+            store::list_profiles,
+            store::create_profile,
+            store::switch_profile,
+            store::delete_profile,
+            store::store_status,
+            store::unlock_store,
+            store::lock_store,
+            store::enable_encryption,
+            store::disable_encryption,
+            backup::export_metadata,
+            backup::import_metadata,
+            backup::merge_metadata,
+            trash::purge_now,
+            trash::get_pending_purge,
+            workspace::clean_workspace,
+            roots::pick_and_register_root,
+            roots::list_pinned_roots,
+            roots::unpin_root
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_, event| {
+            if let tauri::RunEvent::Exit = event {
+                lfm_core::workspace::remove_session();
+            }
+        });
 }
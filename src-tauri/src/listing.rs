@@ -0,0 +1,26 @@
+use lfm_core::fsops::FileEntry;
+use lfm_core::listing::{self, EntryEstimate, ListingInfo};
+
+/// A quick estimate of the files under `root`, to warn before scanning a
+/// huge one.
+#[tauri::command(async)]
+pub fn estimate_entries(root: String) -> Result<EntryEstimate, String> {
+    listing::estimate_entries(&root)
+}
+
+/// Like `list_files`, but the entries go to a temporary on-disk result set
+/// and are fetched with `listing_page`, keeping memory flat on huge trees.
+#[tauri::command(async)]
+pub fn open_listing(root: String, show_hidden: bool) -> Result<ListingInfo, String> {
+    listing::open_listing(&root, show_hidden)
+}
+
+#[tauri::command(async)]
+pub fn listing_page(id: String, offset: u64, limit: usize) -> Result<Vec<FileEntry>, String> {
+    listing::listing_page(&id, offset, limit)
+}
+
+#[tauri::command]
+pub fn close_listing(id: String) -> Result<(), String> {
+    listing::close_listing(&id)
+}
@@ -0,0 +1,120 @@
+// Structured logs as JSON lines, in a file under the app data directory that
+// is rotated daily, so failures on odd drives can be reported with context.
+use lfm_core::settings;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+use crate::store;
+
+const LOG_PREFIX: &str = "lfm";
+// Days of logs kept
+const KEPT_FILES: usize = 7;
+
+pub struct Logging {
+    dir: PathBuf,
+    level: reload::Handle<LevelFilter, Registry>,
+    // Flushes buffered lines when the app exits
+    _guard: WorkerGuard,
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    if !settings::LOG_LEVELS.contains(&level) {
+        return Err(format!("Unknown log level: {level}"));
+    }
+    level.parse().map_err(|_| format!("Unknown log level: {level}"))
+}
+
+/// Start writing logs at `level` (`info` when None) to `logs/` in the app
+/// data directory.
+pub fn init(app: &AppHandle, level: Option<&str>) -> Result<Logging, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("No app data directory: {e}"))?
+        .join("logs");
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(KEPT_FILES)
+        .build(&dir)
+        .map_err(|e| format!("Failed to open log file: {e}"))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let (filter, handle) = reload::Layer::new(parse_level(level.unwrap_or("info"))?);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().json().with_writer(writer))
+        .try_init()
+        .map_err(|e| format!("Failed to start logging: {e}"))?;
+    Ok(Logging {
+        dir,
+        level: handle,
+        _guard: guard,
+    })
+}
+
+/// Switch the running logger to `level`, `info` when None.
+pub fn apply_level(app: &AppHandle, level: Option<&str>) -> Result<(), String> {
+    let filter = parse_level(level.unwrap_or("info"))?;
+    if let Some(logging) = app.try_state::<Logging>() {
+        logging.level.reload(filter).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Change how much is logged, e.g. `debug` while reproducing a problem.
+/// Remembered in the settings.
+#[tauri::command]
+pub fn set_log_level(app: AppHandle, level: String) -> Result<(), String> {
+    parse_level(&level)?;
+    let store = store::open(&app)?;
+    let mut current = settings::load(&store)?;
+    current.log_level = Some(level);
+    settings::save(&store, &current)?;
+    apply_level(&app, current.log_level.as_deref())
+}
+
+/// The last `n` log records, oldest first. Lines that aren't JSON, e.g. cut
+/// off by a crash, come back as plain strings.
+#[tauri::command(async)]
+pub fn get_recent_logs(app: AppHandle, n: usize) -> Result<Vec<Value>, String> {
+    let Some(logging) = app.try_state::<Logging>() else {
+        return Ok(Vec::new());
+    };
+    let mut files: Vec<PathBuf> = fs::read_dir(&logging.dir)
+        .map_err(|e| format!("Failed to read logs: {e}"))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with(LOG_PREFIX))
+        })
+        .collect();
+    // Dated names sort oldest first
+    files.sort();
+    let mut out: Vec<Value> = Vec::new();
+    for file in files.iter().rev() {
+        if out.len() >= n {
+            break;
+        }
+        let Ok(text) = fs::read_to_string(file) else {
+            continue;
+        };
+        for line in text.lines().rev().filter(|l| !l.trim().is_empty()) {
+            if out.len() >= n {
+                break;
+            }
+            out.push(serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.to_string())));
+        }
+    }
+    out.reverse();
+    Ok(out)
+}
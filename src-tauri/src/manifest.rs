@@ -0,0 +1,68 @@
+use lfm_core::hashing::HashCache;
+use lfm_core::i18n;
+use lfm_core::index;
+use lfm_core::manifest::{self, ManifestCheck, ManifestOptions, ManifestSummary};
+use lfm_core::settings;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use crate::events::ProgressEvents;
+use crate::index::emit_hash_progress;
+use crate::jobs;
+use crate::notify;
+use crate::store;
+
+/// Write a CSV or JSON manifest of `relative_paths` of `root`, or of the
+/// whole tree when empty, optionally with a hash of every file. Emits
+/// `manifest://progress` while hashing.
+#[tauri::command(async)]
+pub fn export_manifest(
+    app: AppHandle,
+    root: String,
+    relative_paths: Vec<String>,
+    destination: String,
+    options: Option<ManifestOptions>,
+) -> Result<ManifestSummary, String> {
+    let threads = settings::load(&store::open(&app)?)?.hash_threads;
+    let cache = app.state::<HashCache>();
+    let job = jobs::start(&app, format!("Writing manifest of {root}"), false);
+    job.depends_on(&[&index::canonical_root(&root)?]);
+    let events = ProgressEvents::for_job(&app, &job);
+    let result = manifest::export_manifest(
+        &cache,
+        &root,
+        &relative_paths,
+        Path::new(&destination),
+        options.unwrap_or_default(),
+        threads,
+        &|done, total| {
+            job.progress(done, total);
+            emit_hash_progress(&events, "manifest://progress", done, total)
+        },
+    )
+    .map_err(|e| job.explain(e));
+    notify::job_finished(&app, "Manifest", &result, |s| {
+        i18n::localize(format!("{} files written to {destination}", s.files))
+    });
+    result
+}
+
+/// Check that `root` received every file listed in a manifest written by
+/// `export_manifest`, intact. Emits `manifest://progress` while hashing.
+#[tauri::command(async)]
+pub fn verify_manifest(app: AppHandle, manifest_path: String, root: String) -> Result<ManifestCheck, String> {
+    let threads = settings::load(&store::open(&app)?)?.hash_threads;
+    let job = jobs::start(&app, format!("Checking {root} against a manifest"), false);
+    job.depends_on(&[&index::canonical_root(&root)?]);
+    let events = ProgressEvents::for_job(&app, &job);
+    let result = manifest::verify_manifest(Path::new(&manifest_path), &root, threads, &|done, total| {
+        job.progress(done, total);
+        emit_hash_progress(&events, "manifest://progress", done, total)
+    })
+    .map_err(|e| job.explain(e));
+    notify::job_finished(&app, "Manifest check", &result, |r| {
+        let damaged = r.missing.len() + r.wrong_size.len() + r.corrupted.len() + r.unreadable.len();
+        i18n::localize(format!("{root}: {} intact, {damaged} missing or damaged", r.intact))
+    });
+    result
+}
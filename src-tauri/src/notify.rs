@@ -0,0 +1,21 @@
+use lfm_core::i18n;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// Tell the user a long job finished, through an OS notification, unless
+/// they are looking at the app anyway.
+pub fn job_finished<T>(app: &AppHandle, job: &str, result: &Result<T, String>, summary: impl FnOnce(&T) -> String) {
+    let focused = app
+        .get_webview_window("main")
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false);
+    if focused {
+        return;
+    }
+    let job = i18n::tr(job);
+    let (title, body) = match result {
+        Ok(value) => (i18n::localize(format!("{job} finished")), summary(value)),
+        Err(e) => (i18n::localize(format!("{job} failed")), i18n::localize(e.clone())),
+    };
+    let _ = app.notification().builder().title(title).body(body).show();
+}
@@ -0,0 +1,124 @@
+// PAR2 recovery data through the external `par2` tool (par2cmdline), which
+// has to be installed and on PATH.
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use lfm_core::paths::resolve_within;
+
+#[derive(Serialize)]
+pub struct Par2Report {
+    // "ok", "repaired", "repairable" or "unrepairable"
+    status: String,
+    output: String,
+}
+
+fn par2_binary() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "par2.exe"
+    } else {
+        "par2"
+    }
+}
+
+fn run_par2(args: &[&std::ffi::OsStr], cwd: &Path) -> Result<(i32, String), String> {
+    let out = Command::new(par2_binary())
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .map_err(|e| format!("Failed to run par2 (is par2cmdline installed?): {e}"))?;
+    let mut text = String::from_utf8_lossy(&out.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&out.stderr));
+    Ok((out.status.code().unwrap_or(-1), text))
+}
+
+fn resolve_par2_file(root: &str, relative_par2: &str) -> Result<PathBuf, String> {
+    let par2 = resolve_within(Path::new(root), relative_par2)?;
+    let is_par2 = par2
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("par2"))
+        .unwrap_or(false);
+    if !par2.is_file() || !is_par2 {
+        return Err("Target is not a .par2 file".into());
+    }
+    Ok(par2)
+}
+
+/// Create `<folder name>.par2` recovery files inside the folder, covering
+/// everything below it. `redundancy` is a percentage (1-100).
+#[tauri::command(async)]
+pub fn create_par2(root: String, relative_dir: String, redundancy: u8) -> Result<Par2Report, String> {
+    if redundancy == 0 || redundancy > 100 {
+        return Err("Redundancy must be between 1 and 100 percent".into());
+    }
+    let dir = resolve_within(Path::new(&root), &relative_dir)?;
+    if !dir.is_dir() {
+        return Err("Target is not a directory".into());
+    }
+    let name = dir
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "recovery".to_string());
+    let par2_file = dir.join(format!("{name}.par2"));
+    if par2_file.exists() {
+        return Err(format!("{} already exists", par2_file.display()));
+    }
+
+    let redundancy_arg = format!("-r{redundancy}");
+    let (code, output) = run_par2(
+        &[
+            "create".as_ref(),
+            "-q".as_ref(),
+            redundancy_arg.as_ref(),
+            "-R".as_ref(),
+            "-B".as_ref(),
+            dir.as_os_str(),
+            par2_file.as_os_str(),
+            dir.as_os_str(),
+        ],
+        &dir,
+    )?;
+    if code != 0 {
+        return Err(format!("par2 create failed ({code}): {output}"));
+    }
+    Ok(Par2Report {
+        status: "ok".into(),
+        output,
+    })
+}
+
+/// Check the files covered by a .par2 set without touching them.
+#[tauri::command(async)]
+pub fn verify_par2(root: String, relative_par2: String) -> Result<Par2Report, String> {
+    let par2 = resolve_par2_file(&root, &relative_par2)?;
+    let cwd = par2.parent().ok_or("File has no parent directory")?;
+    let (code, output) = run_par2(&["verify".as_ref(), "-q".as_ref(), par2.as_os_str()], cwd)?;
+    // par2cmdline exit codes: 0 all good, 1 repair possible, 2 repair impossible
+    let status = match code {
+        0 => "ok",
+        1 => "repairable",
+        2 => "unrepairable",
+        _ => return Err(format!("par2 verify failed ({code}): {output}")),
+    };
+    Ok(Par2Report {
+        status: status.into(),
+        output,
+    })
+}
+
+/// Repair damaged or missing files covered by a .par2 set.
+#[tauri::command(async)]
+pub fn repair_par2(root: String, relative_par2: String) -> Result<Par2Report, String> {
+    let par2 = resolve_par2_file(&root, &relative_par2)?;
+    let cwd = par2.parent().ok_or("File has no parent directory")?;
+    let (code, output) = run_par2(&["repair".as_ref(), "-q".as_ref(), par2.as_os_str()], cwd)?;
+    let status = match code {
+        0 => "repaired",
+        2 => "unrepairable",
+        _ => return Err(format!("par2 repair failed ({code}): {output}")),
+    };
+    Ok(Par2Report {
+        status: status.into(),
+        output,
+    })
+}
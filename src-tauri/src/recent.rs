@@ -0,0 +1,22 @@
+use lfm_core::recent::{self, RecentChanges};
+use tauri::AppHandle;
+
+use crate::store;
+
+/// Files modified since `since` (ms since epoch), or since the last visit
+/// of this root when omitted.
+#[tauri::command(async)]
+pub fn recent_changes(
+    app: AppHandle,
+    root: String,
+    since: Option<i64>,
+    limit: usize,
+) -> Result<RecentChanges, String> {
+    recent::recent_changes(&store::open(&app)?, &root, since, limit)
+}
+
+/// Remember that the user looked at this root, e.g. when closing it.
+#[tauri::command]
+pub fn mark_root_seen(app: AppHandle, root: String) -> Result<(), String> {
+    recent::mark_root_seen(&store::open(&app)?, &root)
+}
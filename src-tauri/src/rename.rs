@@ -0,0 +1,54 @@
+use lfm_core::hooks;
+use lfm_core::renumber::{self, RenumberReport, Sequence};
+use lfm_core::translit::{self, Scheme, TranslitReport};
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::store;
+
+/// Rename the selection to ASCII-only names, transliterating Cyrillic with
+/// the given scheme and dropping accents. `dry_run` only previews.
+#[tauri::command(async)]
+pub fn transliterate_names(
+    app: AppHandle,
+    root: String,
+    relative_paths: Vec<String>,
+    scheme: Option<Scheme>,
+    dry_run: bool,
+) -> Result<TranslitReport, String> {
+    let scheme = scheme.unwrap_or_default();
+    if dry_run {
+        return translit::transliterate_names(&root, &relative_paths, scheme, true);
+    }
+    let details = json!({ "root": root, "relative_paths": relative_paths });
+    hooks::around(&store::open(&app)?, "rename", details, || {
+        translit::transliterate_names(&root, &relative_paths, scheme, false)
+    })
+}
+
+/// Numbered file sequences (IMG_0001.JPG …) directly inside a folder.
+#[tauri::command(async)]
+pub fn find_sequences(root: String, relative_dir: String) -> Result<Vec<Sequence>, String> {
+    renumber::find_sequences(&root, &relative_dir)
+}
+
+/// Renumber a sequence contiguously from `start`, closing gaps or shifting
+/// it out of the way of another one. `dry_run` only previews.
+#[tauri::command(async)]
+pub fn renumber_sequence(
+    app: AppHandle,
+    root: String,
+    relative_dir: String,
+    prefix: String,
+    width: usize,
+    start: Option<u64>,
+    dry_run: bool,
+) -> Result<RenumberReport, String> {
+    if dry_run {
+        return renumber::renumber_sequence(&root, &relative_dir, &prefix, width, start, true);
+    }
+    let details = json!({ "root": root, "relative_dir": relative_dir, "prefix": prefix, "start": start });
+    hooks::around(&store::open(&app)?, "rename", details, || {
+        renumber::renumber_sequence(&root, &relative_dir, &prefix, width, start, false)
+    })
+}
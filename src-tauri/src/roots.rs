@@ -0,0 +1,29 @@
+use lfm_core::i18n;
+use lfm_core::roots::{self, PinnedRoot, RootId};
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::store;
+
+/// Ask for a folder with the system picker, check it can be browsed and
+/// pin it. Returns the pinned root, whose `path` is what the file commands
+/// take as their root, or None when the picker was dismissed.
+#[tauri::command(async)]
+pub fn pick_and_register_root(app: AppHandle) -> Result<Option<PinnedRoot>, String> {
+    let Some(picked) = app.dialog().file().set_title("Choose a folder").blocking_pick_folder() else {
+        return Ok(None);
+    };
+    let path = picked.into_path().map_err(|e| format!("Invalid folder: {e}"))?;
+    let pinned = roots::register_root(&store::open(&app)?, &path).map_err(i18n::localize)?;
+    Ok(Some(pinned))
+}
+
+#[tauri::command]
+pub fn list_pinned_roots(app: AppHandle) -> Result<Vec<PinnedRoot>, String> {
+    roots::list_roots(&store::open(&app)?)
+}
+
+#[tauri::command]
+pub fn unpin_root(app: AppHandle, id: RootId) -> Result<(), String> {
+    roots::unpin_root(&store::open(&app)?, &id)
+}
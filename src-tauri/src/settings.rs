@@ -0,0 +1,59 @@
+use lfm_core::i18n;
+use lfm_core::quota::{self, QuotaStatus};
+use lfm_core::settings::{self, Settings};
+use lfm_core::stats::{self, UsageStats};
+use tauri::{AppHandle, Manager};
+
+use crate::events::EventThrottle;
+use crate::logging;
+use crate::store;
+use crate::tray;
+
+#[tauri::command]
+pub fn get_settings(app: AppHandle) -> Result<Settings, String> {
+    settings::load(&store::open(&app)?)
+}
+
+/// Put `settings` into effect in the running app: event pacing, IO sizes,
+/// log level and locale.
+pub fn apply(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    app.state::<EventThrottle>().set_flush_ms(settings.event_flush_ms);
+    settings::apply_io(settings);
+    logging::apply_level(app, settings.log_level.as_deref())?;
+    i18n::set_locale(settings.locale.as_deref().unwrap_or("en"))?;
+    tray::refresh(app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_settings(app: AppHandle, settings: Settings) -> Result<(), String> {
+    settings::save(&store::open(&app)?, &settings)?;
+    apply(&app, &settings)
+}
+
+/// Language of the messages commands return, e.g. `ru`; remembered in the
+/// settings.
+#[tauri::command]
+pub fn set_locale(app: AppHandle, locale: String) -> Result<(), String> {
+    let store = store::open(&app)?;
+    let mut current = settings::load(&store)?;
+    current.locale = Some(locale);
+    settings::save(&store, &current)?;
+    i18n::set_locale(current.locale.as_deref().unwrap_or("en"))?;
+    // The tray menu is built in the selected language
+    tray::refresh(&app);
+    Ok(())
+}
+
+/// Folders from the `folder_quotas` setting that are over their limit.
+#[tauri::command(async)]
+pub fn folders_over_quota(app: AppHandle) -> Result<Vec<QuotaStatus>, String> {
+    Ok(quota::over_quota(&settings::load(&store::open(&app)?)?.folder_quotas))
+}
+
+/// Files moved and deleted and space reclaimed, all time, this year and per
+/// month. Counted locally only.
+#[tauri::command]
+pub fn usage_stats(app: AppHandle) -> Result<UsageStats, String> {
+    stats::usage_stats(&store::open(&app)?)
+}
@@ -0,0 +1,99 @@
+use lfm_core::encryption::{self, EncryptionStatus, Key};
+use lfm_core::profiles::{self, ProfileInfo};
+use lfm_core::store::Store;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::settings;
+
+/// Key of the active profile's store while it is unlocked.
+#[derive(Default)]
+pub struct StoreKey(Mutex<Option<Key>>);
+
+impl StoreKey {
+    fn get(&self) -> Option<Key> {
+        *self.0.lock().unwrap()
+    }
+
+    fn set(&self, key: Option<Key>) {
+        *self.0.lock().unwrap() = key;
+    }
+}
+
+fn base_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("No app data directory: {e}"))
+}
+
+/// The active profile's metadata store, under the app data directory.
+pub fn open(app: &AppHandle) -> Result<Store, String> {
+    let store = profiles::active_store(&base_dir(app)?)?;
+    Ok(match app.state::<StoreKey>().get() {
+        Some(key) => store.with_key(key),
+        None => store,
+    })
+}
+
+#[tauri::command]
+pub fn list_profiles(app: AppHandle) -> Result<Vec<ProfileInfo>, String> {
+    profiles::list_profiles(&base_dir(&app)?)
+}
+
+#[tauri::command]
+pub fn create_profile(app: AppHandle, name: String) -> Result<(), String> {
+    profiles::create_profile(&base_dir(&app)?, &name)
+}
+
+/// Make `name` the active profile; emits `profile://changed` so the UI can
+/// reload everything it got from the store.
+#[tauri::command]
+pub fn switch_profile(app: AppHandle, name: String) -> Result<(), String> {
+    profiles::switch_profile(&base_dir(&app)?, &name)?;
+    // Each profile has its own passphrase
+    app.state::<StoreKey>().set(None);
+    let _ = app.emit("profile://changed", name);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_profile(app: AppHandle, name: String) -> Result<(), String> {
+    profiles::delete_profile(&base_dir(&app)?, &name)
+}
+
+#[tauri::command]
+pub fn store_status(app: AppHandle) -> Result<EncryptionStatus, String> {
+    Ok(encryption::status(&open(&app)?))
+}
+
+/// Unlock the active profile's store for the rest of the session. Its
+/// settings couldn't be read at startup, so they take effect now.
+#[tauri::command(async)]
+pub fn unlock_store(app: AppHandle, passphrase: String) -> Result<(), String> {
+    let key = encryption::unlock(open(&app)?.dir(), &passphrase)?;
+    app.state::<StoreKey>().set(Some(key));
+    settings::apply(&app, &lfm_core::settings::load(&open(&app)?)?)
+}
+
+/// Forget the key; the store can't be read again until it is unlocked.
+#[tauri::command]
+pub fn lock_store(app: AppHandle) {
+    app.state::<StoreKey>().set(None);
+}
+
+/// Encrypt the active profile's store with a key derived from `passphrase`.
+/// The store stays unlocked for the rest of the session.
+#[tauri::command(async)]
+pub fn enable_encryption(app: AppHandle, passphrase: String) -> Result<(), String> {
+    let key = encryption::enable(&open(&app)?, &passphrase)?;
+    app.state::<StoreKey>().set(Some(key));
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub fn disable_encryption(app: AppHandle, passphrase: String) -> Result<(), String> {
+    encryption::disable(&open(&app)?, &passphrase)?;
+    app.state::<StoreKey>().set(None);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use lfm_core::eta::{self, EtaEstimator};
+use lfm_core::hooks;
+use lfm_core::i18n;
+use lfm_core::quota;
+use lfm_core::settings;
+use lfm_core::sync::{self, SyncOptions, SyncReport};
+use serde::Serialize;
+use serde_json::json;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::AppHandle;
+
+use crate::events::ProgressEvents;
+use crate::jobs;
+use crate::notify;
+use crate::store;
+
+#[derive(Serialize)]
+struct SyncProgress {
+    bytes_done: u64,
+    bytes_total: u64,
+    // smoothed, seeded from earlier transfers to the same destination
+    bytes_per_sec: f64,
+    eta_secs: Option<u64>,
+}
+
+/// Mirror `source` onto `destination`, emitting `sync://progress` in bytes
+/// with the current speed and an ETA. Refused when it would take a limited
+/// folder over its limit, unless `override_quota` is set.
+#[tauri::command(async)]
+pub fn sync_folders(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    options: SyncOptions,
+    override_quota: Option<bool>,
+) -> Result<SyncReport, String> {
+    let store = store::open(&app)?;
+    let settings = settings::load(&store)?;
+    let destination = Path::new(&destination);
+    if !override_quota.unwrap_or(false) {
+        quota::check_sync(&store, &settings, Path::new(&source), destination, options).map_err(i18n::localize)?;
+    }
+    let estimator = Mutex::new(EtaEstimator::new(eta::prior_throughput(&store, destination)?));
+    let started = Instant::now();
+    // Only the destination is written to; reading the source alongside other jobs is fine
+    let job = jobs::start_reserving(&app, format!("Sync to {}", destination.display()), true, &[destination]);
+    job.depends_on(&[Path::new(&source), destination]);
+    let events = ProgressEvents::for_job(&app, &job);
+    let details = json!({ "source": source, "destination": destination, "options": options });
+    let result = hooks::around(&store, "sync", details, || {
+        sync::sync_trees(
+            &store,
+            &settings,
+            Path::new(&source),
+            destination,
+            options,
+            job.cancel_flag(),
+            &|done, total| {
+                job.progress(done, total);
+                let estimate = estimator.lock().unwrap().update(done, total);
+                let progress = SyncProgress {
+                    bytes_done: done,
+                    bytes_total: total,
+                    bytes_per_sec: estimate.bytes_per_sec,
+                    eta_secs: estimate.eta_secs,
+                };
+                events.emit("sync://progress", progress, done == total);
+            },
+        )
+    });
+    notify::job_finished(&app, "Sync", &result, |r| {
+        i18n::localize(format!(
+            "{} copied, {} updated, {} deleted, {} errors",
+            r.copied.len(),
+            r.updated.len(),
+            r.deleted.len(),
+            r.errors.len()
+        ))
+    });
+    let report = result.map_err(|e| i18n::localize(job.explain(e)))?;
+    if !options.dry_run && !report.cancelled {
+        let _ = eta::record_throughput(&store, destination, report.bytes, started.elapsed());
+    }
+    Ok(report)
+}
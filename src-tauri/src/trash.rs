@@ -0,0 +1,44 @@
+use lfm_core::i18n;
+use lfm_core::settings;
+use lfm_core::trash::{self, PendingPurge, PurgeReport};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::store;
+
+// How often expired files are looked for; the first pass runs at startup
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Empty the trash of every plugged-in drive, whatever the retention.
+#[tauri::command(async)]
+pub fn purge_now(app: AppHandle) -> Result<PurgeReport, String> {
+    trash::purge(&store::open(&app)?, None).map_err(i18n::localize)
+}
+
+/// Trashed files with the time each will be purged, and the space that
+/// purging would reclaim.
+#[tauri::command(async)]
+pub fn get_pending_purge(app: AppHandle) -> Result<PendingPurge, String> {
+    let store = store::open(&app)?;
+    let retention = settings::load(&store)?.trash_retention_days;
+    trash::pending_purge(&store, retention)
+}
+
+/// Purge expired files in the background for as long as the app runs,
+/// emitting `trash://purged` when something went.
+pub fn spawn_purger(app: AppHandle) {
+    thread::spawn(move || loop {
+        // A locked store or missing setting just skips this round
+        if let Ok(store) = store::open(&app) {
+            if let Ok(Some(days)) = settings::load(&store).map(|s| s.trash_retention_days) {
+                if let Ok(report) = trash::purge_expired(&store, days) {
+                    if report.purged > 0 {
+                        let _ = app.emit("trash://purged", &report);
+                    }
+                }
+            }
+        }
+        thread::sleep(PURGE_INTERVAL);
+    });
+}
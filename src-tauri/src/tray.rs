@@ -0,0 +1,83 @@
+use lfm_core::i18n;
+use lfm_core::settings;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::jobs::Jobs;
+use crate::store;
+
+const TRAY_ID: &str = "main";
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+    let label = i18n::tr("Open window");
+    menu.append(&MenuItem::with_id(app, "open", label, true, None::<&str>)?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    let jobs = app.state::<Jobs>().list();
+    if jobs.is_empty() {
+        let label = i18n::tr("No active jobs");
+        menu.append(&MenuItem::with_id(app, "idle", label, false, None::<&str>)?)?;
+    }
+    for job in jobs {
+        let status = match (job.done * 100).checked_div(job.total) {
+            _ if job.suspended => i18n::localize(format!("{}: waiting for the drive", job.label)),
+            Some(percent) => format!("{}: {percent}%", job.label),
+            None => job.label.clone(),
+        };
+        menu.append(&MenuItem::with_id(app, format!("job:{}", job.id), status, false, None::<&str>)?)?;
+        if job.cancellable {
+            let label = i18n::localize(format!("Cancel {}", job.label));
+            menu.append(&MenuItem::with_id(app, format!("cancel:{}", job.id), label, true, None::<&str>)?)?;
+        }
+    }
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    let label = i18n::tr("Quit");
+    menu.append(&MenuItem::with_id(app, "quit", label, true, None::<&str>)?)?;
+    Ok(menu)
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        "open" => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        "quit" => app.exit(0),
+        id => {
+            if let Some(job) = id.strip_prefix("cancel:").and_then(|n| n.parse().ok()) {
+                let _ = app.state::<Jobs>().cancel(job);
+            }
+        }
+    }
+}
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("sdmanager")
+        .menu(&build_menu(app)?)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Rebuild the tray menu from the current jobs.
+pub fn refresh(app: &AppHandle) {
+    if let (Some(tray), Ok(menu)) = (app.tray_by_id(TRAY_ID), build_menu(app)) {
+        let _ = tray.set_menu(Some(menu));
+    }
+}
+
+/// Whether closing the window should only hide it: always while jobs are
+/// running, and otherwise when the `close_to_tray` setting is on.
+pub fn keep_running(app: &AppHandle) -> bool {
+    app.state::<Jobs>().is_busy()
+        || store::open(app)
+            .and_then(|s| settings::load(&s))
+            .is_ok_and(|s| s.close_to_tray)
+}
@@ -0,0 +1,21 @@
+use lfm_core::workspace::{self, CleanReport};
+use std::thread;
+use tauri::{AppHandle, Manager};
+
+/// Keep this run's scratch files under the app cache dir, and clear out
+/// what earlier runs left there in the background.
+pub fn init(app: &AppHandle) {
+    if let Ok(cache) = app.path().app_cache_dir() {
+        workspace::set_base(cache.join("workspace"));
+    }
+    thread::spawn(|| {
+        let _ = workspace::clean_workspace();
+    });
+}
+
+/// Remove the scratch files of crashed earlier runs. This run's, and those
+/// of the CLI or other instances still running, are kept.
+#[tauri::command(async)]
+pub fn clean_workspace() -> Result<CleanReport, String> {
+    workspace::clean_workspace()
+}